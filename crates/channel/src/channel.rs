@@ -13,6 +13,7 @@ pub use channel_chat::{
 };
 pub use channel_store::{
     Channel, ChannelEvent, ChannelMembership, ChannelStore, DevServer, RemoteProject,
+    RemoteProjectStatus,
 };
 
 #[cfg(test)]
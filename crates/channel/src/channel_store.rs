@@ -77,6 +77,27 @@ impl From<proto::RemoteProject> for RemoteProject {
     }
 }
 
+/// The health of a remote project, as last reported by its dev server.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum RemoteProjectStatus {
+    #[default]
+    Healthy,
+    Errored(SharedString),
+    Disconnected,
+}
+
+impl From<proto::UpdateDevServerProjectStatus> for RemoteProjectStatus {
+    fn from(message: proto::UpdateDevServerProjectStatus) -> Self {
+        match message.status() {
+            proto::update_dev_server_project_status::Status::Healthy => Self::Healthy,
+            proto::update_dev_server_project_status::Status::Errored => {
+                Self::Errored(message.error.unwrap_or_default().into())
+            }
+            proto::update_dev_server_project_status::Status::Disconnected => Self::Disconnected,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DevServer {
     pub id: DevServerId,
@@ -103,6 +124,7 @@ pub struct ChannelStore {
     channel_states: HashMap<ChannelId, ChannelState>,
     hosted_projects: HashMap<ProjectId, HostedProject>,
     remote_projects: HashMap<RemoteProjectId, RemoteProject>,
+    remote_project_statuses: HashMap<RemoteProjectId, RemoteProjectStatus>,
     dev_servers: HashMap<DevServerId, DevServer>,
 
     outgoing_invites: HashSet<(ChannelId, UserId)>,
@@ -111,7 +133,7 @@ pub struct ChannelStore {
     opened_chats: HashMap<ChannelId, OpenedModelHandle<ChannelChat>>,
     client: Arc<Client>,
     user_store: Model<UserStore>,
-    _rpc_subscriptions: [Subscription; 2],
+    _rpc_subscriptions: [Subscription; 3],
     _watch_connection_status: Task<Option<()>>,
     disconnect_channel_buffers_task: Option<Task<()>>,
     _update_channels: Task<()>,
@@ -233,6 +255,10 @@ impl ChannelStore {
         let rpc_subscriptions = [
             client.add_message_handler(cx.weak_model(), Self::handle_update_channels),
             client.add_message_handler(cx.weak_model(), Self::handle_update_user_channels),
+            client.add_message_handler(
+                cx.weak_model(),
+                Self::handle_update_dev_server_project_status,
+            ),
         ];
 
         let mut connection_status = client.status();
@@ -266,6 +292,7 @@ impl ChannelStore {
             channel_participants: Default::default(),
             hosted_projects: Default::default(),
             remote_projects: Default::default(),
+            remote_project_statuses: Default::default(),
             dev_servers: Default::default(),
             outgoing_invites: Default::default(),
             opened_buffers: Default::default(),
@@ -387,6 +414,13 @@ impl ChannelStore {
         self.remote_projects.get(&id)
     }
 
+    pub fn remote_project_status(&self, id: RemoteProjectId) -> RemoteProjectStatus {
+        self.remote_project_statuses
+            .get(&id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
     pub fn remote_projects_for_id(&self, channel_id: ChannelId) -> Vec<RemoteProject> {
         let mut remote_projects: Vec<RemoteProject> = self
             .channel_states
@@ -918,6 +952,8 @@ impl ChannelStore {
                     dev_server_id: dev_server_id.0,
                     name,
                     path,
+                    priority: None,
+                    settings: None,
                 })
                 .await
         })
@@ -1009,6 +1045,21 @@ impl ChannelStore {
         Ok(())
     }
 
+    async fn handle_update_dev_server_project_status(
+        this: Model<Self>,
+        message: TypedEnvelope<proto::UpdateDevServerProjectStatus>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<()> {
+        this.update(&mut cx, |this, cx| {
+            let remote_project_id = RemoteProjectId(message.payload.remote_project_id);
+            this.remote_project_statuses
+                .insert(remote_project_id, message.payload.into());
+            cx.notify();
+        })?;
+        Ok(())
+    }
+
     async fn handle_update_user_channels(
         this: Model<Self>,
         message: TypedEnvelope<proto::UpdateUserChannels>,
@@ -1333,6 +1384,7 @@ impl ChannelStore {
 
             for remote_project_id in payload.deleted_remote_projects {
                 let remote_project_id = RemoteProjectId(remote_project_id);
+                self.remote_project_statuses.remove(&remote_project_id);
 
                 if let Some(old_project) = self.remote_projects.remove(&remote_project_id) {
                     self.channel_states
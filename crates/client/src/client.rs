@@ -517,6 +517,16 @@ impl Client {
         }
     }
 
+    /// Sets how connections made after this call are kept alive, e.g. to
+    /// ping more often than an aggressive NAT would drop an idle connection.
+    pub fn set_keepalive(&self, keepalive: Keepalive) {
+        self.peer.set_keepalive(keepalive);
+    }
+
+    pub fn keepalive(&self) -> Keepalive {
+        self.peer.keepalive()
+    }
+
     pub fn peer_id(&self) -> Option<PeerId> {
         if let Status::Connected { peer_id, .. } = &*self.status().borrow() {
             Some(*peer_id)
@@ -764,6 +774,14 @@ impl Client {
         self
     }
 
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn dev_server_token(&self) -> Option<DevServerToken> {
+        match &self.state.read().credentials {
+            Some(Credentials::DevServer { token }) => Some(token.clone()),
+            _ => None,
+        }
+    }
+
     #[async_recursion(?Send)]
     pub async fn authenticate_and_connect(
         self: &Arc<Self>,
@@ -850,6 +868,11 @@ impl Client {
                             delete_credentials_from_keychain(cx).await.log_err();
                             self.set_status(Status::SignedOut, cx);
                             self.authenticate_and_connect(false, cx).await
+                        } else if matches!(credentials, Credentials::DevServer { .. }) {
+                            // A dev server can't obtain a new token by itself, so stop
+                            // reconnecting instead of falling back to browser sign-in.
+                            self.set_status(Status::SignedOut, cx);
+                            Err(EstablishConnectionError::Unauthorized)?
                         } else {
                             self.set_status(Status::ConnectionError, cx);
                             Err(EstablishConnectionError::Unauthorized)?
@@ -4,7 +4,7 @@ use futures::{stream::BoxStream, StreamExt};
 use gpui::{BackgroundExecutor, Context, Model, TestAppContext};
 use parking_lot::Mutex;
 use rpc::{
-    proto::{self, GetPrivateUserInfo, GetPrivateUserInfoResponse},
+    proto::{self, GetPrivateUserInfo, GetPrivateUserInfoResponse, GetUsers, UsersResponse},
     ConnectionId, Peer, Receipt, TypedEnvelope,
 };
 use std::sync::Arc;
@@ -177,6 +177,41 @@ impl FakeServer {
                 continue;
             }
 
+            // Shared projects keep the server up to date with these requests,
+            // which tests rarely care about.
+            if message.is::<TypedEnvelope<proto::UpdateProject>>() {
+                self.respond(
+                    message
+                        .downcast::<TypedEnvelope<proto::UpdateProject>>()
+                        .unwrap()
+                        .receipt(),
+                    proto::Ack {},
+                );
+                continue;
+            }
+
+            if message.is::<TypedEnvelope<proto::UpdateWorktree>>() {
+                self.respond(
+                    message
+                        .downcast::<TypedEnvelope<proto::UpdateWorktree>>()
+                        .unwrap()
+                        .receipt(),
+                    proto::Ack {},
+                );
+                continue;
+            }
+
+            if message.is::<TypedEnvelope<GetUsers>>() {
+                self.respond(
+                    message
+                        .downcast::<TypedEnvelope<GetUsers>>()
+                        .unwrap()
+                        .receipt(),
+                    UsersResponse { users: Vec::new() },
+                );
+                continue;
+            }
+
             panic!(
                 "fake server received unexpected message type: {:?}",
                 type_name
@@ -188,6 +223,14 @@ impl FakeServer {
         self.peer.respond(receipt, response).unwrap()
     }
 
+    pub fn respond_with_error<T: proto::RequestMessage>(
+        &self,
+        receipt: Receipt<T>,
+        error: proto::Error,
+    ) {
+        self.peer.respond_with_error(receipt, error).unwrap()
+    }
+
     fn connection_id(&self) -> ConnectionId {
         self.state.lock().connection_id.expect("not connected")
     }
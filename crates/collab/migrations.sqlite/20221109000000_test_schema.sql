@@ -418,7 +418,9 @@ CREATE TABLE remote_projects (
     channel_id INTEGER NOT NULL REFERENCES channels(id),
     dev_server_id INTEGER NOT NULL REFERENCES dev_servers(id),
     name TEXT NOT NULL,
-    path TEXT NOT NULL
+    path TEXT NOT NULL,
+    priority INTEGER,
+    settings TEXT
 );
 
 ALTER TABLE hosted_projects ADD COLUMN remote_project_id INTEGER REFERENCES remote_projects(id);
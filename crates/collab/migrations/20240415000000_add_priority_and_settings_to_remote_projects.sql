@@ -0,0 +1,2 @@
+ALTER TABLE remote_projects ADD COLUMN priority INTEGER;
+ALTER TABLE remote_projects ADD COLUMN settings TEXT;
@@ -685,6 +685,7 @@ pub struct RejoinedRoom {
 
 pub struct ResharedProject {
     pub id: ProjectId,
+    pub remote_project_id: Option<RemoteProjectId>,
     pub old_connection_id: ConnectionId,
     pub collaborators: Vec<ProjectCollaborator>,
     pub worktrees: Vec<proto::WorktreeMetadata>,
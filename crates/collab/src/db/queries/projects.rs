@@ -176,6 +176,49 @@ impl Database {
         project_id: ProjectId,
         worktrees: &[proto::WorktreeMetadata],
         tx: &DatabaseTransaction,
+    ) -> Result<()> {
+        self.upsert_project_worktrees(project_id, worktrees, tx)
+            .await?;
+
+        worktree::Entity::delete_many()
+            .filter(worktree::Column::ProjectId.eq(project_id).and(
+                worktree::Column::Id.is_not_in(worktrees.iter().map(|worktree| worktree.id as i64)),
+            ))
+            .exec(tx)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Applies changes to a project's worktrees, leaving the worktrees that
+    /// weren't mentioned as they are.
+    pub(in crate::db) async fn update_changed_project_worktrees(
+        &self,
+        project_id: ProjectId,
+        updated_worktrees: &[proto::WorktreeMetadata],
+        removed_worktree_ids: &[u64],
+        tx: &DatabaseTransaction,
+    ) -> Result<()> {
+        self.upsert_project_worktrees(project_id, updated_worktrees, tx)
+            .await?;
+
+        if !removed_worktree_ids.is_empty() {
+            worktree::Entity::delete_many()
+                .filter(worktree::Column::ProjectId.eq(project_id).and(
+                    worktree::Column::Id.is_in(removed_worktree_ids.iter().map(|id| *id as i64)),
+                ))
+                .exec(tx)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn upsert_project_worktrees(
+        &self,
+        project_id: ProjectId,
+        worktrees: &[proto::WorktreeMetadata],
+        tx: &DatabaseTransaction,
     ) -> Result<()> {
         if !worktrees.is_empty() {
             worktree::Entity::insert_many(worktrees.iter().map(|worktree| worktree::ActiveModel {
@@ -196,13 +239,6 @@ impl Database {
             .await?;
         }
 
-        worktree::Entity::delete_many()
-            .filter(worktree::Column::ProjectId.eq(project_id).and(
-                worktree::Column::Id.is_not_in(worktrees.iter().map(|worktree| worktree.id as i64)),
-            ))
-            .exec(tx)
-            .await?;
-
         Ok(())
     }
 
@@ -45,6 +45,8 @@ impl Database {
                 name: remote_project.name,
                 dev_server_id: remote_project.dev_server_id.to_proto(),
                 path: remote_project.path,
+                priority: remote_project.priority,
+                settings: remote_project.settings,
             })
             .collect())
     }
@@ -68,6 +70,8 @@ impl Database {
                     name: remote_project.name,
                     dev_server_id: remote_project.dev_server_id.to_proto(),
                     path: remote_project.path,
+                    priority: remote_project.priority,
+                    settings: remote_project.settings,
                 })
                 .collect())
         })
@@ -99,6 +103,8 @@ impl Database {
         dev_server_id: DevServerId,
         name: &str,
         path: &str,
+        priority: Option<i32>,
+        settings: Option<&str>,
         user_id: UserId,
     ) -> crate::Result<(channel::Model, remote_project::Model)> {
         self.transaction(|tx| async move {
@@ -112,6 +118,8 @@ impl Database {
                 channel_id: ActiveValue::Set(channel_id),
                 dev_server_id: ActiveValue::Set(dev_server_id),
                 path: ActiveValue::Set(path.to_string()),
+                priority: ActiveValue::Set(priority),
+                settings: ActiveValue::Set(settings.map(str::to_string)),
             })
             .exec_with_returning(&*tx)
             .await?;
@@ -176,22 +184,37 @@ impl Database {
     pub async fn reshare_remote_projects(
         &self,
         reshared_projects: &Vec<proto::UpdateProject>,
+        reshared_project_changes: &Vec<proto::ResharedProjectChanges>,
         dev_server_id: DevServerId,
         connection: ConnectionId,
     ) -> crate::Result<Vec<ResharedProject>> {
         // todo!() project_transaction? (maybe we can make the lock per-dev-server instead of per-project?)
         self.transaction(|tx| async move {
             let mut ret = Vec::new();
-            for reshared_project in reshared_projects {
-                let project_id = ProjectId::from_proto(reshared_project.project_id);
-                let (project, remote_project) = project::Entity::find_by_id(project_id)
+            let worktree_updates = reshared_projects
+                .iter()
+                .map(|project| (project.project_id, WorktreeUpdate::Full(project)))
+                .chain(
+                    reshared_project_changes
+                        .iter()
+                        .map(|changes| (changes.project_id, WorktreeUpdate::Changes(changes))),
+                );
+            for (project_id, worktree_update) in worktree_updates {
+                // Project ids don't survive the server losing its state, so a
+                // dev server may name projects that no longer exist, or that now
+                // belong to someone else. Those are left out of the response, and
+                // the dev server shares them again.
+                let project_id = ProjectId::from_proto(project_id);
+                let Some((project, Some(remote_project))) = project::Entity::find_by_id(project_id)
                     .find_also_related(remote_project::Entity)
                     .one(&*tx)
                     .await?
-                    .ok_or_else(|| anyhow!("project does not exist"))?;
+                else {
+                    continue;
+                };
 
-                if remote_project.map(|rp| rp.dev_server_id) != Some(dev_server_id) {
-                    return Err(anyhow!("remote project reshared from wrong server"))?;
+                if remote_project.dev_server_id != dev_server_id {
+                    continue;
                 }
 
                 let Ok(old_connection_id) = project.host_connection() else {
@@ -214,11 +237,38 @@ impl Database {
                     .all(&*tx)
                     .await?;
 
-                self.update_project_worktrees(project_id, &reshared_project.worktrees, &tx)
-                    .await?;
+                let worktrees = match worktree_update {
+                    WorktreeUpdate::Full(reshared_project) => {
+                        self.update_project_worktrees(project_id, &reshared_project.worktrees, &tx)
+                            .await?;
+                        reshared_project.worktrees.clone()
+                    }
+                    WorktreeUpdate::Changes(changes) => {
+                        self.update_changed_project_worktrees(
+                            project_id,
+                            &changes.updated_worktrees,
+                            &changes.removed_worktrees,
+                            &tx,
+                        )
+                        .await?;
+                        worktree::Entity::find()
+                            .filter(worktree::Column::ProjectId.eq(project_id))
+                            .all(&*tx)
+                            .await?
+                            .into_iter()
+                            .map(|worktree| proto::WorktreeMetadata {
+                                id: worktree.id as u64,
+                                root_name: worktree.root_name,
+                                visible: worktree.visible,
+                                abs_path: worktree.abs_path,
+                            })
+                            .collect()
+                    }
+                };
 
                 ret.push(super::ResharedProject {
                     id: project_id,
+                    remote_project_id: Some(remote_project.id),
                     old_connection_id,
                     collaborators: collaborators
                         .iter()
@@ -229,7 +279,7 @@ impl Database {
                             is_host: collaborator.is_host,
                         })
                         .collect(),
-                    worktrees: reshared_project.worktrees.clone(),
+                    worktrees,
                 });
             }
             Ok(ret)
@@ -259,3 +309,11 @@ impl Database {
         .await
     }
 }
+
+/// How a dev server describes a reshared project's worktrees.
+enum WorktreeUpdate<'a> {
+    /// Every worktree in the project.
+    Full(&'a proto::UpdateProject),
+    /// The worktrees that changed since the dev server lost its connection.
+    Changes(&'a proto::ResharedProjectChanges),
+}
@@ -543,6 +543,7 @@ impl Database {
 
                 reshared_projects.push(ResharedProject {
                     id: project_id,
+                    remote_project_id: None,
                     old_connection_id,
                     collaborators: collaborators
                         .iter()
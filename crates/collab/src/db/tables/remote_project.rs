@@ -12,6 +12,8 @@ pub struct Model {
     pub dev_server_id: DevServerId,
     pub name: String,
     pub path: String,
+    pub priority: Option<i32>,
+    pub settings: Option<String>,
 }
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -37,6 +39,8 @@ impl Model {
             dev_server_id: self.dev_server_id.to_proto(),
             name: self.name.clone(),
             path: self.path.clone(),
+            priority: self.priority,
+            settings: self.settings.clone(),
         }
     }
 }
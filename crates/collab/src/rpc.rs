@@ -82,6 +82,7 @@ pub const CLEANUP_TIMEOUT: Duration = Duration::from_secs(15);
 const MESSAGE_COUNT_PER_PAGE: usize = 100;
 const MAX_MESSAGE_LEN: usize = 1024;
 const NOTIFICATION_COUNT_PER_PAGE: usize = 50;
+const REMOTE_PROJECT_COUNT_PER_PAGE: usize = 1000;
 
 type MessageHandler =
     Box<dyn Send + Sync + Fn(Box<dyn AnyTypedEnvelope>, Session) -> BoxFuture<'static, ()>>;
@@ -314,6 +315,28 @@ where
     }
 }
 
+fn dev_server_message_handler<M: EnvelopedMessage, InnertRetFut>(
+    handler: impl 'static + Send + Sync + Fn(M, DevServerSession) -> InnertRetFut,
+) -> impl 'static + Send + Sync + Fn(M, Session) -> BoxFuture<'static, Result<()>>
+where
+    InnertRetFut: Send + Future<Output = Result<()>>,
+{
+    let handler = Arc::new(handler);
+    move |message, session| {
+        let handler = handler.clone();
+        Box::pin(async move {
+            if let Some(dev_server_session) = session.for_dev_server() {
+                Ok(handler(message, dev_server_session).await?)
+            } else {
+                Err(Error::Internal(anyhow!(
+                    "must be a dev server to call {}",
+                    M::NAME
+                )))
+            }
+        })
+    }
+}
+
 fn user_message_handler<M: EnvelopedMessage, InnertRetFut>(
     handler: impl 'static + Send + Sync + Fn(M, UserSession) -> InnertRetFut,
 ) -> impl 'static + Send + Sync + Fn(M, Session) -> BoxFuture<'static, Result<()>>
@@ -408,6 +431,7 @@ impl Server {
             .add_request_handler(dev_server_handler(share_remote_project))
             .add_request_handler(dev_server_handler(shutdown_dev_server))
             .add_request_handler(dev_server_handler(reconnect_dev_server))
+            .add_message_handler(dev_server_message_handler(update_dev_server_project_status))
             .add_message_handler(user_message_handler(leave_project))
             .add_request_handler(update_project)
             .add_request_handler(update_worktree)
@@ -1096,8 +1120,9 @@ impl Server {
                     .db
                     .get_remote_projects_for_dev_server(dev_server.id)
                     .await?;
-                self.peer
-                    .send(connection_id, proto::DevServerInstructions { projects })?;
+                for instructions in dev_server_instructions(projects) {
+                    self.peer.send(connection_id, instructions)?;
+                }
             }
         }
 
@@ -1557,6 +1582,7 @@ async fn rejoin_room(
                         .iter()
                         .map(|collaborator| collaborator.to_proto())
                         .collect(),
+                    remote_project_id: None,
                 })
                 .collect(),
             rejoined_projects: rejoined_room
@@ -2022,6 +2048,38 @@ async fn share_remote_project(
     Ok(())
 }
 
+/// Forward a dev server's report on the health of a shared project to the
+/// members of the project's channel.
+async fn update_dev_server_project_status(
+    message: proto::UpdateDevServerProjectStatus,
+    session: DevServerSession,
+) -> Result<()> {
+    let remote_project = session
+        .db()
+        .await
+        .get_remote_project(RemoteProjectId::from_proto(message.remote_project_id))
+        .await?;
+    if remote_project.dev_server_id != session.dev_server_id() {
+        return Err(anyhow!(
+            "remote project {} does not belong to this dev server",
+            message.remote_project_id
+        ))?;
+    }
+
+    for (connection_id, _) in session
+        .connection_pool()
+        .await
+        .channel_connection_ids(remote_project.channel_id)
+    {
+        session
+            .peer
+            .send(connection_id, message.clone())
+            .trace_err();
+    }
+
+    Ok(())
+}
+
 /// Join someone elses shared project.
 async fn join_project(
     request: proto::JoinProject,
@@ -2232,6 +2290,8 @@ async fn create_remote_project(
             DevServerId(request.dev_server_id as i32),
             &request.name,
             &request.path,
+            request.priority,
+            request.settings.as_deref(),
             session.user_id(),
         )
         .await?;
@@ -2256,10 +2316,9 @@ async fn create_remote_project(
     let dev_server_id = remote_project.dev_server_id;
     let dev_server_connection_id = connection_pool.dev_server_connection_id(dev_server_id);
     if let Some(dev_server_connection_id) = dev_server_connection_id {
-        session.peer.send(
-            dev_server_connection_id,
-            proto::DevServerInstructions { projects },
-        )?;
+        for instructions in dev_server_instructions(projects) {
+            session.peer.send(dev_server_connection_id, instructions)?;
+        }
     }
 
     response.send(proto::CreateRemoteProjectResponse {
@@ -2268,6 +2327,34 @@ async fn create_remote_project(
     Ok(())
 }
 
+/// Splits a dev server's projects into as many `DevServerInstructions` pages as needed.
+fn dev_server_instructions(
+    projects: Vec<proto::RemoteProject>,
+) -> Vec<proto::DevServerInstructions> {
+    if projects.len() <= REMOTE_PROJECT_COUNT_PER_PAGE {
+        return vec![proto::DevServerInstructions {
+            projects,
+            page: 0,
+            page_count: 0,
+        }];
+    }
+
+    let pages = projects
+        .chunks(REMOTE_PROJECT_COUNT_PER_PAGE)
+        .map(|projects| projects.to_vec())
+        .collect::<Vec<_>>();
+    let page_count = pages.len() as u32;
+    pages
+        .into_iter()
+        .enumerate()
+        .map(|(page, projects)| proto::DevServerInstructions {
+            projects,
+            page: page as u32,
+            page_count,
+        })
+        .collect()
+}
+
 async fn create_dev_server(
     request: proto::CreateDevServer,
     response: Response<proto::CreateDevServer>,
@@ -2336,10 +2423,15 @@ async fn reconnect_dev_server(
     response: Response<proto::ReconnectDevServer>,
     session: DevServerSession,
 ) -> Result<()> {
+    if let Some(display_name) = &request.display_name {
+        tracing::info!(%display_name, "dev server reconnected");
+    }
+
     let reshared_projects = {
         let db = session.db().await;
         db.reshare_remote_projects(
             &request.reshared_projects,
+            &request.reshared_project_changes,
             session.dev_server_id(),
             session.0.connection_id,
         )
@@ -2390,8 +2482,12 @@ async fn reconnect_dev_server(
                     .iter()
                     .map(|collaborator| collaborator.to_proto())
                     .collect(),
+                remote_project_id: project
+                    .remote_project_id
+                    .map(|remote_project_id| remote_project_id.to_proto()),
             })
             .collect(),
+        supports_reshared_project_changes: true,
     })?;
 
     Ok(())
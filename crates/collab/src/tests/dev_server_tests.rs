@@ -3,8 +3,10 @@ use std::path::Path;
 use editor::Editor;
 use fs::Fs;
 use gpui::VisualTestContext;
-use rpc::proto::DevServerStatus;
+use project::project_settings::{GitGutterSetting, ProjectSettings};
+use rpc::proto::{self, DevServerStatus};
 use serde_json::json;
+use settings::{Settings, SettingsLocation};
 
 use crate::tests::TestServer;
 
@@ -108,3 +110,69 @@ async fn test_dev_server(cx: &mut gpui::TestAppContext, cx2: &mut gpui::TestAppC
         .unwrap();
     assert_eq!(content, "wow!remote\nremote\nremote\n");
 }
+
+#[gpui::test]
+async fn test_remote_project_priority_and_settings(
+    cx: &mut gpui::TestAppContext,
+    cx2: &mut gpui::TestAppContext,
+) {
+    let (server, client) = TestServer::start1(cx).await;
+
+    let channel_id = server
+        .make_channel("test", None, (&client, cx), &mut [])
+        .await;
+
+    let resp = client
+        .channel_store()
+        .update(cx, |store, cx| {
+            store.create_dev_server(channel_id, "server-1".to_string(), cx)
+        })
+        .await
+        .unwrap();
+
+    let dev_server = server.create_dev_server(resp.access_token, cx2).await;
+    dev_server
+        .fs()
+        .insert_tree("/remote", json!({ "1.txt": "remote" }))
+        .await;
+    cx.executor().run_until_parked();
+
+    let response = client
+        .client()
+        .request(proto::CreateRemoteProject {
+            channel_id: channel_id.0,
+            dev_server_id: resp.dev_server_id,
+            name: "project-1".to_string(),
+            path: "/remote".to_string(),
+            priority: Some(5),
+            settings: Some(r#"{ "git": { "git_gutter": "hide" } }"#.to_string()),
+        })
+        .await
+        .unwrap();
+    let remote_project = response.remote_project.unwrap();
+    assert_eq!(remote_project.priority, Some(5));
+    assert_eq!(
+        remote_project.settings.as_deref(),
+        Some(r#"{ "git": { "git_gutter": "hide" } }"#)
+    );
+
+    cx.executor().run_until_parked();
+
+    // The dev server receives the priority and settings with its instructions.
+    cx2.update(|cx| {
+        let manifest = headless::DevServer::global(cx).read(cx).share_manifest(cx);
+        assert_eq!(manifest.projects.len(), 1);
+        assert_eq!(manifest.projects[0].priority, Some(5));
+
+        let git_gutter = ProjectSettings::get(
+            Some(SettingsLocation {
+                worktree_id: manifest.projects[0].worktrees[0].id as usize,
+                path: Path::new("1.txt"),
+            }),
+            cx,
+        )
+        .git
+        .git_gutter;
+        assert!(matches!(git_gutter, Some(GitGutterSetting::Hide)));
+    });
+}
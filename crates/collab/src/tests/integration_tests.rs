@@ -1798,6 +1798,94 @@ async fn test_project_reconnect(
     buffer_b1.read_with(cx_b, |buffer, _| assert_eq!(buffer.text(), "WXaYZ"));
 }
 
+#[gpui::test(iterations = 10)]
+async fn test_worktrees_changed_while_host_disconnected(
+    executor: BackgroundExecutor,
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+) {
+    let mut server = TestServer::start(executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    server
+        .create_room(&mut [(&client_a, cx_a), (&client_b, cx_b)])
+        .await;
+
+    client_a
+        .fs()
+        .insert_tree(
+            "/root",
+            json!({
+                "dir1": { "a.txt": "a" },
+                "dir2": { "b.txt": "b" },
+                "dir3": { "c.txt": "c" },
+            }),
+        )
+        .await;
+
+    let active_call_a = cx_a.read(ActiveCall::global);
+    let (project_a, worktree1_id) = client_a.build_local_project("/root/dir1", cx_a).await;
+    let (worktree_a2, _) = project_a
+        .update(cx_a, |p, cx| {
+            p.find_or_create_local_worktree("/root/dir2", true, cx)
+        })
+        .await
+        .unwrap();
+    let worktree2_id = worktree_a2.read_with(cx_a, |tree, _| tree.id());
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| call.share_project(project_a.clone(), cx))
+        .await
+        .unwrap();
+    let project_b = client_b.build_remote_project(project_id, cx_b).await;
+    executor.run_until_parked();
+
+    // Drop client A's connection.
+    server.forbid_connections();
+    server.disconnect_client(client_a.peer_id().unwrap());
+    executor.advance_clock(RECEIVE_TIMEOUT);
+
+    // While client A is disconnected, add a worktree and remove another.
+    project_a.update(cx_a, |project, cx| {
+        project.remove_worktree(worktree2_id, cx)
+    });
+    let (worktree_a3, _) = project_a
+        .update(cx_a, |p, cx| {
+            p.find_or_create_local_worktree("/root/dir3", true, cx)
+        })
+        .await
+        .unwrap();
+    worktree_a3
+        .read_with(cx_a, |tree, _| tree.as_local().unwrap().scan_complete())
+        .await;
+    let worktree3_id = worktree_a3.read_with(cx_a, |tree, _| tree.id());
+    executor.run_until_parked();
+
+    // Client A reconnects and rejoins the room, resharing the project.
+    server.allow_connections();
+    client_a
+        .authenticate_and_connect(false, &cx_a.to_async())
+        .await
+        .unwrap();
+    executor.run_until_parked();
+
+    project_b.read_with(cx_b, |project, cx| {
+        assert!(!project.is_disconnected());
+        assert!(project.worktree_for_id(worktree1_id, cx).is_some());
+        assert!(project.worktree_for_id(worktree2_id, cx).is_none());
+        assert_eq!(
+            project
+                .worktree_for_id(worktree3_id, cx)
+                .unwrap()
+                .read(cx)
+                .snapshot()
+                .paths()
+                .map(|p| p.to_str().unwrap())
+                .collect::<Vec<_>>(),
+            vec!["c.txt"]
+        );
+    });
+}
+
 #[gpui::test(iterations = 10)]
 async fn test_active_call_events(
     executor: BackgroundExecutor,
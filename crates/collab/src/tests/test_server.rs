@@ -39,6 +39,7 @@ use std::{
         atomic::{AtomicBool, AtomicUsize, Ordering::SeqCst},
         Arc,
     },
+    time::Duration,
 };
 use util::http::FakeHttpClient;
 use workspace::{Workspace, WorkspaceId, WorkspaceStore};
@@ -321,7 +322,7 @@ impl TestServer {
 
         let clock = Arc::new(FakeSystemClock::default());
         let http = FakeHttpClient::with_404_response();
-        let mut client = cx.update(|cx| Client::new(clock, http.clone(), cx));
+        let mut client = cx.update(|cx| Client::new(clock.clone(), http.clone(), cx));
         let server = self.server.clone();
         let db = self.app_state.db.clone();
         let connection_killers = self.connection_killers.clone();
@@ -413,6 +414,13 @@ impl TestServer {
                     user_store: app_state.user_store.clone(),
                     fs: fs.clone(),
                     node_runtime: app_state.node_runtime.clone(),
+                    clock,
+                    access_log: None,
+                    project_factory: None,
+                    options: headless::DevServerOptions {
+                        instruction_debounce: Some(Duration::ZERO),
+                        ..Default::default()
+                    },
                 },
                 cx,
             );
@@ -427,6 +427,7 @@ impl Copilot {
                     Arc::new(Mutex::new(None)),
                     new_server_id,
                     binary,
+                    &[],
                     Path::new("/"),
                     None,
                     cx.clone(),
@@ -10045,7 +10045,7 @@ impl EditorSnapshot {
 
         let show_git_gutter = matches!(
             ProjectSettings::get_global(cx).git.git_gutter,
-            Some(GitGutterSetting::TrackedFiles)
+            Some(GitGutterSetting::TrackedFiles | GitGutterSetting::All)
         );
         let gutter_settings = EditorSettings::get_global(cx).gutter;
 
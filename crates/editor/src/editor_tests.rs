@@ -18,7 +18,7 @@ use language::{
 };
 use parking_lot::Mutex;
 use project::project_settings::{LspSettings, ProjectSettings};
-use project::FakeFs;
+use project::{FakeFs, LSP_SETTINGS_RESTART_DEBOUNCE};
 use serde_json::{self, json};
 use std::sync::atomic;
 use std::sync::atomic::AtomicUsize;
@@ -8172,6 +8172,7 @@ async fn test_language_server_restart_due_to_settings_change(cx: &mut gpui::Test
             },
         );
     });
+    cx.executor().advance_clock(LSP_SETTINGS_RESTART_DEBOUNCE);
     cx.executor().run_until_parked();
     assert_eq!(
         server_restarts.load(atomic::Ordering::Acquire),
@@ -8183,14 +8184,14 @@ async fn test_language_server_restart_due_to_settings_change(cx: &mut gpui::Test
         project_settings.lsp.insert(
             "Some other server name".into(),
             LspSettings {
-                binary: None,
-                settings: None,
                 initialization_options: Some(json!({
                     "some other init value": false
                 })),
+                ..Default::default()
             },
         );
     });
+    cx.executor().advance_clock(LSP_SETTINGS_RESTART_DEBOUNCE);
     cx.executor().run_until_parked();
     assert_eq!(
         server_restarts.load(atomic::Ordering::Acquire),
@@ -8202,14 +8203,14 @@ async fn test_language_server_restart_due_to_settings_change(cx: &mut gpui::Test
         project_settings.lsp.insert(
             language_server_name.into(),
             LspSettings {
-                binary: None,
-                settings: None,
                 initialization_options: Some(json!({
                     "anotherInitValue": false
                 })),
+                ..Default::default()
             },
         );
     });
+    cx.executor().advance_clock(LSP_SETTINGS_RESTART_DEBOUNCE);
     cx.executor().run_until_parked();
     assert_eq!(
         server_restarts.load(atomic::Ordering::Acquire),
@@ -8221,14 +8222,14 @@ async fn test_language_server_restart_due_to_settings_change(cx: &mut gpui::Test
         project_settings.lsp.insert(
             language_server_name.into(),
             LspSettings {
-                binary: None,
-                settings: None,
                 initialization_options: Some(json!({
                     "anotherInitValue": false
                 })),
+                ..Default::default()
             },
         );
     });
+    cx.executor().advance_clock(LSP_SETTINGS_RESTART_DEBOUNCE);
     cx.executor().run_until_parked();
     assert_eq!(
         server_restarts.load(atomic::Ordering::Acquire),
@@ -8240,18 +8241,56 @@ async fn test_language_server_restart_due_to_settings_change(cx: &mut gpui::Test
         project_settings.lsp.insert(
             language_server_name.into(),
             LspSettings {
-                binary: None,
-                settings: None,
                 initialization_options: None,
+                ..Default::default()
             },
         );
     });
+    cx.executor().advance_clock(LSP_SETTINGS_RESTART_DEBOUNCE);
     cx.executor().run_until_parked();
     assert_eq!(
         server_restarts.load(atomic::Ordering::Acquire),
         2,
         "Should restart LSP server on another related LSP settings change"
     );
+
+    update_test_project_settings(cx, |project_settings| {
+        project_settings.lsp.insert(
+            language_server_name.into(),
+            LspSettings {
+                settings: Some(json!({ "checkOnSave": false })),
+                ..Default::default()
+            },
+        );
+    });
+    cx.executor().advance_clock(LSP_SETTINGS_RESTART_DEBOUNCE);
+    cx.executor().run_until_parked();
+    assert_eq!(
+        server_restarts.load(atomic::Ordering::Acquire),
+        2,
+        "Should not restart LSP server when only its workspace configuration changes"
+    );
+
+    for init_value in [1, 2, 3] {
+        update_test_project_settings(cx, |project_settings| {
+            project_settings.lsp.insert(
+                language_server_name.into(),
+                LspSettings {
+                    initialization_options: Some(json!({ "burstValue": init_value })),
+                    ..Default::default()
+                },
+            );
+        });
+        cx.executor()
+            .advance_clock(LSP_SETTINGS_RESTART_DEBOUNCE / 2);
+    }
+    cx.executor().advance_clock(LSP_SETTINGS_RESTART_DEBOUNCE);
+    cx.executor().run_until_parked();
+    assert_eq!(
+        server_restarts.load(atomic::Ordering::Acquire),
+        3,
+        "Should restart LSP server once after a burst of related LSP settings changes"
+    );
 }
 
 #[gpui::test]
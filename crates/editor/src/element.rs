@@ -4,7 +4,7 @@ use crate::{
         TransformBlock,
     },
     editor_settings::{DoubleClickInMultibuffer, MultiCursorModifier, ShowScrollbar},
-    git::{blame::GitBlame, diff_hunk_to_display, DisplayDiffHunk},
+    git::{blame::GitBlame, diff_hunk_to_display, is_untracked, DisplayDiffHunk},
     hover_popover::{
         self, hover_at, HOVER_POPOVER_GAP, MIN_POPOVER_CHARACTER_WIDTH, MIN_POPOVER_LINE_HEIGHT,
     },
@@ -1075,7 +1075,22 @@ impl EditorElement {
         &self,
         display_rows: Range<u32>,
         snapshot: &EditorSnapshot,
+        cx: &mut ElementContext,
     ) -> Vec<DisplayDiffHunk> {
+        let show_untracked = matches!(
+            ProjectSettings::get_global(cx).git.git_gutter,
+            Some(GitGutterSetting::All)
+        );
+        if show_untracked {
+            let buffer = self.editor.read(cx).buffer().read(cx).as_singleton();
+            if buffer.map_or(false, |buffer| is_untracked(buffer.read(cx), cx)) {
+                let end_row = display_rows.end.min(snapshot.max_point().row() + 1);
+                return vec![DisplayDiffHunk::Untracked {
+                    display_row_range: display_rows.start..end_row,
+                }];
+            }
+        }
+
         let buffer_snapshot = &snapshot.buffer_snapshot;
 
         let buffer_start_row = DisplayPoint::new(display_rows.start, 0)
@@ -2076,7 +2091,7 @@ impl EditorElement {
 
         let show_git_gutter = matches!(
             ProjectSettings::get_global(cx).git.git_gutter,
-            Some(GitGutterSetting::TrackedFiles)
+            Some(GitGutterSetting::TrackedFiles | GitGutterSetting::All)
         );
 
         if show_git_gutter {
@@ -2147,6 +2162,27 @@ impl EditorElement {
                         continue;
                     }
 
+                    DisplayDiffHunk::Untracked { display_row_range } => {
+                        // Outline the rows rather than filling them, to tell
+                        // untracked files apart from added hunks.
+                        let start_y = display_row_range.start as f32 * line_height - scroll_top;
+                        let end_y = display_row_range.end as f32 * line_height - scroll_top;
+
+                        let width = 0.275 * line_height;
+                        let highlight_origin = layout.gutter_hitbox.origin + point(-width, start_y);
+                        let highlight_size = size(width * 2., end_y - start_y);
+                        let highlight_bounds = Bounds::new(highlight_origin, highlight_size);
+                        cx.paint_quad(quad(
+                            highlight_bounds,
+                            Corners::all(0.05 * line_height),
+                            transparent_black(),
+                            Edges::all(px(1.)),
+                            cx.theme().status().created,
+                        ));
+
+                        continue;
+                    }
+
                     DisplayDiffHunk::Unfolded {
                         display_row_range,
                         status,
@@ -2936,6 +2972,10 @@ fn render_blame_entry(
 
     let permalink = blame.read(cx).permalink_for_entry(&blame_entry);
     let commit_message = blame.read(cx).message_for_entry(&blame_entry);
+    let hover_details = ProjectSettings::get_global(cx)
+        .git
+        .hover_details
+        .unwrap_or(true);
 
     h_flex()
         .w_full()
@@ -2969,13 +3009,15 @@ fn render_blame_entry(
                 cx.open_url(url.as_str())
             })
         })
-        .hoverable_tooltip(move |cx| {
-            BlameEntryTooltip::new(
-                sha_color.cursor,
-                commit_message.clone(),
-                blame_entry.clone(),
-                cx,
-            )
+        .when(hover_details, |this| {
+            this.hoverable_tooltip(move |cx| {
+                BlameEntryTooltip::new(
+                    sha_color.cursor,
+                    commit_message.clone(),
+                    blame_entry.clone(),
+                    cx,
+                )
+            })
         })
         .into_any()
 }
@@ -3488,7 +3530,7 @@ impl Element for EditorElement {
                     cx,
                 );
 
-                let display_hunks = self.layout_git_gutters(start_row..end_row, &snapshot);
+                let display_hunks = self.layout_git_gutters(start_row..end_row, &snapshot, cx);
 
                 let blamed_display_rows = self.layout_blame_entries(
                     buffer_rows,
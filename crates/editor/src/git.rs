@@ -3,7 +3,9 @@ pub mod blame;
 use std::ops::Range;
 
 use git::diff::{DiffHunk, DiffHunkStatus};
-use language::Point;
+use gpui::AppContext;
+use language::{Buffer, Point};
+use project::repository::GitFileStatus;
 
 use crate::{
     display_map::{DisplaySnapshot, ToDisplayPoint},
@@ -20,6 +22,11 @@ pub enum DisplayDiffHunk {
         display_row_range: Range<u32>,
         status: DiffHunkStatus,
     },
+
+    /// Rows of a file that git doesn't track, which has no hunks to show.
+    Untracked {
+        display_row_range: Range<u32>,
+    },
 }
 
 impl DisplayDiffHunk {
@@ -28,7 +35,8 @@ impl DisplayDiffHunk {
             &DisplayDiffHunk::Folded { display_row } => display_row,
             DisplayDiffHunk::Unfolded {
                 display_row_range, ..
-            } => display_row_range.start,
+            }
+            | DisplayDiffHunk::Untracked { display_row_range } => display_row_range.start,
         }
     }
 
@@ -38,13 +46,32 @@ impl DisplayDiffHunk {
 
             DisplayDiffHunk::Unfolded {
                 display_row_range, ..
-            } => display_row_range.start..=display_row_range.end,
+            }
+            | DisplayDiffHunk::Untracked { display_row_range } => {
+                display_row_range.start..=display_row_range.end
+            }
         };
 
         range.contains(&display_row)
     }
 }
 
+/// Returns whether the buffer's file is in a git repository but not tracked by
+/// it. Such files have no diff base, so no hunks are computed for them.
+pub fn is_untracked(buffer: &Buffer, cx: &AppContext) -> bool {
+    if buffer.diff_base().is_some() {
+        return false;
+    }
+    let Some(file) = project::File::from_dyn(buffer.file()) else {
+        return false;
+    };
+    let git_status = file
+        .entry_id
+        .and_then(|entry_id| file.worktree.read(cx).entry_for_id(entry_id))
+        .and_then(|entry| entry.git_status);
+    git_status == Some(GitFileStatus::Added)
+}
+
 pub fn diff_hunk_to_display(hunk: DiffHunk<u32>, snapshot: &DisplaySnapshot) -> DisplayDiffHunk {
     let hunk_start_point = Point::new(hunk.associated_range.start, 0);
     let hunk_start_point_sub = Point::new(hunk.associated_range.start.saturating_sub(1), 0);
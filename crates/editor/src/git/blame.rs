@@ -6,7 +6,11 @@ use git::{
 };
 use gpui::{Model, ModelContext, Subscription, Task};
 use language::{Bias, Buffer, BufferSnapshot, Edit};
-use project::{Item, Project};
+use project::{
+    project_settings::{BlameGranularity, ProjectSettings},
+    Item, Project,
+};
+use settings::Settings;
 use smallvec::SmallVec;
 use sum_tree::SumTree;
 use url::Url;
@@ -53,6 +57,7 @@ pub struct GitBlame {
     buffer_snapshot: BufferSnapshot,
     buffer_edits: text::Subscription,
     task: Task<Result<()>>,
+    regenerate_on_change_task: Task<()>,
     generated: bool,
     _refresh_subscription: Subscription,
 }
@@ -82,12 +87,12 @@ impl GitBlame {
                         .any(|(_, entry_id, _)| project_entry_id == Some(*entry_id))
                     {
                         log::debug!("Updated buffers. Regenerating blame data...",);
-                        this.generate(cx);
+                        this.regenerate_on_change(cx);
                     }
                 }
                 project::Event::WorktreeUpdatedGitRepositories => {
                     log::debug!("Status of git repositories updated. Regenerating blame data...",);
-                    this.generate(cx);
+                    this.regenerate_on_change(cx);
                 }
                 _ => {}
             }
@@ -105,6 +110,7 @@ impl GitBlame {
             permalinks: HashMap::default(),
             messages: HashMap::default(),
             task: Task::ready(Ok(())),
+            regenerate_on_change_task: Task::ready(()),
             generated: false,
             _refresh_subscription: refresh_subscription,
         };
@@ -131,11 +137,29 @@ impl GitBlame {
     ) -> impl 'a + Iterator<Item = Option<BlameEntry>> {
         self.sync(cx);
 
+        let granularity = ProjectSettings::get_global(cx)
+            .git
+            .blame_granularity
+            .unwrap_or_default();
+        let mut previous: Option<(u32, Oid)> = None;
         let mut cursor = self.entries.cursor::<u32>();
         rows.into_iter().map(move |row| {
             let row = row?;
             cursor.seek_forward(&row, Bias::Right, &());
-            cursor.item()?.blame.clone()
+            let blame = cursor.item()?.blame.clone();
+            if granularity == BlameGranularity::Hunk {
+                let sha = blame.as_ref().map(|blame| blame.sha);
+                let continues_hunk = previous
+                    .zip(sha)
+                    .map_or(false, |((prev_row, prev_sha), sha)| {
+                        prev_row + 1 == row && prev_sha == sha
+                    });
+                previous = sha.map(|sha| (row, sha));
+                if continues_hunk {
+                    return None;
+                }
+            }
+            blame
         })
     }
 
@@ -250,6 +274,19 @@ impl GitBlame {
         );
     }
 
+    fn regenerate_on_change(&mut self, cx: &mut ModelContext<Self>) {
+        let delay = ProjectSettings::get_global(cx).git.git_update_debounce();
+        if delay.is_zero() {
+            self.generate(cx);
+            return;
+        }
+
+        self.regenerate_on_change_task = cx.spawn(|this, mut cx| async move {
+            cx.background_executor().timer(delay).await;
+            this.update(&mut cx, |this, cx| this.generate(cx)).ok();
+        });
+    }
+
     fn generate(&mut self, cx: &mut ModelContext<Self>) {
         let buffer_edits = self.buffer.update(cx, |buffer, _| buffer.subscribe());
         let snapshot = self.buffer.read(cx).snapshot();
@@ -505,6 +542,86 @@ mod tests {
         });
     }
 
+    #[gpui::test]
+    async fn test_blame_for_rows_with_hunk_granularity(cx: &mut gpui::TestAppContext) {
+        init_test(cx);
+        cx.update(|cx| {
+            cx.update_global(|store: &mut SettingsStore, cx| {
+                store.update_user_settings::<ProjectSettings>(cx, |settings| {
+                    settings.git.blame_granularity = Some(BlameGranularity::Hunk);
+                });
+            });
+        });
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/my-repo",
+            json!({
+                ".git": {},
+                "file.txt": r#"
+                    AAA Line 1
+                    AAA Line 2
+                    AAA Line 3
+                    BBB Line 4
+                    AAA Line 5
+                "#
+                .unindent()
+            }),
+        )
+        .await;
+
+        fs.set_blame_for_repo(
+            Path::new("/my-repo/.git"),
+            vec![(
+                Path::new("file.txt"),
+                Blame {
+                    entries: vec![
+                        blame_entry("1b1b1b", 0..2),
+                        blame_entry("1b1b1b", 2..3),
+                        blame_entry("0d0d0d", 3..4),
+                        blame_entry("1b1b1b", 4..5),
+                    ],
+                    ..Default::default()
+                },
+            )],
+        );
+        let project = Project::test(fs, ["/my-repo".as_ref()], cx).await;
+        let buffer = project
+            .update(cx, |project, cx| {
+                project.open_local_buffer("/my-repo/file.txt", cx)
+            })
+            .await
+            .unwrap();
+
+        let git_blame = cx.new_model(|cx| GitBlame::new(buffer.clone(), project, cx));
+
+        cx.executor().run_until_parked();
+
+        git_blame.update(cx, |blame, cx| {
+            // Consecutive lines from the same commit share one annotation.
+            assert_blame_rows!(
+                blame,
+                (0..5),
+                vec![
+                    Some(blame_entry("1b1b1b", 0..2)),
+                    None,
+                    None,
+                    Some(blame_entry("0d0d0d", 3..4)),
+                    Some(blame_entry("1b1b1b", 4..5)),
+                ],
+                cx
+            );
+            // A run that starts above the visible rows is annotated on the
+            // first visible row.
+            assert_blame_rows!(
+                blame,
+                (1..3),
+                vec![Some(blame_entry("1b1b1b", 0..2)), None],
+                cx
+            );
+        });
+    }
+
     #[gpui::test]
     async fn test_blame_for_rows_with_edits(cx: &mut gpui::TestAppContext) {
         init_test(cx);
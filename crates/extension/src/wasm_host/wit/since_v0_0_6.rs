@@ -226,18 +226,18 @@ impl ExtensionImports for WasmState {
                     }
                     "lsp" => {
                         let settings = key
-                            .and_then(|key| {
-                                ProjectSettings::get_global(cx)
-                                    .lsp
-                                    .get(&Arc::<str>::from(key))
-                            })
-                            .cloned()
+                            .and_then(|key| ProjectSettings::get_global(cx).lsp_settings(key))
                             .unwrap_or_default();
                         Ok(serde_json::to_string(&settings::LspSettings {
-                            binary: settings.binary.map(|binary| settings::BinarySettings {
-                                path: binary.path,
-                                arguments: binary.arguments,
-                            }),
+                            binary: settings
+                                .binary
+                                .map(|binary| -> Result<_> {
+                                    Ok(settings::BinarySettings {
+                                        path: binary.resolved_path()?,
+                                        arguments: binary.resolved_arguments()?,
+                                    })
+                                })
+                                .transpose()?,
                             settings: settings.settings,
                             initialization_options: settings.initialization_options,
                         })?)
@@ -55,6 +55,8 @@ pub trait Fs: Send + Sync {
     async fn open_sync(&self, path: &Path) -> Result<Box<dyn io::Read>>;
     async fn load(&self, path: &Path) -> Result<String>;
     async fn atomic_write(&self, path: PathBuf, text: String) -> Result<()>;
+    /// Appends `text` to the file at `path`, creating the file if it doesn't exist.
+    async fn append(&self, path: &Path, text: String) -> Result<()>;
     async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()>;
     async fn canonicalize(&self, path: &Path) -> Result<PathBuf>;
     async fn is_file(&self, path: &Path) -> bool;
@@ -268,6 +270,17 @@ impl Fs for RealFs {
         Ok(())
     }
 
+    async fn append(&self, path: &Path, text: String) -> Result<()> {
+        let mut file = smol::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await?;
+        file.write_all(text.as_bytes()).await?;
+        file.flush().await?;
+        Ok(())
+    }
+
     async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()> {
         let buffer_size = text.summary().len.min(10 * 1024);
         if let Some(path) = path.parent() {
@@ -511,6 +524,7 @@ struct FakeFsState {
     buffered_events: Vec<PathBuf>,
     metadata_call_count: usize,
     read_dir_call_count: usize,
+    metadata_delays: BTreeMap<PathBuf, Duration>,
 }
 
 #[cfg(any(test, feature = "test-support"))]
@@ -662,6 +676,7 @@ impl FakeFs {
                 events_paused: false,
                 read_dir_call_count: 0,
                 metadata_call_count: 0,
+                metadata_delays: Default::default(),
             }),
         })
     }
@@ -836,6 +851,23 @@ impl FakeFs {
         });
     }
 
+    pub fn set_committed_contents_for_repo(
+        &self,
+        dot_git: &Path,
+        revision: &str,
+        contents: &[(&Path, String)],
+    ) {
+        self.with_git_state(dot_git, true, |state| {
+            state.committed_contents.insert(
+                revision.to_string(),
+                contents
+                    .iter()
+                    .map(|(path, content)| (path.to_path_buf(), content.clone()))
+                    .collect(),
+            );
+        });
+    }
+
     pub fn set_blame_for_repo(&self, dot_git: &Path, blames: Vec<(&Path, git::blame::Blame)>) {
         self.with_git_state(dot_git, true, |state| {
             state.blames.clear();
@@ -953,6 +985,15 @@ impl FakeFs {
         self.state.lock().metadata_call_count
     }
 
+    /// Makes `metadata` calls for the given path take `delay` to complete,
+    /// simulating a slow mount.
+    pub fn set_metadata_delay(&self, path: impl AsRef<Path>, delay: Duration) {
+        self.state
+            .lock()
+            .metadata_delays
+            .insert(normalize_path(path.as_ref()), delay);
+    }
+
     fn simulate_random_delay(&self) -> impl futures::Future<Output = ()> {
         self.executor.simulate_random_delay()
     }
@@ -1280,6 +1321,19 @@ impl Fs for FakeFs {
         Ok(())
     }
 
+    async fn append(&self, path: &Path, text: String) -> Result<()> {
+        self.simulate_random_delay().await;
+        let path = normalize_path(path);
+        let mut content = if self.is_file(&path).await {
+            self.load_internal(&path).await?
+        } else {
+            Vec::new()
+        };
+        content.extend(text.into_bytes());
+        self.write_file_internal(path, content)?;
+        Ok(())
+    }
+
     async fn save(&self, path: &Path, text: &Rope, line_ending: LineEnding) -> Result<()> {
         self.simulate_random_delay().await;
         let path = normalize_path(path);
@@ -1322,6 +1376,10 @@ impl Fs for FakeFs {
     async fn metadata(&self, path: &Path) -> Result<Option<Metadata>> {
         self.simulate_random_delay().await;
         let path = normalize_path(path);
+        let delay = self.state.lock().metadata_delays.get(&path).copied();
+        if let Some(delay) = delay {
+            self.executor.timer(delay).await;
+        }
         let mut state = self.state.lock();
         state.metadata_call_count += 1;
         if let Some((mut entry, _)) = state.try_read_path(&path, false) {
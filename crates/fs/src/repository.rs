@@ -30,6 +30,32 @@ pub trait GitRepository: Send {
     /// Note that for symlink entries, this will return the contents of the symlink, not the target.
     fn load_index_text(&self, relative_file_path: &Path) -> Option<String>;
 
+    /// Loads a file's contents at the given revision, such as a branch name or
+    /// a commit SHA. Returns `None` if the file doesn't exist at that revision,
+    /// and an error if the revision can't be resolved.
+    fn load_committed_text(
+        &self,
+        relative_file_path: &Path,
+        revision: &str,
+    ) -> Result<Option<String>>;
+
+    /// Loads the text that a file's changes are shown against: its contents at
+    /// `revision` if one is given, or its contents in the index otherwise.
+    /// Falls back to the index if `revision` can't be resolved.
+    fn load_diff_base(&self, relative_file_path: &Path, revision: Option<&str>) -> Option<String> {
+        if let Some(revision) = revision {
+            match self.load_committed_text(relative_file_path, revision) {
+                Ok(text) => return text,
+                Err(error) => {
+                    log::warn!(
+                        "failed to load diff base at {revision:?}, using the index: {error:?}"
+                    )
+                }
+            }
+        }
+        self.load_index_text(relative_file_path)
+    }
+
     /// Returns the URL of the remote with the given name.
     fn remote_url(&self, name: &str) -> Option<String>;
     fn branch_name(&self) -> Option<String>;
@@ -113,6 +139,28 @@ impl GitRepository for RealGitRepository {
         None
     }
 
+    fn load_committed_text(
+        &self,
+        relative_file_path: &Path,
+        revision: &str,
+    ) -> Result<Option<String>> {
+        check_path_to_repo_path_errors(relative_file_path)?;
+
+        let tree = self
+            .repository
+            .revparse_single(revision)
+            .and_then(|object| object.peel_to_tree())
+            .with_context(|| format!("resolving revision {revision:?}"))?;
+        let entry = match tree.get_path(relative_file_path) {
+            Ok(entry) => entry,
+            Err(error) if error.code() == git2::ErrorCode::NotFound => return Ok(None),
+            Err(error) => return Err(error.into()),
+        };
+
+        let content = self.repository.find_blob(entry.id())?.content().to_owned();
+        Ok(Some(String::from_utf8(content)?))
+    }
+
     fn remote_url(&self, name: &str) -> Option<String> {
         let remote = self.repository.find_remote(name).ok()?;
         remote.url().map(|url| url.to_string())
@@ -290,6 +338,7 @@ pub struct FakeGitRepository {
 #[derive(Debug, Clone, Default)]
 pub struct FakeGitRepositoryState {
     pub index_contents: HashMap<PathBuf, String>,
+    pub committed_contents: HashMap<String, HashMap<PathBuf, String>>,
     pub blames: HashMap<PathBuf, Blame>,
     pub worktree_statuses: HashMap<RepoPath, GitFileStatus>,
     pub branch_name: Option<String>,
@@ -309,6 +358,15 @@ impl GitRepository for FakeGitRepository {
         state.index_contents.get(path).cloned()
     }
 
+    fn load_committed_text(&self, path: &Path, revision: &str) -> Result<Option<String>> {
+        let state = self.state.lock();
+        let contents = state
+            .committed_contents
+            .get(revision)
+            .with_context(|| format!("unknown revision {revision:?}"))?;
+        Ok(contents.get(path).cloned())
+    }
+
     fn remote_url(&self, _name: &str) -> Option<String> {
         None
     }
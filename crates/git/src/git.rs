@@ -30,7 +30,9 @@ impl Oid {
         self.0.as_bytes()
     }
 
-    pub(crate) fn is_zero(&self) -> bool {
+    /// Returns whether this is the all-zero id, which git uses for changes that
+    /// aren't committed yet.
+    pub fn is_zero(&self) -> bool {
         self.0.is_zero()
     }
 }
@@ -1,30 +1,446 @@
-use anyhow::Result;
-use client::{user::UserStore, Client, ClientSettings, RemoteProjectId};
+#[cfg(test)]
+mod headless_tests;
+mod health;
+mod http_server;
+mod instructions;
+mod json_logger;
+mod logging;
+mod metrics;
+mod signals;
+
+pub use health::HealthCheck;
+pub use json_logger::init_json_logger;
+pub use logging::{AccessEvent, AccessKind, AccessLog, ProjectLogRetention};
+pub use metrics::Metrics;
+pub use signals::request_interrupt;
+
+use anyhow::{anyhow, Context as _, Result};
+use chrono::{DateTime, Utc};
+use client::{
+    user::UserStore, Client, ClientSettings, DevServerToken, Keepalive, RemoteProjectId, Status,
+    UserId,
+};
+use clock::SystemClock;
 use fs::Fs;
-use futures::Future;
-use gpui::{AppContext, AsyncAppContext, Context, Global, Model, ModelContext, Task, WeakModel};
-use language::LanguageRegistry;
+use futures::{
+    channel::{mpsc, oneshot},
+    select_biased, stream, Future, FutureExt, StreamExt,
+};
+use gpui::{
+    AppContext, AsyncAppContext, Context, EventEmitter, Global, Model, ModelContext, Subscription,
+    Task, WeakModel,
+};
+use http_server::HttpServer;
+use language::{LanguageRegistry, LanguageServerId};
+use logging::ProjectLog;
 use node_runtime::NodeRuntime;
 use postage::stream::Stream;
-use project::Project;
-use rpc::{proto, TypedEnvelope};
-use settings::Settings;
-use std::{collections::HashMap, sync::Arc};
-use util::{ResultExt, TryFutureExt};
+use project::{Project, WorktreeSettings};
+use rand::Rng;
+use rpc::proto;
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsStore};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    mem,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use sysinfo::System;
+use util::{http::HttpClient, ResultExt, TryFutureExt};
+
+/// How often the status file is rewritten when nothing else has changed.
+const STATUS_FILE_INTERVAL: Duration = Duration::from_secs(30);
+/// The longest we wait between checks for idle projects.
+const IDLE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+/// How many connection events are kept when [`DevServerOptions::connection_history_len`] is unset.
+const DEFAULT_CONNECTION_HISTORY_LEN: usize = 64;
+/// How long opening a project's worktree may take before its share is abandoned.
+const SHARE_FS_TIMEOUT: Duration = Duration::from_secs(60);
+/// How long an unshared project's language servers may take to shut down
+/// before the project is dropped anyway.
+const LANGUAGE_SERVER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(10);
+/// How many times in a row a project may fail to share before it is no longer
+/// retried after reconnecting.
+const MAX_SHARE_ATTEMPTS: usize = 3;
+/// How many projects are shared at once when sharing them again after reconnecting.
+const MAX_CONCURRENT_RESHARES: usize = 8;
+/// How long an interrupted dev server that is draining waits for its projects to
+/// be unshared when [`DevServerOptions::drain_timeout`] is unset.
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(5 * 60);
 
 pub struct DevServer {
     client: Arc<Client>,
     app_state: AppState,
-    projects: HashMap<RemoteProjectId, Model<Project>>,
+    projects: HashMap<RemoteProjectId, SharedProject>,
+    /// The projects the server most recently asked us to share.
+    desired_projects: HashSet<RemoteProjectId>,
+    /// Projects that are currently being shared and are not yet in `projects`.
+    pending_shares: HashSet<RemoteProjectId>,
+    /// Desired projects whose last attempt to share them failed. They are
+    /// retried after reconnecting.
+    failed_shares: HashMap<RemoteProjectId, FailedShare>,
+    /// Desired projects that weren't shared because of [`DevServerOptions::max_projects`],
+    /// which the server was told about.
+    refused_projects: HashSet<RemoteProjectId>,
+    /// Projects from the pages of a paginated instruction received so far.
+    instruction_pages: Vec<proto::RemoteProject>,
+    next_instruction_page: u32,
+    last_reconnect: Option<DateTime<Utc>>,
+    /// When the current connection to the server was established, or `None`
+    /// while disconnected.
+    connected_since: Option<DateTime<Utc>>,
+    /// How many times the dev server connected again after losing its connection.
+    reconnect_count: usize,
+    /// The most recent changes to the connection's status, oldest first.
+    connection_history: VecDeque<ConnectionEvent>,
+    counters: Counters,
+    /// Reconnection attempts that failed since the last successful connection.
+    failed_reconnects: usize,
+    /// Rejoin requests that failed since the last one that succeeded.
+    failed_rejoins: usize,
+    /// Whether the server accepts the changes to each project's worktrees,
+    /// rather than all of them, when the dev server rejoins. Learned from the
+    /// response to the previous rejoin.
+    reshares_project_changes: bool,
+    /// Whether [`Event::ConnectionDegraded`] was emitted since the last
+    /// successful connection.
+    degraded: bool,
+    /// Whether orphaned directories in [`DevServerOptions::managed_dirs`] were cleaned up.
+    cleaned_up_managed_dirs: bool,
+    /// The name sent to the server when connecting. See [`DevServerOptions::display_name`].
+    display_name: Option<String>,
+    status_file_tx: Option<mpsc::UnboundedSender<()>>,
+    /// Instructions waiting to be applied, in the order they arrived.
+    instructions_tx: mpsc::UnboundedSender<proto::DevServerInstructions>,
+    _apply_instructions: Task<()>,
     _subscriptions: Vec<client::Subscription>,
     _maintain_connection: Task<Option<()>>,
+    _maintain_status_file: Option<Task<Option<()>>>,
+    _unshare_idle_projects: Option<Task<Option<()>>>,
+    /// Serves [`Metrics`] on [`DevServerOptions::metrics_address`].
+    metrics_server: Option<HttpServer>,
+    /// Answers probes on [`DevServerOptions::health_address`].
+    health_server: Option<HttpServer>,
+    /// Whether the dev server has been connected to the server since it started.
+    has_connected: bool,
+    /// Signaled by the Ctrl-C handler installed in [`init`] and by [`request_interrupt`].
+    interrupt_tx: mpsc::UnboundedSender<()>,
+    shutting_down: bool,
+    /// Whether the dev server has stopped sharing new projects. See [`DevServer::drain`].
+    draining: bool,
+    /// Signaled once a draining dev server that was interrupted has no more
+    /// shared projects.
+    drained_tx: Option<oneshot::Sender<()>>,
+    _watch_interrupt: Task<Option<()>>,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+struct Counters {
+    connections: u64,
+    shares_succeeded: u64,
+    shares_failed: u64,
+    idle_unshares: u64,
+}
+
+struct FailedShare {
+    remote_project: proto::RemoteProject,
+    /// How many times in a row sharing the project has failed.
+    attempts: usize,
+}
+
+struct SharedProject {
+    project: Model<Project>,
+    /// The path the server asked us to share.
+    path: String,
+    priority: Option<i32>,
+    /// Settings the server asked us to apply to this project.
+    settings: Option<String>,
+    /// The last time a collaborator was seen in this project.
+    last_activity: DateTime<Utc>,
+    /// The users of the project's current collaborators, so that we know who left.
+    collaborators: HashMap<proto::PeerId, UserId>,
+    log: Option<ProjectLog>,
+    language_server_logs: HashMap<LanguageServerId, lsp::Subscription>,
+    /// Languages in the project whose language servers can't start because
+    /// they need Node and no Node runtime is available.
+    languages_without_node: Vec<String>,
+    /// The status last reported to the server.
+    status: ProjectStatus,
+    /// The project's worktrees when the connection to the server was lost,
+    /// which are the ones the server knows about. Rejoining only sends what
+    /// changed since.
+    worktrees_at_disconnect: Option<Vec<proto::WorktreeMetadata>>,
+    _subscription: Subscription,
+    _detect_languages_without_node: Task<Option<()>>,
 }
 
+/// The health of a shared project. The server assumes a project is healthy
+/// once it's shared, and is told whenever that changes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ProjectStatus {
+    Healthy,
+    Errored(String),
+    Disconnected,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// A project was unshared because nobody had used it for
+    /// [`DevServerOptions::idle_unshare_timeout`]. It will be shared again the next
+    /// time the server sends instructions that include it.
+    IdleProjectUnshared(RemoteProjectId),
+    /// The connection to the server ended in a way that reconnecting can't fix.
+    ConnectionTerminated(Status),
+    /// Reconnecting to the server failed [`ReconnectAlert::failed_attempts`]
+    /// times in a row. The client keeps retrying at its capped interval.
+    ConnectionDegraded { failed_reconnects: usize },
+    /// The connection was re-established after being degraded.
+    ConnectionRecovered,
+    /// These projects were not shared because [`DevServerOptions::max_projects`]
+    /// projects are already shared, and were reported to the server as
+    /// errored. They are shared by later instructions once other projects
+    /// are removed.
+    ProjectsSkipped(Vec<RemoteProjectId>),
+    /// The process was interrupted and the dev server is shutting down.
+    Interrupted,
+    /// Rejoining the server failed [`ReconnectPolicy::max_retries`] times in
+    /// a row, and the dev server is shutting down.
+    RejoinRetriesExhausted { failed_attempts: usize },
+    /// A shared project's local settings file couldn't be loaded. The project
+    /// keeps using the settings that were last loaded successfully.
+    SettingsLoadFailed {
+        remote_project_id: RemoteProjectId,
+        path: Arc<Path>,
+        message: String,
+    },
+    /// A project in a batch of instructions failed to share, so the projects
+    /// that batch did share were unshared again. See
+    /// [`DevServerOptions::transactional_instructions`].
+    InstructionsRolledBack(Vec<RemoteProjectId>),
+    /// The dev server stopped sharing new projects. See [`DevServer::drain`].
+    Draining,
+    /// A draining dev server that was interrupted is about to quit, either
+    /// because all of its projects were unshared or because it timed out
+    /// waiting for them.
+    Drained { timed_out: bool },
+}
+
+impl EventEmitter<Event> for DevServer {}
+
 pub struct AppState {
     pub node_runtime: Arc<dyn NodeRuntime>,
     pub user_store: Model<UserStore>,
     pub languages: Arc<LanguageRegistry>,
     pub fs: Arc<dyn Fs>,
+    pub clock: Arc<dyn SystemClock>,
+    /// Where to record collaborators joining and leaving shared projects, in
+    /// addition to the dev server's log.
+    pub access_log: Option<Arc<dyn AccessLog>>,
+    /// Constructs the project for each remote project that is shared. When
+    /// unset, a local project is created with [`Project::local`].
+    pub project_factory: Option<ProjectFactory>,
+    pub options: DevServerOptions,
+}
+
+/// Controls how the dev server behaves. Every option has a default, so only
+/// the ones that matter need to be set.
+#[derive(Clone, Debug, Default)]
+pub struct DevServerOptions {
+    /// When set, projects that have had no collaborators for this long are unshared.
+    pub idle_unshare_timeout: Option<Duration>,
+    /// When set, the dev server keeps a JSON summary of its state at this path,
+    /// so that supervisors can poll it instead of talking to the server.
+    pub status_file_path: Option<PathBuf>,
+    /// When set, the language server and project logs of each shared project
+    /// are also written to `<remote-project-id>.log` in this directory.
+    pub project_log_dir: Option<PathBuf>,
+    pub project_log_retention: ProjectLogRetention,
+    /// Overrides how the connection to the server is kept alive. When unset,
+    /// the client's defaults are used.
+    pub keepalive: Option<Keepalive>,
+    /// How many recent connection events to keep for debugging. When unset,
+    /// a default number of events is kept.
+    pub connection_history_len: Option<usize>,
+    /// What to do when a project's path is inside another shared project's
+    /// path, or contains it.
+    pub overlapping_projects: OverlappingProjectPolicy,
+    /// When set, the dev server serves its [`Metrics`] in Prometheus' text
+    /// format over HTTP on this address.
+    pub metrics_address: Option<SocketAddr>,
+    /// When set, the dev server reports the connection as degraded after
+    /// repeatedly failing to reconnect.
+    pub reconnect_alert: Option<ReconnectAlert>,
+    /// When set, directories left behind in the managed root by an earlier
+    /// run are removed once the dev server learns which projects to share.
+    pub managed_dirs: Option<ManagedDirs>,
+    /// The name the dev server presents to the server each time it connects.
+    /// When unset, the machine's hostname is used.
+    pub display_name: Option<String>,
+    /// When set, at most this many projects are shared at once.
+    pub max_projects: Option<usize>,
+    /// How long to wait before retrying after rejoining the server fails.
+    pub reconnect_policy: ReconnectPolicy,
+    /// When set, the dev server answers liveness and readiness probes over
+    /// HTTP on this address, at `/healthz` and `/readyz` respectively.
+    pub health_address: Option<SocketAddr>,
+    /// Globs for paths that worktrees of shared projects never scan, in
+    /// addition to the `file_scan_exclusions` setting.
+    pub file_scan_exclusions: Option<Vec<String>>,
+    /// When set, each batch of instructions is applied in full or not at all.
+    /// If any project in the batch fails to share, the projects the batch
+    /// shared are unshared again and the projects it removed stay shared.
+    pub transactional_instructions: bool,
+    /// The file the dev server's token was read from. The token is read from
+    /// it again when the process receives SIGHUP, so that it can be rotated
+    /// without restarting the dev server.
+    pub token_path: Option<PathBuf>,
+    /// How long an interrupted dev server that is draining waits for its
+    /// projects to be unshared before quitting anyway. Defaults to 5 minutes.
+    pub drain_timeout: Option<Duration>,
+    /// How long to wait for more instructions before applying the latest
+    /// ones, so that instructions sent in quick succession are applied once.
+    /// Defaults to 250 milliseconds.
+    pub instruction_debounce: Option<Duration>,
+    /// When set, instructions are only checked: the dev server logs which
+    /// projects it would share and unshare, and tells the server whether each
+    /// project's path is a directory, but doesn't share anything.
+    pub dry_run: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ManagedDirs {
+    /// The directory under which the dev server creates the directories it manages.
+    pub root: PathBuf,
+    /// How old an unreferenced directory must be before it is removed, so
+    /// that directories another process is still setting up are left alone.
+    pub stale_after: Duration,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ReconnectAlert {
+    /// How many reconnection attempts in a row must fail before
+    /// [`Event::ConnectionDegraded`] is emitted.
+    pub failed_attempts: usize,
+    /// When set, a JSON payload is posted to this URL when the connection
+    /// becomes degraded and when it recovers.
+    pub webhook_url: Option<String>,
+}
+
+/// Controls how quickly the dev server retries rejoining the server after it
+/// connects but its [`proto::ReconnectDevServer`] request fails.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReconnectPolicy {
+    /// How long to wait after the first failure.
+    pub base_delay: Duration,
+    /// The longest to wait between attempts.
+    pub max_delay: Duration,
+    /// How much the delay grows after each consecutive failure.
+    pub multiplier: f64,
+    /// The delay is randomly varied by up to this fraction of itself, so that
+    /// dev servers that lost their connection together don't retry together.
+    pub jitter: f64,
+    /// When set, the dev server quits once this many consecutive attempts fail.
+    pub max_retries: Option<usize>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            multiplier: 2.,
+            jitter: 0.2,
+            max_retries: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// The delay before retrying after `failed_attempts` consecutive failures.
+    pub fn delay(&self, failed_attempts: usize, rng: &mut impl Rng) -> Duration {
+        let exponent = failed_attempts.saturating_sub(1).min(i32::MAX as usize) as i32;
+        let delay = self.base_delay.as_secs_f64() * self.multiplier.powi(exponent);
+        let jitter = if self.jitter > 0. {
+            rng.gen_range(-self.jitter..=self.jitter)
+        } else {
+            0.
+        };
+        Duration::from_secs_f64((delay * (1. + jitter)).clamp(0., self.max_delay.as_secs_f64()))
+    }
+}
+
+pub type ProjectFactory =
+    Arc<dyn Fn(&proto::RemoteProject, &Arc<Client>, &AppState, &mut AppContext) -> Model<Project>>;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OverlappingProjectPolicy {
+    /// Share the project anyway, logging a warning.
+    #[default]
+    Warn,
+    /// Refuse to share the project.
+    Refuse,
+}
+
+/// A change in the state of the dev server's connection to the server.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConnectionEvent {
+    pub timestamp: DateTime<Utc>,
+    pub kind: ConnectionEventKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ConnectionEventKind {
+    /// The client started connecting or reconnecting. `status` is the step it reached.
+    Connecting {
+        status: String,
+    },
+    Connected,
+    /// A connection attempt failed. The client will try again.
+    ConnectFailed {
+        status: String,
+    },
+    /// An established connection was lost, or the client gave up reconnecting.
+    Disconnected {
+        reason: String,
+    },
+}
+
+/// The contents of the status file written to [`DevServerOptions::status_file_path`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StatusFile {
+    pub status: String,
+    pub shared_project_count: usize,
+    pub remote_project_ids: Vec<u64>,
+    pub last_reconnect: Option<DateTime<Utc>>,
+    pub connection_history: Vec<ConnectionEvent>,
+}
+
+/// A detailed description of everything the dev server is currently sharing.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShareManifest {
+    pub projects: Vec<ShareManifestProject>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShareManifestProject {
+    pub remote_project_id: u64,
+    pub project_id: Option<u64>,
+    pub path: String,
+    pub priority: Option<i32>,
+    pub worktrees: Vec<ShareManifestWorktree>,
+    pub languages_without_node: Vec<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShareManifestWorktree {
+    pub id: u64,
+    pub root_name: String,
+    pub abs_path: PathBuf,
+    pub file_scan_exclusions: Vec<String>,
 }
 
 struct GlobalDevServer(Model<DevServer>);
@@ -35,16 +451,45 @@ pub fn init(client: Arc<Client>, app_state: AppState, cx: &mut AppContext) {
     let dev_server = cx.new_model(|cx| DevServer::new(client.clone(), app_state, cx));
     cx.set_global(GlobalDevServer(dev_server.clone()));
 
-    // Set up a handler when the dev server is shut down by the user pressing Ctrl-C
-    let (tx, rx) = futures::channel::oneshot::channel();
-    set_ctrlc_handler(move || tx.send(()).log_err().unwrap()).log_err();
+    // Set up a handler for when the dev server is shut down by the user pressing
+    // Ctrl-C, or by a service manager sending SIGTERM.
+    signals::handle_interrupts(dev_server.read(cx).interrupt_tx.clone());
 
-    cx.spawn(|cx| async move {
-        rx.await.log_err();
-        log::info!("Received interrupt signal");
-        cx.update(|cx| cx.quit()).log_err();
-    })
-    .detach();
+    #[cfg(unix)]
+    {
+        let (reload_tx, mut reload_rx) = mpsc::unbounded();
+        signals::handle_reload_signals(move || {
+            reload_tx.unbounded_send(()).ok();
+        })
+        .log_err();
+        cx.spawn({
+            let dev_server = dev_server.clone();
+            |mut cx| async move {
+                while reload_rx.next().await.is_some() {
+                    if let Ok(reload) =
+                        dev_server.update(&mut cx, |dev_server, cx| dev_server.reload_token(cx))
+                    {
+                        reload.await.log_err();
+                    }
+                }
+            }
+        })
+        .detach();
+
+        let (drain_tx, mut drain_rx) = mpsc::unbounded();
+        signals::handle_drain_signals(move || {
+            drain_tx.unbounded_send(()).ok();
+        })
+        .log_err();
+        cx.spawn(|mut cx| async move {
+            while drain_rx.next().await.is_some() {
+                dev_server
+                    .update(&mut cx, |dev_server, cx| dev_server.drain(cx))
+                    .log_err();
+            }
+        })
+        .detach();
+    }
 
     let server_url = ClientSettings::get_global(&cx).server_url.clone();
     cx.spawn(|cx| async move {
@@ -61,17 +506,20 @@ pub fn init(client: Arc<Client>, app_state: AppState, cx: &mut AppContext) {
     .detach();
 }
 
-fn set_ctrlc_handler<F>(f: F) -> Result<(), ctrlc::Error>
-where
-    F: FnOnce() + 'static + Send,
-{
-    let f = std::sync::Mutex::new(Some(f));
-    ctrlc::set_handler(move || {
-        if let Ok(mut guard) = f.lock() {
-            let f = guard.take().expect("f can only be taken once");
-            f();
-        }
-    })
+/// Reads the dev server's token from a file, such as one mounted from a
+/// secret store. Surrounding whitespace is ignored.
+pub fn read_dev_server_token(path: &Path) -> Result<DevServerToken> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read the dev server token from {:?}", path))?;
+    parse_dev_server_token(&contents, path)
+}
+
+fn parse_dev_server_token(contents: &str, path: &Path) -> Result<DevServerToken> {
+    let token = contents.trim();
+    if token.is_empty() {
+        return Err(anyhow!("the dev server token file {:?} is empty", path));
+    }
+    Ok(DevServerToken(token.to_string()))
 }
 
 impl DevServer {
@@ -82,70 +530,486 @@ impl DevServer {
     pub fn new(client: Arc<Client>, app_state: AppState, cx: &mut ModelContext<Self>) -> Self {
         cx.on_app_quit(Self::app_will_quit).detach();
 
+        if let Some(keepalive) = app_state.options.keepalive {
+            client.set_keepalive(keepalive);
+        }
+
+        // Worktrees read these when they are created, before their initial scan.
+        if let Some(exclusions) = &app_state.options.file_scan_exclusions {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.set_server_settings(
+                    serde_json::json!({ "file_scan_exclusions": exclusions }),
+                    cx,
+                )
+            })
+            .log_err();
+        }
+
         let maintain_connection = cx.spawn({
             let client = client.clone();
             move |this, cx| Self::maintain_connection(this, client.clone(), cx).log_err()
         });
 
+        let (status_file_tx, maintain_status_file) = if let Some(path) =
+            app_state.options.status_file_path.clone()
+        {
+            let (tx, rx) = mpsc::unbounded();
+            let task =
+                cx.spawn(move |this, cx| Self::maintain_status_file(this, path, rx, cx).log_err());
+            (Some(tx), Some(task))
+        } else {
+            (None, None)
+        };
+
+        let (instructions_tx, instructions_rx) = mpsc::unbounded();
+        let apply_instructions =
+            cx.spawn(move |this, cx| Self::apply_instructions(this, instructions_rx, cx));
+
+        let unshare_idle_projects = app_state.options.idle_unshare_timeout.map(|timeout| {
+            let interval = timeout.min(IDLE_CHECK_INTERVAL);
+            cx.spawn(move |this, mut cx| {
+                async move {
+                    loop {
+                        cx.background_executor().timer(interval).await;
+                        this.update(&mut cx, |this, cx| this.unshare_idle_projects(cx))?;
+                    }
+                }
+                .log_err()
+            })
+        });
+
+        let metrics_server = app_state.options.metrics_address.and_then(|address| {
+            HttpServer::new(address, |requests| {
+                cx.spawn(move |this, cx| Self::serve_metrics(this, requests, cx).log_err())
+            })
+            .context("failed to serve metrics")
+            .log_err()
+        });
+
+        let health_server = app_state.options.health_address.and_then(|address| {
+            HttpServer::new(address, |requests| {
+                cx.spawn(move |this, cx| Self::serve_health_checks(this, requests, cx).log_err())
+            })
+            .context("failed to serve health checks")
+            .log_err()
+        });
+
+        let (interrupt_tx, mut interrupt_rx) = mpsc::unbounded();
+        let watch_interrupt = cx.spawn(move |this, mut cx| async move {
+            interrupt_rx.next().await?;
+            this.update(&mut cx, |this, cx| this.interrupted(cx)).ok()
+        });
+
         DevServer {
             _subscriptions: vec![
                 client.add_message_handler(cx.weak_model(), Self::handle_dev_server_instructions)
             ],
             _maintain_connection: maintain_connection,
+            _maintain_status_file: maintain_status_file,
+            _unshare_idle_projects: unshare_idle_projects,
+            metrics_server,
+            health_server,
+            has_connected: client.status().borrow().is_connected(),
+            interrupt_tx,
+            shutting_down: false,
+            draining: false,
+            drained_tx: None,
+            _watch_interrupt: watch_interrupt,
             projects: Default::default(),
+            desired_projects: Default::default(),
+            pending_shares: Default::default(),
+            failed_shares: Default::default(),
+            refused_projects: Default::default(),
+            instruction_pages: Default::default(),
+            next_instruction_page: 0,
+            last_reconnect: None,
+            connected_since: client
+                .status()
+                .borrow()
+                .is_connected()
+                .then(|| app_state.clock.utc_now()),
+            reconnect_count: 0,
+            connection_history: VecDeque::new(),
+            counters: Counters::default(),
+            failed_reconnects: 0,
+            failed_rejoins: 0,
+            reshares_project_changes: false,
+            degraded: false,
+            cleaned_up_managed_dirs: false,
+            display_name: app_state
+                .options
+                .display_name
+                .clone()
+                .or_else(System::host_name),
+            status_file_tx,
+            instructions_tx,
+            _apply_instructions: apply_instructions,
             app_state,
             client,
         }
     }
 
-    fn app_will_quit(&mut self, _: &mut ModelContext<Self>) -> impl Future<Output = ()> {
-        let request = self.client.request(proto::ShutdownDevServer {});
-        async move {
-            request.await.log_err();
+    pub fn share_manifest(&self, cx: &AppContext) -> ShareManifest {
+        let file_scan_exclusions = WorktreeSettings::get_global(cx)
+            .file_scan_exclusions
+            .clone()
+            .unwrap_or_default();
+
+        let mut projects = self
+            .projects
+            .iter()
+            .map(|(remote_project_id, shared_project)| {
+                let project = shared_project.project.read(cx);
+                ShareManifestProject {
+                    remote_project_id: remote_project_id.0,
+                    project_id: project.remote_id(),
+                    path: shared_project.path.clone(),
+                    priority: shared_project.priority,
+                    worktrees: project
+                        .worktrees()
+                        .map(|worktree| {
+                            let worktree = worktree.read(cx);
+                            ShareManifestWorktree {
+                                id: worktree.id().to_proto(),
+                                root_name: worktree.root_name().to_string(),
+                                abs_path: worktree.abs_path().to_path_buf(),
+                                file_scan_exclusions: file_scan_exclusions.clone(),
+                            }
+                        })
+                        .collect(),
+                    languages_without_node: shared_project.languages_without_node.clone(),
+                }
+            })
+            .collect::<Vec<_>>();
+        projects.sort_by_key(|project| project.remote_project_id);
+
+        ShareManifest { projects }
+    }
+
+    pub fn write_share_manifest(&self, path: PathBuf, cx: &AppContext) -> Task<Result<()>> {
+        let fs = self.app_state.fs.clone();
+        let manifest = self.share_manifest(cx);
+        cx.background_executor().spawn(async move {
+            fs.atomic_write(path, serde_json::to_string_pretty(&manifest)?)
+                .await
+        })
+    }
+
+    pub fn status_file(&self) -> StatusFile {
+        let status = status_name(*self.client.status().borrow());
+        let mut remote_project_ids = self.projects.keys().map(|id| id.0).collect::<Vec<_>>();
+        remote_project_ids.sort_unstable();
+
+        StatusFile {
+            status: status.to_string(),
+            shared_project_count: remote_project_ids.len(),
+            remote_project_ids,
+            last_reconnect: self.last_reconnect,
+            connection_history: self.connection_history.iter().cloned().collect(),
         }
     }
 
-    async fn handle_dev_server_instructions(
-        this: Model<Self>,
-        envelope: TypedEnvelope<proto::DevServerInstructions>,
-        _: Arc<Client>,
+    /// When the current connection to the server was established, or `None`
+    /// while the dev server is disconnected.
+    pub fn connected_since(&self) -> Option<DateTime<Utc>> {
+        self.connected_since
+    }
+
+    /// How many times the dev server connected again after losing its connection.
+    pub fn reconnect_count(&self) -> usize {
+        self.reconnect_count
+    }
+
+    /// Recent changes to the connection's status, oldest first.
+    pub fn connection_history(&self) -> impl Iterator<Item = &ConnectionEvent> {
+        self.connection_history.iter()
+    }
+
+    fn record_connection_status(&mut self, status: Status) {
+        let name = status_name(status).to_string();
+        log::info!(
+            event = "connection_status",
+            status = name.as_str();
+            "dev server connection status: {}",
+            name
+        );
+        let kind = match status {
+            Status::Connected { .. } => ConnectionEventKind::Connected,
+            Status::Authenticating
+            | Status::Connecting
+            | Status::Reauthenticating
+            | Status::Reconnecting => ConnectionEventKind::Connecting { status: name },
+            Status::ConnectionError | Status::ReconnectionError { .. } => {
+                ConnectionEventKind::ConnectFailed { status: name }
+            }
+            Status::ConnectionLost | Status::SignedOut | Status::UpgradeRequired => {
+                ConnectionEventKind::Disconnected { reason: name }
+            }
+        };
+
+        let max_len = self
+            .app_state
+            .options
+            .connection_history_len
+            .unwrap_or(DEFAULT_CONNECTION_HISTORY_LEN);
+        while !self.connection_history.is_empty() && self.connection_history.len() >= max_len {
+            self.connection_history.pop_front();
+        }
+        if max_len > 0 {
+            self.connection_history.push_back(ConnectionEvent {
+                timestamp: self.app_state.clock.utc_now(),
+                kind,
+            });
+        }
+    }
+
+    fn track_reconnect_failures(&mut self, status: Status, cx: &mut ModelContext<Self>) {
+        match status {
+            Status::ReconnectionError { .. } => {
+                self.failed_reconnects += 1;
+                let Some(alert) = self.app_state.options.reconnect_alert.as_ref() else {
+                    return;
+                };
+                if self.degraded || self.failed_reconnects < alert.failed_attempts {
+                    return;
+                }
+
+                self.degraded = true;
+                log::error!(
+                    event = "connection_degraded",
+                    failed_reconnects = self.failed_reconnects;
+                    "dev server connection degraded after {} failed reconnects",
+                    self.failed_reconnects
+                );
+                self.send_reconnect_webhook(
+                    serde_json::json!({
+                        "status": "degraded",
+                        "failed_reconnects": self.failed_reconnects,
+                    }),
+                    cx,
+                );
+                cx.emit(Event::ConnectionDegraded {
+                    failed_reconnects: self.failed_reconnects,
+                });
+            }
+            Status::Connected { .. } => {
+                self.failed_reconnects = 0;
+                if mem::take(&mut self.degraded) {
+                    log::info!(
+                        event = "connection_recovered";
+                        "dev server connection recovered"
+                    );
+                    self.send_reconnect_webhook(serde_json::json!({ "status": "recovered" }), cx);
+                    cx.emit(Event::ConnectionRecovered);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn send_reconnect_webhook(&self, payload: serde_json::Value, cx: &mut ModelContext<Self>) {
+        let Some(url) = self
+            .app_state
+            .options
+            .reconnect_alert
+            .as_ref()
+            .and_then(|alert| alert.webhook_url.clone())
+        else {
+            return;
+        };
+        let http_client = self.client.http_client();
+        cx.background_executor()
+            .spawn(
+                async move {
+                    let response = http_client
+                        .post_json(&url, payload.to_string().into())
+                        .await?;
+                    if !response.status().is_success() {
+                        return Err(anyhow!(
+                            "reconnect webhook {url} responded with {}",
+                            response.status()
+                        ));
+                    }
+                    anyhow::Ok(())
+                }
+                .log_err(),
+            )
+            .detach();
+    }
+
+    /// Unshares every project that has had no collaborators for longer than
+    /// [`DevServerOptions::idle_unshare_timeout`].
+    pub fn unshare_idle_projects(&mut self, cx: &mut ModelContext<Self>) {
+        let Some(idle_timeout) = self.app_state.options.idle_unshare_timeout else {
+            return;
+        };
+
+        let now = self.app_state.clock.utc_now();
+        let idle_projects = self
+            .projects
+            .iter_mut()
+            .filter_map(|(remote_project_id, shared_project)| {
+                if !shared_project.project.read(cx).collaborators().is_empty() {
+                    shared_project.last_activity = now;
+                    return None;
+                }
+                let idle_for = (now - shared_project.last_activity)
+                    .to_std()
+                    .unwrap_or_default();
+                (idle_for >= idle_timeout).then_some(*remote_project_id)
+            })
+            .collect::<Vec<_>>();
+
+        for remote_project_id in idle_projects {
+            log::info!(
+                event = "idle_project_unshared",
+                remote_project_id = remote_project_id.0;
+                "unsharing idle remote project {}",
+                remote_project_id.0
+            );
+            self.unshare_project(&remote_project_id, cx).log_err();
+            self.counters.idle_unshares += 1;
+            cx.emit(Event::IdleProjectUnshared(remote_project_id));
+        }
+    }
+
+    /// Reads the token from [`DevServerOptions::token_path`] again. The client uses the
+    /// new token the next time it connects to the server.
+    pub fn reload_token(&self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
+        let Some(path) = self.app_state.options.token_path.clone() else {
+            log::info!("not reloading the dev server token, as it wasn't read from a file");
+            return Task::ready(Ok(()));
+        };
+
+        let fs = self.app_state.fs.clone();
+        let client = self.client.clone();
+        cx.background_executor().spawn(async move {
+            let contents = fs
+                .load(&path)
+                .await
+                .with_context(|| format!("failed to read the dev server token from {:?}", path))?;
+            client.set_dev_server_token(parse_dev_server_token(&contents, &path)?);
+            log::info!(event = "token_reloaded"; "reloaded the dev server token from {:?}", path);
+            Ok(())
+        })
+    }
+
+    fn status_changed(&self) {
+        if let Some(tx) = &self.status_file_tx {
+            tx.unbounded_send(()).ok();
+        }
+    }
+
+    async fn maintain_status_file(
+        this: WeakModel<Self>,
+        path: PathBuf,
+        mut status_changed: mpsc::UnboundedReceiver<()>,
         mut cx: AsyncAppContext,
     ) -> Result<()> {
-        let (added_projects, removed_projects_ids) = this.read_with(&mut cx, |this, _| {
-            let removed_projects = this
-                .projects
-                .keys()
-                .filter(|remote_project_id| {
-                    !envelope
-                        .payload
-                        .projects
-                        .iter()
-                        .any(|p| p.id == remote_project_id.0)
-                })
-                .cloned()
-                .collect::<Vec<_>>();
+        loop {
+            let Some(this) = this.upgrade() else {
+                return Ok(());
+            };
+            let (fs, status_file) = this.read_with(&cx, |this, _| {
+                (this.app_state.fs.clone(), this.status_file())
+            })?;
+            drop(this);
 
-            let added_projects = envelope
-                .payload
-                .projects
-                .into_iter()
-                .filter(|project| !this.projects.contains_key(&RemoteProjectId(project.id)))
-                .collect::<Vec<_>>();
+            // `atomic_write` writes to a temporary file and renames it into place,
+            // so readers never observe a partially written status file.
+            fs.atomic_write(path.clone(), serde_json::to_string_pretty(&status_file)?)
+                .await
+                .log_err();
 
-            (added_projects, removed_projects)
-        })?;
+            let mut timer = cx.background_executor().timer(STATUS_FILE_INTERVAL).fuse();
+            select_biased! {
+                changed = status_changed.next() => {
+                    if changed.is_none() {
+                        return Ok(());
+                    }
+                }
+                _ = timer => {}
+            }
+        }
+    }
 
-        for remote_project in added_projects {
-            DevServer::share_project(this.clone(), &remote_project, &mut cx).await?;
+    /// Shuts the dev server down in response to Ctrl-C or a termination signal.
+    fn interrupted(&mut self, cx: &mut ModelContext<Self>) {
+        if mem::replace(&mut self.shutting_down, true) {
+            return;
+        }
+        log::info!("Received interrupt signal");
+        cx.emit(Event::Interrupted);
+        if !self.draining || self.projects.is_empty() {
+            cx.quit();
+            return;
         }
 
-        this.update(&mut cx, |this, cx| {
-            for old_project_id in &removed_projects_ids {
-                this.unshare_project(old_project_id, cx)?;
-            }
-            Ok::<(), anyhow::Error>(())
-        })??;
-        Ok(())
+        // Quitting can only be delayed briefly once it has started, so wait for
+        // the remaining projects to be unshared before quitting.
+        let (drained_tx, drained_rx) = oneshot::channel();
+        self.drained_tx = Some(drained_tx);
+        let timeout = self
+            .app_state
+            .options
+            .drain_timeout
+            .unwrap_or(DEFAULT_DRAIN_TIMEOUT);
+        log::info!(
+            event = "waiting_for_drain",
+            shared_projects = self.projects.len();
+            "waiting up to {:?} for {} remote projects to be unshared",
+            timeout,
+            self.projects.len()
+        );
+        let mut timeout = cx.background_executor().timer(timeout).fuse();
+        cx.spawn(|this, mut cx| async move {
+            let timed_out = select_biased! {
+                _ = drained_rx.fuse() => false,
+                _ = timeout => true,
+            };
+            this.update(&mut cx, |this, cx| {
+                if timed_out {
+                    log::warn!(
+                        "timed out draining, {} remote projects are still shared",
+                        this.projects.len()
+                    );
+                }
+                cx.emit(Event::Drained { timed_out });
+                cx.quit();
+            })
+            .log_err();
+        })
+        .detach();
+    }
+
+    /// Stops sharing new projects, while projects that are already shared
+    /// stay shared until instructions remove them. If the dev server is then
+    /// interrupted, it waits for them to be unshared, for at most
+    /// [`DevServerOptions::drain_timeout`], before quitting.
+    pub fn drain(&mut self, cx: &mut ModelContext<Self>) {
+        if mem::replace(&mut self.draining, true) {
+            return;
+        }
+        log::info!(
+            event = "draining",
+            shared_projects = self.projects.len();
+            "draining, no new remote projects will be shared"
+        );
+        self.status_changed();
+        cx.emit(Event::Draining);
+    }
+
+    fn app_will_quit(&mut self, _: &mut ModelContext<Self>) -> impl Future<Output = ()> {
+        if let Some(server) = self.metrics_server.take() {
+            server.close();
+        }
+        if let Some(server) = self.health_server.take() {
+            server.close();
+        }
+        let request = self.client.request(proto::ShutdownDevServer {});
+        async move {
+            request.await.log_err();
+        }
     }
 
     fn unshare_project(
@@ -153,8 +1017,51 @@ impl DevServer {
         remote_project_id: &RemoteProjectId,
         cx: &mut ModelContext<Self>,
     ) -> Result<()> {
-        if let Some(project) = self.projects.remove(remote_project_id) {
-            project.update(cx, |project, cx| project.unshare(cx))?;
+        if let Some(shared_project) = self.projects.remove(remote_project_id) {
+            if self.projects.is_empty() {
+                if let Some(drained_tx) = self.drained_tx.take() {
+                    drained_tx.send(()).ok();
+                }
+            }
+            if let Some(log) = &shared_project.log {
+                log.log("project unshared".into());
+                log.close();
+            }
+            let project = shared_project.project;
+            if shared_project.settings.is_some() {
+                clear_project_settings(&project, cx);
+            }
+            log::info!(
+                event = "project_unshared",
+                remote_project_id = remote_project_id.0,
+                project_id = project.read(cx).remote_id();
+                "unsharing remote project {}",
+                remote_project_id.0
+            );
+            let stop_language_servers = project.update(cx, |project, cx| {
+                project.unshare(cx)?;
+                anyhow::Ok(project.stop_language_servers(cx))
+            })?;
+            self.status_changed();
+
+            // Keep the project alive until its language servers have exited, so
+            // that their processes aren't left behind.
+            let remote_project_id = *remote_project_id;
+            cx.spawn(|_, cx| async move {
+                let mut timeout = cx
+                    .background_executor()
+                    .timer(LANGUAGE_SERVER_SHUTDOWN_TIMEOUT)
+                    .fuse();
+                select_biased! {
+                    _ = stop_language_servers.fuse() => {}
+                    _ = timeout => log::warn!(
+                        "timed out stopping language servers for remote project {}",
+                        remote_project_id.0
+                    ),
+                }
+                drop(project);
+            })
+            .detach();
         }
         Ok(())
     }
@@ -164,24 +1071,94 @@ impl DevServer {
         remote_project: &proto::RemoteProject,
         cx: &mut AsyncAppContext,
     ) -> Result<()> {
+        let remote_project_id = RemoteProjectId(remote_project.id);
+        let result = Self::share_project_internal(this.clone(), remote_project, cx).await;
+        this.update(cx, |this, _| {
+            this.pending_shares.remove(&remote_project_id);
+            if result.is_ok() {
+                this.failed_shares.remove(&remote_project_id);
+                return;
+            }
+
+            this.counters.shares_failed += 1;
+            if this.desired_projects.contains(&remote_project_id) {
+                let attempts = this
+                    .failed_shares
+                    .get(&remote_project_id)
+                    .map_or(0, |failed_share| failed_share.attempts);
+                this.failed_shares.insert(
+                    remote_project_id,
+                    FailedShare {
+                        remote_project: remote_project.clone(),
+                        attempts: attempts + 1,
+                    },
+                );
+            }
+        })?;
+        result
+    }
+
+    async fn share_project_internal(
+        this: Model<Self>,
+        remote_project: &proto::RemoteProject,
+        cx: &mut AsyncAppContext,
+    ) -> Result<()> {
+        let remote_project_id = RemoteProjectId(remote_project.id);
+        if !this.read_with(cx, |this, _| {
+            this.desired_projects.contains(&remote_project_id)
+        })? {
+            return Ok(());
+        }
+
+        this.read_with(cx, |this, _| {
+            this.check_overlapping_projects(remote_project)
+        })??;
+
         let (client, project) = this.update(cx, |this, cx| {
-            let project = Project::local(
-                this.client.clone(),
-                this.app_state.node_runtime.clone(),
-                this.app_state.user_store.clone(),
-                this.app_state.languages.clone(),
-                this.app_state.fs.clone(),
-                cx,
-            );
+            let project = match &this.app_state.project_factory {
+                Some(project_factory) => {
+                    project_factory(remote_project, &this.client, &this.app_state, cx)
+                }
+                None => Project::local(
+                    this.client.clone(),
+                    this.app_state.node_runtime.clone(),
+                    this.app_state.user_store.clone(),
+                    this.app_state.languages.clone(),
+                    this.app_state.fs.clone(),
+                    cx,
+                ),
+            };
+            project.update(cx, |project, _| {
+                project.set_keep_last_good_local_settings(true)
+            });
 
             (this.client.clone(), project)
         })?;
 
-        project
-            .update(cx, |project, cx| {
-                project.find_or_create_local_worktree(&remote_project.path, true, cx)
+        let mut timeout = cx.background_executor().timer(SHARE_FS_TIMEOUT).fuse();
+        let open_worktree = project.update(cx, |project, cx| {
+            project.find_or_create_local_worktree(&remote_project.path, true, cx)
+        })?;
+        let (worktree, _) = select_biased! {
+            worktree = open_worktree.fuse() => worktree?,
+            _ = timeout => {
+                return Err(anyhow!(
+                    "timed out opening {} for remote project {}",
+                    remote_project.path,
+                    remote_project.id
+                ));
+            }
+        };
+
+        if let Some(settings) = remote_project.settings.as_deref() {
+            let root_id = worktree.entity_id().as_u64() as usize;
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.set_local_default_settings(root_id, Some(settings), cx)
             })?
-            .await?;
+            .with_context(|| {
+                format!("invalid settings for remote project {}", remote_project.id)
+            })?;
+        }
 
         let worktrees =
             project.read_with(cx, |project, cx| project.worktree_metadata_protos(cx))?;
@@ -195,26 +1172,402 @@ impl DevServer {
 
         let project_id = response.project_id;
         project.update(cx, |project, cx| project.shared(project_id, cx))??;
-        this.update(cx, |this, _| {
-            this.projects
-                .insert(RemoteProjectId(remote_project.id), project);
+        this.update(cx, |this, cx| {
+            if !this.desired_projects.contains(&remote_project_id) {
+                // Instructions removed this project while it was being shared.
+                project
+                    .update(cx, |project, cx| project.unshare(cx))
+                    .log_err();
+                if remote_project.settings.is_some() {
+                    clear_project_settings(&project, cx);
+                }
+                return;
+            }
+
+            let subscription = cx.subscribe(&project, move |this, project, event, cx| {
+                this.handle_project_event(remote_project_id, project, event, cx)
+            });
+            let detect_languages_without_node = cx.spawn({
+                let worktree = worktree.clone();
+                move |this, cx| {
+                    Self::detect_languages_without_node(this, remote_project_id, worktree, cx)
+                        .log_err()
+                }
+            });
+            let log = this.app_state.options.project_log_dir.clone().map(|dir| {
+                let log = ProjectLog::new(
+                    this.app_state.fs.clone(),
+                    this.app_state.clock.clone(),
+                    dir.join(format!("{}.log", remote_project_id.0)),
+                    this.app_state.options.project_log_retention,
+                    cx,
+                );
+                log.log(format!(
+                    "shared {} as project {}",
+                    remote_project.path, project_id
+                ));
+                log
+            });
+            this.projects.insert(
+                remote_project_id,
+                SharedProject {
+                    project,
+                    path: remote_project.path.clone(),
+                    priority: remote_project.priority,
+                    settings: remote_project.settings.clone(),
+                    last_activity: this.app_state.clock.utc_now(),
+                    collaborators: HashMap::default(),
+                    log,
+                    language_server_logs: HashMap::default(),
+                    languages_without_node: Vec::new(),
+                    status: ProjectStatus::Healthy,
+                    worktrees_at_disconnect: None,
+                    _subscription: subscription,
+                    _detect_languages_without_node: detect_languages_without_node,
+                },
+            );
+            log::info!(
+                event = "project_shared",
+                remote_project_id = remote_project.id,
+                project_id = project_id;
+                "shared remote project {} as project {}",
+                remote_project.id,
+                project_id
+            );
+            this.counters.shares_succeeded += 1;
+            if this.refused_projects.remove(&remote_project_id) {
+                Self::send_project_status(&this.client, remote_project_id, &ProjectStatus::Healthy);
+            }
+            this.status_changed();
         })?;
         Ok(())
     }
 
+    /// Finds the languages in a newly shared project whose language servers run
+    /// on Node and, if Node is unavailable, records them on the project so that
+    /// they are reported in the [`ShareManifest`].
+    async fn detect_languages_without_node(
+        this: WeakModel<Self>,
+        remote_project_id: RemoteProjectId,
+        worktree: Model<project::Worktree>,
+        mut cx: AsyncAppContext,
+    ) -> Result<()> {
+        let scan_complete = worktree.read_with(&cx, |worktree, _| {
+            worktree.as_local().map(|worktree| worktree.scan_complete())
+        })?;
+        if let Some(scan_complete) = scan_complete {
+            scan_complete.await;
+        }
+
+        // Languages are detected by file name and extension, so one file of
+        // each is enough.
+        let paths = worktree.read_with(&cx, |worktree, _| {
+            let mut seen = HashSet::default();
+            worktree
+                .files(false, 0)
+                .filter_map(|entry| {
+                    let key = entry
+                        .path
+                        .extension()
+                        .or_else(|| entry.path.file_name())?
+                        .to_os_string();
+                    seen.insert(key).then(|| entry.path.to_path_buf())
+                })
+                .collect::<Vec<_>>()
+        })?;
+
+        let (languages, node_runtime) = this.read_with(&cx, |this, _| {
+            (
+                this.app_state.languages.clone(),
+                this.app_state.node_runtime.clone(),
+            )
+        })?;
+        let mut languages_needing_node = Vec::new();
+        for path in paths {
+            let Ok(language) = languages.language_for_file_path(&path).await else {
+                continue;
+            };
+            let name = language.name().to_string();
+            if !languages_needing_node.contains(&name)
+                && languages
+                    .lsp_adapters(&language)
+                    .iter()
+                    .any(|adapter| adapter.requires_node_runtime())
+            {
+                languages_needing_node.push(name);
+            }
+        }
+        if languages_needing_node.is_empty() {
+            return Ok(());
+        }
+
+        if let Err(error) = node_runtime.binary_path().await {
+            languages_needing_node.sort_unstable();
+            log::warn!(
+                event = "node_unavailable",
+                remote_project_id = remote_project_id.0;
+                "language servers for {} in remote project {} need Node, which is unavailable: {:#}",
+                languages_needing_node.join(", "),
+                remote_project_id.0,
+                error
+            );
+            this.update(&mut cx, |this, _| {
+                if let Some(shared_project) = this.projects.get_mut(&remote_project_id) {
+                    shared_project.languages_without_node = languages_needing_node;
+                    this.status_changed();
+                }
+            })?;
+        }
+        Ok(())
+    }
+
+    fn check_overlapping_projects(&self, remote_project: &proto::RemoteProject) -> Result<()> {
+        let path = Path::new(&remote_project.path);
+        for (remote_project_id, shared_project) in &self.projects {
+            let shared_path = Path::new(&shared_project.path);
+            if !path.starts_with(shared_path) && !shared_path.starts_with(path) {
+                continue;
+            }
+
+            let message = format!(
+                "{} for remote project {} overlaps {} for remote project {}",
+                remote_project.path, remote_project.id, shared_project.path, remote_project_id.0
+            );
+            match self.app_state.options.overlapping_projects {
+                OverlappingProjectPolicy::Warn => log::warn!("{}", message),
+                OverlappingProjectPolicy::Refuse => return Err(anyhow!(message)),
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_project_event(
+        &mut self,
+        remote_project_id: RemoteProjectId,
+        project: Model<Project>,
+        event: &project::Event,
+        cx: &mut ModelContext<Self>,
+    ) {
+        let now = self.app_state.clock.utc_now();
+        let Some(shared_project) = self.projects.get_mut(&remote_project_id) else {
+            return;
+        };
+
+        let status = match event {
+            project::Event::LocalSettingsFailed { path, message, .. } => {
+                Some(ProjectStatus::Errored(format!(
+                    "failed to load settings in {:?}: {}",
+                    path, message
+                )))
+            }
+            project::Event::WorktreeRemoved(_) if project.read(cx).worktrees().next().is_none() => {
+                Some(ProjectStatus::Errored(format!(
+                    "{} is no longer open",
+                    shared_project.path
+                )))
+            }
+            project::Event::RemoteIdChanged(Some(_)) => Some(ProjectStatus::Healthy),
+            project::Event::RemoteIdChanged(None)
+            | project::Event::DisconnectedFromHost
+            | project::Event::Closed => Some(ProjectStatus::Disconnected),
+            _ => None,
+        };
+
+        let access = match event {
+            project::Event::CollaboratorJoined(peer_id) => {
+                shared_project.last_activity = now;
+                project
+                    .read(cx)
+                    .collaborators()
+                    .get(peer_id)
+                    .map(|collaborator| {
+                        shared_project
+                            .collaborators
+                            .insert(*peer_id, collaborator.user_id);
+                        (AccessKind::Joined, *peer_id, collaborator.user_id)
+                    })
+            }
+            project::Event::CollaboratorLeft(peer_id) => {
+                shared_project.last_activity = now;
+                shared_project
+                    .collaborators
+                    .remove(peer_id)
+                    .map(|user_id| (AccessKind::Left, *peer_id, user_id))
+            }
+            project::Event::CollaboratorUpdated {
+                old_peer_id,
+                new_peer_id,
+            } => {
+                shared_project.last_activity = now;
+                if let Some(user_id) = shared_project.collaborators.remove(old_peer_id) {
+                    shared_project.collaborators.insert(*new_peer_id, user_id);
+                }
+                None
+            }
+            project::Event::LanguageServerAdded(server_id) => {
+                if let Some((log, server)) = shared_project
+                    .log
+                    .clone()
+                    .zip(project.read(cx).language_server_for_id(*server_id))
+                {
+                    log.log(format!(
+                        "language server {} ({}) started",
+                        server_id,
+                        server.name()
+                    ));
+                    let server_id = *server_id;
+                    let subscription = server.on_notification::<lsp::notification::LogMessage, _>(
+                        move |params, _| {
+                            log.log(format!("[{}] {}", server_id, params.message.trim()))
+                        },
+                    );
+                    shared_project
+                        .language_server_logs
+                        .insert(server_id, subscription);
+                }
+                None
+            }
+            project::Event::LanguageServerRemoved(server_id) => {
+                if shared_project
+                    .language_server_logs
+                    .remove(server_id)
+                    .is_some()
+                {
+                    if let Some(log) = &shared_project.log {
+                        log.log(format!("language server {} stopped", server_id));
+                    }
+                }
+                None
+            }
+            project::Event::LanguageServerLog(server_id, message) => {
+                if let Some(log) = &shared_project.log {
+                    log.log(format!("[{}] {}", server_id, message.trim()));
+                }
+                None
+            }
+            project::Event::LocalSettingsFailed { path, message, .. } => {
+                log::warn!(
+                    "keeping previous settings for {:?} in remote project {}: {}",
+                    path,
+                    remote_project_id.0,
+                    message
+                );
+                if let Some(log) = &shared_project.log {
+                    log.log(format!(
+                        "failed to load settings in {:?}: {}",
+                        path, message
+                    ));
+                }
+                cx.emit(Event::SettingsLoadFailed {
+                    remote_project_id,
+                    path: path.clone(),
+                    message: message.clone(),
+                });
+                None
+            }
+            project::Event::Notification(message) => {
+                if let Some(log) = &shared_project.log {
+                    log.log(message.clone());
+                }
+                None
+            }
+            _ => None,
+        };
+
+        if let Some((kind, peer_id, user_id)) = access {
+            let event = AccessEvent {
+                kind,
+                remote_project_id,
+                project_id: project.read(cx).remote_id(),
+                user_id,
+                peer_id,
+                timestamp: now,
+            };
+            log::info!(
+                "user {} {} remote project {}",
+                event.user_id,
+                match event.kind {
+                    AccessKind::Joined => "joined",
+                    AccessKind::Left => "left",
+                },
+                event.remote_project_id.0
+            );
+            if let Some(log) = &shared_project.log {
+                log.log(format!(
+                    "user {} {}",
+                    event.user_id,
+                    match event.kind {
+                        AccessKind::Joined => "joined",
+                        AccessKind::Left => "left",
+                    }
+                ));
+            }
+            if let Some(access_log) = &self.app_state.access_log {
+                access_log.record(event);
+            }
+        }
+
+        if let Some(status) = status {
+            self.set_project_status(remote_project_id, status);
+        }
+    }
+
+    /// Tells the server when a shared project becomes healthy, errored or
+    /// disconnected, so that people can see which projects are actually live.
+    fn set_project_status(&mut self, remote_project_id: RemoteProjectId, status: ProjectStatus) {
+        let Some(shared_project) = self.projects.get_mut(&remote_project_id) else {
+            return;
+        };
+        if shared_project.status == status {
+            return;
+        }
+
+        log::info!(
+            event = "project_status_changed",
+            remote_project_id = remote_project_id.0;
+            "remote project {} is now {:?}",
+            remote_project_id.0,
+            status
+        );
+        Self::send_project_status(&self.client, remote_project_id, &status);
+        shared_project.status = status;
+    }
+
+    fn send_project_status(
+        client: &Client,
+        remote_project_id: RemoteProjectId,
+        status: &ProjectStatus,
+    ) {
+        let (proto_status, error) = match status {
+            ProjectStatus::Healthy => (
+                proto::update_dev_server_project_status::Status::Healthy,
+                None,
+            ),
+            ProjectStatus::Errored(error) => (
+                proto::update_dev_server_project_status::Status::Errored,
+                Some(error.clone()),
+            ),
+            ProjectStatus::Disconnected => (
+                proto::update_dev_server_project_status::Status::Disconnected,
+                None,
+            ),
+        };
+        client
+            .send(proto::UpdateDevServerProjectStatus {
+                remote_project_id: remote_project_id.0,
+                status: proto_status as i32,
+                error,
+            })
+            .log_err();
+    }
+
     async fn maintain_connection(
         this: WeakModel<Self>,
         client: Arc<Client>,
         mut cx: AsyncAppContext,
     ) -> Result<()> {
         let mut client_status = client.status();
-
         let _ = client_status.try_recv();
-        let current_status = *client_status.borrow();
-        if current_status.is_connected() {
-            // wait for first disconnect
-            client_status.recv().await;
-        }
 
         loop {
             let Some(current_status) = client_status.recv().await else {
@@ -223,43 +1576,366 @@ impl DevServer {
             let Some(this) = this.upgrade() else {
                 return Ok(());
             };
+            this.update(&mut cx, |this, cx| {
+                this.record_connection_status(current_status);
+                this.track_reconnect_failures(current_status, cx);
+            })?;
+
+            // The client stops reconnecting once it has been signed out (for example
+            // because the dev server's token was revoked) or needs an upgrade, so
+            // there is nothing left for the dev server to do.
+            if current_status.is_signed_out() {
+                log::error!(
+                    event = "connection_terminated",
+                    status = status_name(current_status);
+                    "dev server connection terminated: {:?}",
+                    current_status
+                );
+                this.update(&mut cx, |this, cx| {
+                    this.connected_since = None;
+                    this.status_changed();
+                    cx.emit(Event::ConnectionTerminated(current_status));
+                    cx.quit();
+                })?;
+                return Ok(());
+            }
 
             if !current_status.is_connected() {
+                this.update(&mut cx, |this, cx| {
+                    this.connected_since = None;
+                    this.record_worktrees_at_disconnect(cx);
+                    this.status_changed();
+                })?;
                 continue;
             }
 
-            this.update(&mut cx, |this, cx| this.rejoin(cx))?.await?;
+            this.update(&mut cx, |this, cx| {
+                let now = this.app_state.clock.utc_now();
+                this.last_reconnect = Some(now);
+                this.connected_since = Some(now);
+                if this.has_connected {
+                    this.reconnect_count += 1;
+                }
+                this.counters.connections += 1;
+                this.has_connected = true;
+                this.status_changed();
+            })?;
+
+            while client.status().borrow().is_connected() {
+                let result = this.update(&mut cx, |this, cx| this.rejoin(cx))?.await;
+                let delay = this.update(&mut cx, |this, cx| this.rejoin_finished(result, cx))?;
+                match delay {
+                    Some(delay) => cx.background_executor().timer(delay).await,
+                    None => break,
+                }
+            }
+
+            if this.read_with(&cx, |this, _| this.shutting_down)? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Records the outcome of a rejoin, returning how long to wait before
+    /// trying again if it failed.
+    fn rejoin_finished(
+        &mut self,
+        result: Result<()>,
+        cx: &mut ModelContext<Self>,
+    ) -> Option<Duration> {
+        let error = match result {
+            Ok(()) => {
+                self.failed_rejoins = 0;
+                self.retry_failed_shares(cx);
+                return None;
+            }
+            Err(error) => error,
+        };
+
+        self.failed_rejoins += 1;
+        let policy = &self.app_state.options.reconnect_policy;
+        if policy
+            .max_retries
+            .map_or(false, |max_retries| self.failed_rejoins > max_retries)
+        {
+            log::error!(
+                "giving up after failing to rejoin the server {} times: {:?}",
+                self.failed_rejoins,
+                error
+            );
+            self.shutting_down = true;
+            cx.emit(Event::RejoinRetriesExhausted {
+                failed_attempts: self.failed_rejoins,
+            });
+            cx.quit();
+            return None;
+        }
+
+        let delay = policy.delay(self.failed_rejoins, &mut rand::thread_rng());
+        log::warn!(
+            "failed to rejoin the server, retrying in {:?}: {:?}",
+            delay,
+            error
+        );
+        Some(delay)
+    }
+
+    fn record_worktrees_at_disconnect(&mut self, cx: &AppContext) {
+        for shared_project in self.projects.values_mut() {
+            if shared_project.worktrees_at_disconnect.is_none() {
+                shared_project.worktrees_at_disconnect =
+                    Some(shared_project.project.read(cx).worktree_metadata_protos(cx));
+            }
         }
     }
 
     fn rejoin(&mut self, cx: &mut ModelContext<Self>) -> Task<Result<()>> {
-        let mut projects: HashMap<u64, Model<Project>> = HashMap::default();
+        let mut projects: HashMap<RemoteProjectId, (u64, Model<Project>)> = HashMap::default();
+        let mut reshared_projects = Vec::new();
+        let mut reshared_project_changes = Vec::new();
+        for (remote_project_id, shared_project) in &self.projects {
+            let handle = &shared_project.project;
+            let project = handle.read(cx);
+            let Some(project_id) = project.remote_id() else {
+                continue;
+            };
+            projects.insert(*remote_project_id, (project_id, handle.clone()));
+
+            let worktrees = project.worktree_metadata_protos(cx);
+            let old_worktrees = shared_project
+                .worktrees_at_disconnect
+                .as_ref()
+                .filter(|_| self.reshares_project_changes);
+            match old_worktrees {
+                Some(old_worktrees) => reshared_project_changes.push(worktree_changes(
+                    project_id,
+                    old_worktrees,
+                    worktrees,
+                )),
+                None => reshared_projects.push(proto::UpdateProject {
+                    project_id,
+                    worktrees,
+                }),
+            }
+        }
         let request = self.client.request(proto::ReconnectDevServer {
-            reshared_projects: self
-                .projects
-                .iter()
-                .flat_map(|(_, handle)| {
-                    let project = handle.read(cx);
-                    let project_id = project.remote_id()?;
-                    projects.insert(project_id, handle.clone());
-                    Some(proto::UpdateProject {
-                        project_id,
-                        worktrees: project.worktree_metadata_protos(cx),
-                    })
-                })
-                .collect(),
+            reshared_projects,
+            display_name: self.display_name.clone(),
+            reshared_project_changes,
         });
-        cx.spawn(|_, mut cx| async move {
+        cx.spawn(|this, mut cx| async move {
             let response = request.await?;
+            let mut failed_reshares = Vec::new();
+            this.update(&mut cx, |this, _| {
+                this.reshares_project_changes = response.supports_reshared_project_changes;
+                for shared_project in this.projects.values_mut() {
+                    shared_project.worktrees_at_disconnect = None;
+                }
+            })?;
 
             for reshared_project in response.reshared_projects {
-                if let Some(project) = projects.get(&reshared_project.id) {
-                    project.update(&mut cx, |project, cx| {
-                        project.reshared(reshared_project, cx).log_err();
-                    })?;
+                // Match projects by their remote project, which outlives the
+                // server's project ids, falling back to the project id for
+                // servers that don't send it.
+                let remote_project_id = match reshared_project.remote_project_id {
+                    Some(remote_project_id) => Some(RemoteProjectId(remote_project_id)),
+                    None => projects
+                        .iter()
+                        .find(|(_, (project_id, _))| *project_id == reshared_project.id)
+                        .map(|(remote_project_id, _)| *remote_project_id),
+                };
+                let Some(remote_project_id) = remote_project_id else {
+                    continue;
+                };
+                match projects.get(&remote_project_id) {
+                    Some((project_id, _)) if *project_id == reshared_project.id => {
+                        let (_, project) = projects.remove(&remote_project_id).unwrap();
+                        let result = project.update(&mut cx, |project, cx| {
+                            project.reshared_with_worktree_metadata(reshared_project, cx)
+                        })?;
+                        if let Err(error) = result {
+                            log::error!(
+                                "failed to reshare remote project {}: {:?}",
+                                remote_project_id.0,
+                                error
+                            );
+                            failed_reshares.push(remote_project_id.0);
+                        }
+                    }
+                    // We shared this remote project under a different project
+                    // id, so it is shared again below.
+                    _ => log::warn!(
+                        "server reshared unexpected project {} for remote project {}",
+                        reshared_project.id,
+                        remote_project_id.0
+                    ),
                 }
             }
+
+            // The server didn't reshare these, so they have to be shared from scratch.
+            if !projects.is_empty() {
+                this.update(&mut cx, |this, cx| {
+                    let remote_project_ids = projects.into_keys().collect::<Vec<_>>();
+                    this.reshare_projects(&remote_project_ids, cx)
+                })??;
+            }
+
+            // Every other project was reshared, but rejoin again so that the
+            // ones that failed are retried.
+            if !failed_reshares.is_empty() {
+                return Err(anyhow!(
+                    "failed to reshare remote projects {:?}",
+                    failed_reshares
+                ));
+            }
             Ok(())
         })
     }
+
+    /// Unshares the given projects and shares them again, for projects that
+    /// the server has lost track of.
+    fn reshare_projects(
+        &mut self,
+        remote_project_ids: &[RemoteProjectId],
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        let mut remote_projects = Vec::new();
+        for remote_project_id in remote_project_ids {
+            let Some(shared_project) = self.projects.get(remote_project_id) else {
+                continue;
+            };
+            log::warn!(
+                "remote project {} was not reshared after reconnecting, sharing it again",
+                remote_project_id.0
+            );
+            remote_projects.push(proto::RemoteProject {
+                id: remote_project_id.0,
+                path: shared_project.path.clone(),
+                priority: shared_project.priority,
+                settings: shared_project.settings.clone(),
+                ..Default::default()
+            });
+            self.unshare_project(remote_project_id, cx)?;
+            self.pending_shares.insert(*remote_project_id);
+        }
+
+        self.share_projects_in_background(remote_projects, cx);
+        Ok(())
+    }
+
+    /// Shares again the projects that failed to share, unless they have
+    /// already failed [`MAX_SHARE_ATTEMPTS`] times. Instructions that include
+    /// them share them again regardless.
+    fn retry_failed_shares(&mut self, cx: &mut ModelContext<Self>) {
+        if self.draining {
+            return;
+        }
+
+        let remote_projects = self
+            .failed_shares
+            .values()
+            .filter(|failed_share| failed_share.attempts < MAX_SHARE_ATTEMPTS)
+            .map(|failed_share| failed_share.remote_project.clone())
+            .filter(|remote_project| {
+                let remote_project_id = RemoteProjectId(remote_project.id);
+                !self.projects.contains_key(&remote_project_id)
+                    && self.pending_shares.insert(remote_project_id)
+            })
+            .collect::<Vec<_>>();
+        if remote_projects.is_empty() {
+            return;
+        }
+
+        log::info!(
+            event = "retrying_failed_shares",
+            count = remote_projects.len();
+            "retrying {} remote projects that failed to share",
+            remote_projects.len()
+        );
+        self.share_projects_in_background(remote_projects, cx);
+    }
+
+    /// Shares projects that have already been added to `pending_shares`, at
+    /// most [`MAX_CONCURRENT_RESHARES`] at a time.
+    fn share_projects_in_background(
+        &mut self,
+        remote_projects: Vec<proto::RemoteProject>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        cx.spawn(|this, cx| async move {
+            let Some(this) = this.upgrade() else {
+                return;
+            };
+            stream::iter(remote_projects.iter().map(|remote_project| {
+                let this = this.clone();
+                let mut cx = cx.clone();
+                async move {
+                    DevServer::share_project(this, remote_project, &mut cx)
+                        .await
+                        .log_err();
+                }
+            }))
+            .buffer_unordered(MAX_CONCURRENT_RESHARES)
+            .collect::<Vec<_>>()
+            .await;
+        })
+        .detach();
+    }
+}
+
+fn status_name(status: Status) -> &'static str {
+    match status {
+        Status::SignedOut => "signed_out",
+        Status::UpgradeRequired => "upgrade_required",
+        Status::Authenticating => "authenticating",
+        Status::Connecting => "connecting",
+        Status::ConnectionError => "connection_error",
+        Status::Connected { .. } => "connected",
+        Status::ConnectionLost => "connection_lost",
+        Status::Reauthenticating => "reauthenticating",
+        Status::Reconnecting => "reconnecting",
+        Status::ReconnectionError { .. } => "reconnection_error",
+    }
+}
+
+/// Removes the settings that the server provided for a project's worktrees.
+fn clear_project_settings(project: &Model<Project>, cx: &mut AppContext) {
+    let root_ids = project
+        .read(cx)
+        .worktrees()
+        .map(|worktree| worktree.entity_id().as_u64() as usize)
+        .collect::<Vec<_>>();
+    cx.update_global::<SettingsStore, _>(|store, cx| {
+        for root_id in root_ids {
+            store
+                .set_local_default_settings(root_id, None, cx)
+                .log_err();
+        }
+    });
+}
+
+/// Describes how a project's worktrees changed from `old_worktrees` to `worktrees`.
+fn worktree_changes(
+    project_id: u64,
+    old_worktrees: &[proto::WorktreeMetadata],
+    worktrees: Vec<proto::WorktreeMetadata>,
+) -> proto::ResharedProjectChanges {
+    let removed_worktrees = old_worktrees
+        .iter()
+        .filter(|old_worktree| {
+            !worktrees
+                .iter()
+                .any(|worktree| worktree.id == old_worktree.id)
+        })
+        .map(|old_worktree| old_worktree.id)
+        .collect();
+    let updated_worktrees = worktrees
+        .into_iter()
+        .filter(|worktree| !old_worktrees.contains(worktree))
+        .collect();
+    proto::ResharedProjectChanges {
+        project_id,
+        updated_worktrees,
+        removed_worktrees,
+    }
 }
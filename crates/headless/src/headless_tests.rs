@@ -0,0 +1,2644 @@
+use super::*;
+#[cfg(unix)]
+use crate::signals::{handle_reload_signals, handle_termination_signals};
+use crate::{
+    logging::{ProjectLog, MAX_PROJECT_LOG_LEN},
+    signals::{forward_interrupts, INTERRUPT_TX},
+};
+use client::test::FakeServer;
+use clock::FakeSystemClock;
+use fs::FakeFs;
+use futures::future;
+use gpui::TestAppContext;
+use language::{FakeLspAdapter, Language, LanguageConfig, LanguageMatcher};
+use node_runtime::FakeNodeRuntime;
+use parking_lot::Mutex;
+use project::project_settings::{GitGutterSetting, ProjectSettings};
+use rpc::TypedEnvelope;
+use serde_json::json;
+use settings::{SettingsLocation, SettingsStore};
+use std::path::Path;
+use util::http::FakeHttpClient;
+
+#[gpui::test]
+async fn test_status_file(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.status_file_path = Some("/status.json".into());
+    })
+    .await;
+    dev_server
+        .fs
+        .insert_tree("/remote", json!({ "a.txt": "a" }))
+        .await;
+    cx.executor().run_until_parked();
+
+    let status = dev_server.read_status_file().await;
+    assert_eq!(status.status, "connected");
+    assert_eq!(status.shared_project_count, 0);
+
+    dev_server.send_instructions(&[(1, "/remote")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().run_until_parked();
+
+    let status = dev_server.read_status_file().await;
+    assert_eq!(status.status, "connected");
+    assert_eq!(status.shared_project_count, 1);
+    assert_eq!(status.remote_project_ids, vec![1]);
+
+    dev_server.server.forbid_connections();
+    dev_server.server.disconnect();
+    cx.executor().run_until_parked();
+
+    let status = dev_server.read_status_file().await;
+    assert_ne!(status.status, "connected");
+    assert_eq!(status.remote_project_ids, vec![1]);
+}
+
+#[gpui::test]
+async fn test_unshare_idle_projects(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.idle_unshare_timeout = Some(Duration::from_secs(10 * 60));
+    })
+    .await;
+    dev_server
+        .fs
+        .insert_tree("/idle", json!({ "a.txt": "a" }))
+        .await;
+    dev_server
+        .fs
+        .insert_tree("/active", json!({ "b.txt": "b" }))
+        .await;
+
+    let events = dev_server.record_events(cx);
+
+    dev_server.send_instructions(&[(1, "/idle"), (2, "/active")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    assert_eq!(dev_server.respond_to_share(102).await, 2);
+    cx.executor().run_until_parked();
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1, 2]);
+
+    dev_server.add_collaborator(102, 1);
+    cx.executor().run_until_parked();
+
+    dev_server.clock.advance(chrono::Duration::minutes(5));
+    dev_server
+        .dev_server
+        .update(cx, |dev_server, cx| dev_server.unshare_idle_projects(cx));
+    cx.executor().run_until_parked();
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1, 2]);
+    assert!(events.lock().is_empty());
+
+    dev_server.clock.advance(chrono::Duration::minutes(6));
+    dev_server
+        .dev_server
+        .update(cx, |dev_server, cx| dev_server.unshare_idle_projects(cx));
+    cx.executor().run_until_parked();
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![2]);
+    assert_eq!(
+        &*events.lock(),
+        &[Event::IdleProjectUnshared(RemoteProjectId(1))]
+    );
+}
+
+#[gpui::test]
+async fn test_removing_and_readding_project(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    dev_server
+        .fs
+        .insert_tree("/one", json!({ "a.txt": "a" }))
+        .await;
+    dev_server
+        .fs
+        .insert_tree("/two", json!({ "b.txt": "b" }))
+        .await;
+
+    dev_server.send_instructions(&[(1, "/one")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().run_until_parked();
+    let old_project = dev_server.shared_project(1, cx);
+
+    // Remove the project and immediately add it back.
+    dev_server.send_instructions(&[]);
+    dev_server.send_instructions(&[(1, "/one")]);
+    let unshare = dev_server
+        .server
+        .receive::<proto::UnshareProject>()
+        .await
+        .unwrap();
+    assert_eq!(unshare.payload.project_id, 101);
+    assert_eq!(dev_server.respond_to_share(102).await, 1);
+    cx.executor().run_until_parked();
+
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1]);
+    let new_project = dev_server.shared_project(1, cx);
+    assert_ne!(old_project, new_project);
+    assert_eq!(
+        old_project.read_with(cx, |project, _| project.remote_id()),
+        None
+    );
+    assert_eq!(
+        new_project.read_with(cx, |project, _| project.remote_id()),
+        Some(102)
+    );
+
+    // Remove a project while it is still being shared.
+    dev_server.send_instructions(&[(1, "/one"), (2, "/two")]);
+    let request = dev_server
+        .server
+        .receive::<proto::ShareRemoteProject>()
+        .await
+        .unwrap();
+    assert_eq!(request.payload.remote_project_id, 2);
+    dev_server.send_instructions(&[(1, "/one")]);
+    cx.executor().run_until_parked();
+    dev_server.server.respond(
+        request.receipt(),
+        proto::ShareProjectResponse { project_id: 103 },
+    );
+    cx.executor().run_until_parked();
+
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1]);
+    assert!(dev_server
+        .dev_server
+        .read_with(cx, |dev_server, _| dev_server.pending_shares.is_empty()));
+}
+
+#[gpui::test]
+async fn test_overlapping_instructions(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    for path in ["/one", "/two", "/three"] {
+        dev_server
+            .fs
+            .insert_tree(path, json!({ "a.txt": "a" }))
+            .await;
+    }
+    let pending_shares = |cx: &TestAppContext| {
+        dev_server.dev_server.read_with(cx, |dev_server, _| {
+            let mut ids = dev_server
+                .pending_shares
+                .iter()
+                .map(|id| id.0)
+                .collect::<Vec<_>>();
+            ids.sort_unstable();
+            ids
+        })
+    };
+
+    dev_server.send_instructions(&[(1, "/one"), (2, "/two")]);
+    let mut requests = Vec::new();
+    for _ in 0..2 {
+        requests.push(
+            dev_server
+                .server
+                .receive::<proto::ShareRemoteProject>()
+                .await
+                .unwrap(),
+        );
+    }
+
+    // The second batch waits for the first one to finish sharing.
+    dev_server.send_instructions(&[(2, "/two"), (3, "/three")]);
+    cx.executor().run_until_parked();
+    assert_eq!(pending_shares(cx), vec![1, 2]);
+
+    for request in requests {
+        let project_id = 100 + request.payload.remote_project_id;
+        dev_server.server.respond(
+            request.receipt(),
+            proto::ShareProjectResponse { project_id },
+        );
+    }
+    cx.executor().run_until_parked();
+
+    // It then sees the projects that the first batch shared.
+    let unshare = dev_server
+        .server
+        .receive::<proto::UnshareProject>()
+        .await
+        .unwrap();
+    assert_eq!(unshare.payload.project_id, 101);
+    assert_eq!(dev_server.respond_to_share(103).await, 3);
+    cx.executor().run_until_parked();
+
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![2, 3]);
+    assert_eq!(pending_shares(cx), Vec::<u64>::new());
+    assert_eq!(
+        dev_server
+            .shared_project(2, cx)
+            .read_with(cx, |project, _| project.remote_id()),
+        Some(102)
+    );
+}
+
+#[gpui::test]
+async fn test_slow_project_does_not_block_others(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    dev_server
+        .fs
+        .insert_tree("/slow", json!({ "a.txt": "a" }))
+        .await;
+    dev_server
+        .fs
+        .insert_tree("/fast", json!({ "b.txt": "b" }))
+        .await;
+    dev_server
+        .fs
+        .set_metadata_delay("/slow", SHARE_FS_TIMEOUT * 2);
+
+    dev_server.send_instructions(&[(1, "/slow"), (2, "/fast")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 2);
+    cx.executor().run_until_parked();
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![2]);
+
+    cx.executor().advance_clock(SHARE_FS_TIMEOUT);
+    cx.executor().run_until_parked();
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![2]);
+    assert!(dev_server
+        .dev_server
+        .read_with(cx, |dev_server, _| dev_server.pending_shares.is_empty()));
+}
+
+#[gpui::test]
+async fn test_failed_shares_retried_after_reconnecting(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    dev_server
+        .fs
+        .insert_tree("/slow", json!({ "a.txt": "a" }))
+        .await;
+    dev_server
+        .fs
+        .insert_tree("/fast", json!({ "b.txt": "b" }))
+        .await;
+    dev_server
+        .fs
+        .set_metadata_delay("/slow", SHARE_FS_TIMEOUT * 2);
+    let failed_shares = |cx: &TestAppContext| {
+        dev_server.dev_server.read_with(cx, |dev_server, _| {
+            dev_server
+                .failed_shares
+                .iter()
+                .map(|(id, failed_share)| (id.0, failed_share.attempts))
+                .collect::<Vec<_>>()
+        })
+    };
+
+    dev_server.send_instructions(&[(1, "/slow"), (2, "/fast")]);
+    assert_eq!(dev_server.respond_to_share(102).await, 2);
+    cx.executor().advance_clock(SHARE_FS_TIMEOUT);
+    cx.executor().run_until_parked();
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![2]);
+    assert_eq!(failed_shares(cx), vec![(1, 1)]);
+
+    // Once the mount recovers, reconnecting shares the project that failed.
+    dev_server.fs.set_metadata_delay("/slow", Duration::ZERO);
+    dev_server.server.disconnect();
+    let request = dev_server
+        .server
+        .receive::<proto::ReconnectDevServer>()
+        .await
+        .unwrap();
+    dev_server.server.respond(
+        request.receipt(),
+        proto::ReconnectDevServerResponse {
+            reshared_projects: vec![proto::ResharedProject {
+                id: 102,
+                collaborators: Vec::new(),
+                remote_project_id: Some(2),
+            }],
+            ..Default::default()
+        },
+    );
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().run_until_parked();
+
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1, 2]);
+    assert_eq!(failed_shares(cx), Vec::new());
+}
+
+#[gpui::test]
+async fn test_transactional_instructions(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.transactional_instructions = true;
+    })
+    .await;
+    let events = dev_server.record_events(cx);
+    for path in ["/one", "/two"] {
+        dev_server
+            .fs
+            .insert_tree(path, json!({ "a.txt": "a" }))
+            .await;
+    }
+
+    dev_server.send_instructions(&[(1, "/one")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().run_until_parked();
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1]);
+
+    // The second share fails because its path doesn't exist, so the first is
+    // unshared again and the removed project stays shared.
+    dev_server.send_instructions(&[(2, "/two"), (3, "/missing")]);
+    assert_eq!(dev_server.respond_to_share(102).await, 2);
+    let unshare = dev_server
+        .server
+        .receive::<proto::UnshareProject>()
+        .await
+        .unwrap();
+    assert_eq!(unshare.payload.project_id, 102);
+    cx.executor().run_until_parked();
+
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1]);
+    dev_server.dev_server.read_with(cx, |dev_server, _| {
+        assert_eq!(
+            dev_server.desired_projects,
+            HashSet::from_iter([RemoteProjectId(1)])
+        );
+        assert!(dev_server.failed_shares.is_empty());
+    });
+    assert_eq!(
+        events.lock().as_slice(),
+        [Event::InstructionsRolledBack(vec![RemoteProjectId(2)])]
+    );
+
+    // A batch that succeeds is applied in full.
+    dev_server.send_instructions(&[(2, "/two")]);
+    assert_eq!(dev_server.respond_to_share(102).await, 2);
+    let unshare = dev_server
+        .server
+        .receive::<proto::UnshareProject>()
+        .await
+        .unwrap();
+    assert_eq!(unshare.payload.project_id, 101);
+    cx.executor().run_until_parked();
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![2]);
+}
+
+#[gpui::test]
+async fn test_drain(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.drain_timeout = Some(Duration::from_secs(30));
+    })
+    .await;
+    let events = dev_server.record_events(cx);
+    for path in ["/one", "/two", "/three"] {
+        dev_server
+            .fs
+            .insert_tree(path, json!({ "a.txt": "a" }))
+            .await;
+    }
+    dev_server.send_instructions(&[(1, "/one"), (2, "/two")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    assert_eq!(dev_server.respond_to_share(102).await, 2);
+    cx.executor().run_until_parked();
+
+    // While draining, removed projects are unshared but new ones aren't shared.
+    dev_server
+        .dev_server
+        .update(cx, |dev_server, cx| dev_server.drain(cx));
+    dev_server.send_instructions(&[(1, "/one"), (3, "/three")]);
+    let unshare = dev_server
+        .server
+        .receive::<proto::UnshareProject>()
+        .await
+        .unwrap();
+    assert_eq!(unshare.payload.project_id, 102);
+    cx.executor().run_until_parked();
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1]);
+    assert!(!dev_server
+        .dev_server
+        .read_with(cx, |dev_server, _| dev_server.readiness().ok));
+
+    // Once interrupted, it waits for the remaining project to be unshared.
+    dev_server
+        .dev_server
+        .update(cx, |dev_server, cx| dev_server.interrupted(cx));
+    cx.executor().advance_clock(Duration::from_secs(10));
+    cx.executor().run_until_parked();
+    assert_eq!(
+        events.lock().as_slice(),
+        [Event::Draining, Event::Interrupted]
+    );
+
+    dev_server.send_instructions(&[]);
+    let unshare = dev_server
+        .server
+        .receive::<proto::UnshareProject>()
+        .await
+        .unwrap();
+    assert_eq!(unshare.payload.project_id, 101);
+    cx.executor().run_until_parked();
+    assert_eq!(
+        events.lock().as_slice(),
+        [
+            Event::Draining,
+            Event::Interrupted,
+            Event::Drained { timed_out: false }
+        ]
+    );
+}
+
+#[gpui::test]
+async fn test_drain_timeout(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.drain_timeout = Some(Duration::from_secs(30));
+    })
+    .await;
+    let events = dev_server.record_events(cx);
+    dev_server
+        .fs
+        .insert_tree("/one", json!({ "a.txt": "a" }))
+        .await;
+    dev_server.send_instructions(&[(1, "/one")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().run_until_parked();
+
+    dev_server.dev_server.update(cx, |dev_server, cx| {
+        dev_server.drain(cx);
+        dev_server.interrupted(cx);
+    });
+    cx.executor().advance_clock(Duration::from_secs(30));
+    cx.executor().run_until_parked();
+    assert_eq!(
+        events.lock().as_slice(),
+        [
+            Event::Draining,
+            Event::Interrupted,
+            Event::Drained { timed_out: true }
+        ]
+    );
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1]);
+}
+
+#[gpui::test]
+async fn test_reconnecting_after_disconnect(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    let events = dev_server.record_events(cx);
+
+    // A transient disconnect is followed by a reconnect and a rejoin.
+    dev_server.server.disconnect();
+    cx.executor().run_until_parked();
+    let request = dev_server
+        .server
+        .receive::<proto::ReconnectDevServer>()
+        .await
+        .unwrap();
+    dev_server.server.respond(
+        request.receipt(),
+        proto::ReconnectDevServerResponse {
+            reshared_projects: Vec::new(),
+            ..Default::default()
+        },
+    );
+    cx.executor().run_until_parked();
+    assert!(events.lock().is_empty());
+
+    // Once the dev server's token is revoked, it stops reconnecting.
+    dev_server
+        .client
+        .set_dev_server_token(client::DevServerToken("revoked".into()));
+    dev_server.server.disconnect();
+    cx.executor().run_until_parked();
+    assert!(dev_server.client.status().borrow().is_signed_out());
+    assert_eq!(
+        &*events.lock(),
+        &[Event::ConnectionTerminated(Status::SignedOut)]
+    );
+
+    cx.executor().advance_clock(Duration::from_secs(60));
+    cx.executor().run_until_parked();
+    assert!(dev_server.client.status().borrow().is_signed_out());
+}
+
+#[gpui::test]
+async fn test_display_name(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.display_name = Some("build-box-1".into());
+    })
+    .await;
+    assert_eq!(
+        dev_server.reconnect().await.payload.display_name.as_deref(),
+        Some("build-box-1")
+    );
+}
+
+#[gpui::test]
+async fn test_display_name_defaults_to_hostname(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    assert_eq!(
+        dev_server.reconnect().await.payload.display_name,
+        System::host_name()
+    );
+}
+
+#[gpui::test]
+async fn test_partially_reshared_projects(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    dev_server
+        .fs
+        .insert_tree("/one", json!({ "a.txt": "a" }))
+        .await;
+    dev_server
+        .fs
+        .insert_tree("/two", json!({ "b.txt": "b" }))
+        .await;
+
+    dev_server.send_instructions(&[(1, "/one")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    dev_server.send_instructions(&[(1, "/one"), (2, "/two")]);
+    assert_eq!(dev_server.respond_to_share(102).await, 2);
+    cx.executor().run_until_parked();
+
+    // The server only reshares one of the two projects.
+    dev_server.server.disconnect();
+    cx.executor().run_until_parked();
+    let request = dev_server
+        .server
+        .receive::<proto::ReconnectDevServer>()
+        .await
+        .unwrap();
+    assert_eq!(request.payload.reshared_projects.len(), 2);
+    dev_server.server.respond(
+        request.receipt(),
+        proto::ReconnectDevServerResponse {
+            reshared_projects: vec![proto::ResharedProject {
+                id: 101,
+                collaborators: Vec::new(),
+                remote_project_id: None,
+            }],
+            ..Default::default()
+        },
+    );
+    cx.executor().run_until_parked();
+
+    // The other one is unshared and shared again.
+    let unshare = dev_server
+        .server
+        .receive::<proto::UnshareProject>()
+        .await
+        .unwrap();
+    assert_eq!(unshare.payload.project_id, 102);
+    assert_eq!(dev_server.respond_to_share(103).await, 2);
+    cx.executor().run_until_parked();
+
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1, 2]);
+    assert_eq!(
+        dev_server
+            .shared_project(1, cx)
+            .read_with(cx, |project, _| project.remote_id()),
+        Some(101)
+    );
+    assert_eq!(
+        dev_server
+            .shared_project(2, cx)
+            .read_with(cx, |project, _| project.remote_id()),
+        Some(103)
+    );
+}
+
+#[gpui::test]
+async fn test_rejoin_after_server_restart(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    for path in ["/one", "/two", "/three"] {
+        dev_server
+            .fs
+            .insert_tree(path, json!({ "a.txt": "a" }))
+            .await;
+    }
+
+    dev_server.send_instructions(&[(1, "/one")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    dev_server.send_instructions(&[(1, "/one"), (2, "/two")]);
+    assert_eq!(dev_server.respond_to_share(102).await, 2);
+    dev_server.send_instructions(&[(1, "/one"), (2, "/two"), (3, "/three")]);
+    assert_eq!(dev_server.respond_to_share(103).await, 3);
+    cx.executor().run_until_parked();
+
+    // After restarting, the server has forgotten project 102 and handed 101
+    // to remote project 2. Only remote project 3 kept its project id.
+    dev_server.server.disconnect();
+    let request = dev_server
+        .server
+        .receive::<proto::ReconnectDevServer>()
+        .await
+        .unwrap();
+    dev_server.server.respond(
+        request.receipt(),
+        proto::ReconnectDevServerResponse {
+            reshared_projects: vec![
+                proto::ResharedProject {
+                    id: 101,
+                    collaborators: Vec::new(),
+                    remote_project_id: Some(2),
+                },
+                proto::ResharedProject {
+                    id: 103,
+                    collaborators: Vec::new(),
+                    remote_project_id: Some(3),
+                },
+            ],
+            ..Default::default()
+        },
+    );
+    cx.executor().run_until_parked();
+
+    let mut unshared_project_ids = Vec::new();
+    for _ in 0..2 {
+        let unshare = dev_server
+            .server
+            .receive::<proto::UnshareProject>()
+            .await
+            .unwrap();
+        unshared_project_ids.push(unshare.payload.project_id);
+    }
+    unshared_project_ids.sort_unstable();
+    assert_eq!(unshared_project_ids, vec![101, 102]);
+    for _ in 0..2 {
+        let request = dev_server
+            .server
+            .receive::<proto::ShareRemoteProject>()
+            .await
+            .unwrap();
+        let project_id = 200 + request.payload.remote_project_id;
+        dev_server.server.respond(
+            request.receipt(),
+            proto::ShareProjectResponse { project_id },
+        );
+    }
+    cx.executor().run_until_parked();
+
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1, 2, 3]);
+    let project_id = |remote_project_id| {
+        dev_server
+            .shared_project(remote_project_id, cx)
+            .read_with(cx, |project, _| project.remote_id())
+    };
+    assert_eq!(project_id(1), Some(201));
+    assert_eq!(project_id(2), Some(202));
+    assert_eq!(project_id(3), Some(103));
+}
+
+#[gpui::test]
+async fn test_reshared_project_changes(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    for path in ["/one", "/two"] {
+        dev_server
+            .fs
+            .insert_tree(path, json!({ "a.txt": "a" }))
+            .await;
+    }
+    dev_server.send_instructions(&[(1, "/one")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().run_until_parked();
+    let project = dev_server.shared_project(1, cx);
+    let worktrees = |cx: &TestAppContext| {
+        project.read_with(cx, |project, cx| project.worktree_metadata_protos(cx))
+    };
+    let respond = |request: TypedEnvelope<proto::ReconnectDevServer>| {
+        dev_server.server.respond(
+            request.receipt(),
+            proto::ReconnectDevServerResponse {
+                reshared_projects: vec![proto::ResharedProject {
+                    id: 101,
+                    collaborators: Vec::new(),
+                    remote_project_id: Some(1),
+                }],
+                supports_reshared_project_changes: true,
+            },
+        );
+    };
+
+    // Until the server says that it accepts changes, every worktree is sent.
+    dev_server.server.disconnect();
+    let request = dev_server
+        .server
+        .receive::<proto::ReconnectDevServer>()
+        .await
+        .unwrap();
+    assert_eq!(
+        request.payload.reshared_projects,
+        vec![proto::UpdateProject {
+            project_id: 101,
+            worktrees: worktrees(cx),
+        }]
+    );
+    assert_eq!(request.payload.reshared_project_changes, Vec::new());
+    respond(request);
+    cx.executor().run_until_parked();
+
+    // Afterwards, only the worktrees that changed while the dev server was
+    // disconnected are sent.
+    dev_server.server.forbid_connections();
+    dev_server.server.disconnect();
+    cx.executor().run_until_parked();
+    project
+        .update(cx, |project, cx| {
+            project.find_or_create_local_worktree("/two", true, cx)
+        })
+        .await
+        .unwrap();
+    dev_server.server.allow_connections();
+    cx.executor().advance_clock(Duration::from_secs(10));
+    cx.executor().run_until_parked();
+    let request = dev_server
+        .server
+        .receive::<proto::ReconnectDevServer>()
+        .await
+        .unwrap();
+    let added_worktree = worktrees(cx)
+        .into_iter()
+        .find(|worktree| worktree.root_name == "two")
+        .unwrap();
+    assert_eq!(request.payload.reshared_projects, Vec::new());
+    assert_eq!(
+        request.payload.reshared_project_changes,
+        vec![proto::ResharedProjectChanges {
+            project_id: 101,
+            updated_worktrees: vec![added_worktree],
+            removed_worktrees: Vec::new(),
+        }]
+    );
+    respond(request);
+    cx.executor().run_until_parked();
+
+    // Nothing changed since the last reconnect.
+    dev_server.server.disconnect();
+    let request = dev_server
+        .server
+        .receive::<proto::ReconnectDevServer>()
+        .await
+        .unwrap();
+    assert_eq!(
+        request.payload.reshared_project_changes,
+        vec![proto::ResharedProjectChanges {
+            project_id: 101,
+            updated_worktrees: Vec::new(),
+            removed_worktrees: Vec::new(),
+        }]
+    );
+    respond(request);
+    cx.executor().run_until_parked();
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1]);
+}
+
+#[gpui::test]
+async fn test_failed_reshare(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    for path in ["/one", "/two"] {
+        dev_server
+            .fs
+            .insert_tree(path, json!({ "a.txt": "a" }))
+            .await;
+    }
+    dev_server.send_instructions(&[(1, "/one"), (2, "/two")]);
+    for _ in 0..2 {
+        let request = dev_server
+            .server
+            .receive::<proto::ShareRemoteProject>()
+            .await
+            .unwrap();
+        let project_id = 100 + request.payload.remote_project_id;
+        dev_server.server.respond(
+            request.receipt(),
+            proto::ShareProjectResponse { project_id },
+        );
+    }
+    cx.executor().run_until_parked();
+    let failed_rejoins = |cx: &TestAppContext| {
+        dev_server
+            .dev_server
+            .read_with(cx, |dev_server, _| dev_server.failed_rejoins)
+    };
+    let collaborator = |peer_id| proto::Collaborator {
+        peer_id,
+        replica_id: 1,
+        user_id: 2,
+    };
+    let peer_id = Some(proto::PeerId { owner_id: 2, id: 1 });
+
+    // Resharing the first project fails because its collaborator is invalid.
+    dev_server.server.disconnect();
+    let request = dev_server
+        .server
+        .receive::<proto::ReconnectDevServer>()
+        .await
+        .unwrap();
+    dev_server.server.respond(
+        request.receipt(),
+        proto::ReconnectDevServerResponse {
+            reshared_projects: vec![
+                proto::ResharedProject {
+                    id: 101,
+                    collaborators: vec![collaborator(None)],
+                    remote_project_id: Some(1),
+                },
+                proto::ResharedProject {
+                    id: 102,
+                    collaborators: vec![collaborator(peer_id)],
+                    remote_project_id: Some(2),
+                },
+            ],
+            ..Default::default()
+        },
+    );
+    cx.executor().run_until_parked();
+
+    // The second project is still reshared, and rejoining is retried.
+    assert_eq!(failed_rejoins(cx), 1);
+    assert_eq!(
+        dev_server
+            .shared_project(2, cx)
+            .read_with(cx, |project, _| project.collaborators().len()),
+        1
+    );
+    cx.executor().advance_clock(Duration::from_secs(2));
+    let request = dev_server
+        .server
+        .receive::<proto::ReconnectDevServer>()
+        .await
+        .unwrap();
+    assert_eq!(request.payload.reshared_projects.len(), 2);
+    dev_server.server.respond(
+        request.receipt(),
+        proto::ReconnectDevServerResponse {
+            reshared_projects: vec![
+                proto::ResharedProject {
+                    id: 101,
+                    collaborators: vec![collaborator(peer_id)],
+                    remote_project_id: Some(1),
+                },
+                proto::ResharedProject {
+                    id: 102,
+                    collaborators: vec![collaborator(peer_id)],
+                    remote_project_id: Some(2),
+                },
+            ],
+            ..Default::default()
+        },
+    );
+    cx.executor().run_until_parked();
+    assert_eq!(failed_rejoins(cx), 0);
+    assert_eq!(
+        dev_server
+            .shared_project(1, cx)
+            .read_with(cx, |project, _| project.collaborators().len()),
+        1
+    );
+}
+
+#[gpui::test]
+async fn test_rejoin_backoff(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.reconnect_policy = ReconnectPolicy {
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(3),
+            multiplier: 2.,
+            jitter: 0.,
+            max_retries: Some(3),
+        };
+    })
+    .await;
+    let events = dev_server.record_events(cx);
+    let failed_rejoins = |cx: &TestAppContext| {
+        dev_server
+            .dev_server
+            .read_with(cx, |dev_server, _| dev_server.failed_rejoins)
+    };
+    let fail_rejoin = || async {
+        let request = dev_server
+            .server
+            .receive::<proto::ReconnectDevServer>()
+            .await
+            .unwrap();
+        dev_server.server.respond_with_error(
+            request.receipt(),
+            proto::Error {
+                message: "not now".into(),
+                ..Default::default()
+            },
+        );
+    };
+
+    // A successful rejoin resets the delay.
+    dev_server.server.disconnect();
+    fail_rejoin().await;
+    cx.executor().run_until_parked();
+    assert_eq!(failed_rejoins(cx), 1);
+    cx.executor().advance_clock(Duration::from_secs(1));
+    let request = dev_server
+        .server
+        .receive::<proto::ReconnectDevServer>()
+        .await
+        .unwrap();
+    dev_server.server.respond(
+        request.receipt(),
+        proto::ReconnectDevServerResponse {
+            reshared_projects: Vec::new(),
+            ..Default::default()
+        },
+    );
+    cx.executor().run_until_parked();
+    assert_eq!(failed_rejoins(cx), 0);
+
+    // Each failure doubles the delay, up to the maximum, until the dev
+    // server gives up.
+    dev_server.server.disconnect();
+    fail_rejoin().await;
+    cx.executor().run_until_parked();
+    cx.executor().advance_clock(Duration::from_secs(1));
+    fail_rejoin().await;
+    cx.executor().run_until_parked();
+    cx.executor().advance_clock(Duration::from_secs(2));
+    fail_rejoin().await;
+    cx.executor().run_until_parked();
+    assert_eq!(failed_rejoins(cx), 3);
+    assert!(events.lock().is_empty());
+    cx.executor().advance_clock(Duration::from_secs(3));
+    fail_rejoin().await;
+    cx.executor().run_until_parked();
+
+    assert_eq!(failed_rejoins(cx), 4);
+    assert_eq!(
+        events.lock().as_slice(),
+        [Event::RejoinRetriesExhausted { failed_attempts: 4 }]
+    );
+}
+
+#[test]
+fn test_reconnect_policy_delay() {
+    let policy = ReconnectPolicy {
+        base_delay: Duration::from_secs(1),
+        max_delay: Duration::from_secs(10),
+        multiplier: 3.,
+        jitter: 0.,
+        max_retries: None,
+    };
+    let mut rng = rand::thread_rng();
+    let delays = (1..=4)
+        .map(|attempts| policy.delay(attempts, &mut rng))
+        .collect::<Vec<_>>();
+    assert_eq!(delays, [1, 3, 9, 10].map(Duration::from_secs));
+
+    let policy = ReconnectPolicy {
+        jitter: 0.5,
+        ..policy
+    };
+    for _ in 0..100 {
+        let delay = policy.delay(2, &mut rng);
+        assert!(
+            delay >= Duration::from_millis(1500) && delay <= Duration::from_millis(4500),
+            "{delay:?}"
+        );
+    }
+}
+
+#[gpui::test]
+async fn test_reconnect_alert(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.reconnect_alert = Some(ReconnectAlert {
+            failed_attempts: 3,
+            webhook_url: None,
+        });
+    })
+    .await;
+    let events = dev_server.record_events(cx);
+    let failed_reconnects = |cx: &TestAppContext| {
+        dev_server
+            .dev_server
+            .read_with(cx, |dev_server, _| dev_server.failed_reconnects)
+    };
+
+    dev_server.server.forbid_connections();
+    dev_server.server.disconnect();
+    cx.executor().run_until_parked();
+    while failed_reconnects(cx) < 4 {
+        cx.executor().advance_clock(Duration::from_secs(10));
+        cx.executor().run_until_parked();
+    }
+    assert_eq!(failed_reconnects(cx), 4);
+    assert_eq!(
+        &*events.lock(),
+        &[Event::ConnectionDegraded {
+            failed_reconnects: 3
+        }]
+    );
+
+    dev_server.server.allow_connections();
+    cx.executor().advance_clock(Duration::from_secs(10));
+    cx.executor().run_until_parked();
+    let request = dev_server
+        .server
+        .receive::<proto::ReconnectDevServer>()
+        .await
+        .unwrap();
+    dev_server.server.respond(
+        request.receipt(),
+        proto::ReconnectDevServerResponse {
+            reshared_projects: Vec::new(),
+            ..Default::default()
+        },
+    );
+    cx.executor().run_until_parked();
+    assert_eq!(failed_reconnects(cx), 0);
+    assert_eq!(
+        &*events.lock(),
+        &[
+            Event::ConnectionDegraded {
+                failed_reconnects: 3
+            },
+            Event::ConnectionRecovered,
+        ]
+    );
+}
+
+#[gpui::test]
+async fn test_orphaned_managed_dirs(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.managed_dirs = Some(ManagedDirs {
+            root: "/managed".into(),
+            stale_after: Duration::from_secs(60 * 60),
+        });
+    })
+    .await;
+    dev_server
+        .fs
+        .insert_tree(
+            "/managed",
+            json!({
+                "referenced": { "a.txt": "a" },
+                "orphaned": { "b.txt": "b" },
+            }),
+        )
+        .await;
+
+    dev_server.send_instructions(&[(1, "/managed/referenced")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().run_until_parked();
+
+    assert!(dev_server.fs.is_dir(Path::new("/managed/referenced")).await);
+    assert!(!dev_server.fs.is_dir(Path::new("/managed/orphaned")).await);
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1]);
+
+    // Later instructions don't clean up again.
+    dev_server
+        .fs
+        .insert_tree("/managed/orphaned", json!({ "b.txt": "b" }))
+        .await;
+    dev_server.send_instructions(&[(1, "/managed/referenced")]);
+    cx.executor().run_until_parked();
+    assert!(dev_server.fs.is_dir(Path::new("/managed/orphaned")).await);
+}
+
+#[gpui::test]
+async fn test_share_manifest(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    cx.update_global::<SettingsStore, _>(|store, cx| {
+        store.update_user_settings::<WorktreeSettings>(cx, |settings| {
+            settings.file_scan_exclusions = Some(vec!["**/target".into()]);
+        });
+    });
+    dev_server
+        .fs
+        .insert_tree("/code/one", json!({ "a.txt": "a" }))
+        .await;
+    dev_server
+        .fs
+        .insert_tree("/code/two", json!({ "b.txt": "b" }))
+        .await;
+
+    dev_server.send_instructions(&[(1, "/code/one"), (2, "/code/two")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    assert_eq!(dev_server.respond_to_share(102).await, 2);
+    cx.executor().run_until_parked();
+
+    let manifest = dev_server
+        .dev_server
+        .read_with(cx, |dev_server, cx| dev_server.share_manifest(cx));
+    assert_eq!(manifest.projects.len(), 2);
+    for (project, (remote_project_id, project_id, path, root_name)) in manifest
+        .projects
+        .iter()
+        .zip([(1, 101, "/code/one", "one"), (2, 102, "/code/two", "two")])
+    {
+        assert_eq!(project.remote_project_id, remote_project_id);
+        assert_eq!(project.project_id, Some(project_id));
+        assert_eq!(project.path, path);
+        assert_eq!(project.worktrees.len(), 1);
+        assert_eq!(project.worktrees[0].root_name, root_name);
+        assert_eq!(project.worktrees[0].abs_path, Path::new(path));
+        assert_eq!(project.worktrees[0].file_scan_exclusions, vec!["**/target"]);
+    }
+
+    dev_server
+        .dev_server
+        .read_with(cx, |dev_server, cx| {
+            dev_server.write_share_manifest("/manifest.json".into(), cx)
+        })
+        .await
+        .unwrap();
+    let contents = dev_server
+        .fs
+        .load(Path::new("/manifest.json"))
+        .await
+        .unwrap();
+    assert_eq!(
+        serde_json::from_str::<ShareManifest>(&contents).unwrap(),
+        manifest
+    );
+}
+
+#[gpui::test]
+async fn test_file_scan_exclusions(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.file_scan_exclusions =
+            Some(vec!["**/node_modules".into(), "**/target".into()]);
+    })
+    .await;
+    dev_server
+        .fs
+        .insert_tree(
+            "/monorepo",
+            json!({
+                "node_modules": { "left-pad": { "index.js": "" } },
+                "target": { "debug": { "app": "" } },
+                "web": {
+                    "node_modules": { "react": { "index.js": "" } },
+                    "index.ts": "",
+                },
+                "src": { "main.rs": "" },
+            }),
+        )
+        .await;
+
+    dev_server.send_instructions(&[(1, "/monorepo")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().run_until_parked();
+
+    let paths = dev_server
+        .shared_project(1, cx)
+        .read_with(cx, |project, cx| {
+            let worktree = project.worktrees().next().unwrap().read(cx);
+            worktree
+                .entries(true)
+                .map(|entry| entry.path.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        });
+    assert_eq!(paths, ["", "src", "src/main.rs", "web", "web/index.ts"]);
+
+    // The exclusions are added to the ones from the settings.
+    let manifest = dev_server
+        .dev_server
+        .read_with(cx, |dev_server, cx| dev_server.share_manifest(cx));
+    let file_scan_exclusions = &manifest.projects[0].worktrees[0].file_scan_exclusions;
+    assert!(file_scan_exclusions.contains(&"**/.git".to_string()));
+    assert!(file_scan_exclusions.contains(&"**/node_modules".to_string()));
+    assert!(file_scan_exclusions.contains(&"**/target".to_string()));
+
+    // They are kept when the settings change.
+    cx.update_global::<SettingsStore, _>(|store, cx| {
+        store.update_user_settings::<WorktreeSettings>(cx, |settings| {
+            settings.file_scan_exclusions = Some(vec!["**/dist".into()]);
+        });
+    });
+    let manifest = dev_server
+        .dev_server
+        .read_with(cx, |dev_server, cx| dev_server.share_manifest(cx));
+    assert_eq!(
+        manifest.projects[0].worktrees[0].file_scan_exclusions,
+        vec!["**/dist", "**/node_modules", "**/target"]
+    );
+}
+
+#[gpui::test]
+async fn test_paginated_instructions(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    dev_server
+        .fs
+        .insert_tree("/one", json!({ "a.txt": "a" }))
+        .await;
+    dev_server
+        .fs
+        .insert_tree("/two", json!({ "b.txt": "b" }))
+        .await;
+
+    dev_server.server.send(proto::DevServerInstructions {
+        projects: vec![proto::RemoteProject {
+            id: 1,
+            path: "/one".into(),
+            ..Default::default()
+        }],
+        page: 0,
+        page_count: 2,
+    });
+    cx.executor().run_until_parked();
+    dev_server.dev_server.read_with(cx, |dev_server, _| {
+        assert!(dev_server.projects.is_empty());
+        assert!(dev_server.pending_shares.is_empty());
+    });
+
+    dev_server.server.send(proto::DevServerInstructions {
+        projects: vec![proto::RemoteProject {
+            id: 2,
+            path: "/two".into(),
+            ..Default::default()
+        }],
+        page: 1,
+        page_count: 2,
+    });
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    assert_eq!(dev_server.respond_to_share(102).await, 2);
+    cx.executor().run_until_parked();
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1, 2]);
+}
+
+#[gpui::test]
+async fn test_instruction_debounce(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.instruction_debounce = Some(Duration::from_millis(250));
+    })
+    .await;
+    for path in ["/one", "/two", "/three"] {
+        dev_server
+            .fs
+            .insert_tree(path, json!({ "a.txt": "a" }))
+            .await;
+    }
+    let pending_shares = |cx: &TestAppContext| {
+        dev_server
+            .dev_server
+            .read_with(cx, |dev_server, _| dev_server.pending_shares.len())
+    };
+
+    // Instructions sent in quick succession are applied once, after the
+    // last of them.
+    dev_server.send_instructions(&[(1, "/one")]);
+    cx.executor().advance_clock(Duration::from_millis(100));
+    dev_server.send_instructions(&[(1, "/one"), (2, "/two")]);
+    cx.executor().advance_clock(Duration::from_millis(100));
+    dev_server.send_instructions(&[(3, "/three")]);
+    cx.executor().advance_clock(Duration::from_millis(100));
+    cx.executor().run_until_parked();
+    assert_eq!(pending_shares(cx), 0);
+    assert_eq!(dev_server.shared_remote_project_ids(cx), Vec::<u64>::new());
+
+    cx.executor().advance_clock(Duration::from_millis(150));
+    assert_eq!(dev_server.respond_to_share(103).await, 3);
+    cx.executor().run_until_parked();
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![3]);
+    assert_eq!(pending_shares(cx), 0);
+}
+
+#[gpui::test]
+async fn test_dry_run(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.dry_run = true;
+    })
+    .await;
+    dev_server
+        .fs
+        .insert_tree(
+            "/",
+            json!({
+                "one": { "a.txt": "a" },
+                "file.txt": "b",
+            }),
+        )
+        .await;
+
+    dev_server.send_instructions(&[(1, "/one"), (2, "/file.txt"), (3, "/missing")]);
+    let mut statuses = Vec::new();
+    for _ in 0..3 {
+        let status = dev_server
+            .server
+            .receive::<proto::UpdateDevServerProjectStatus>()
+            .await
+            .unwrap();
+        statuses.push((
+            status.payload.remote_project_id,
+            status.payload.status(),
+            status.payload.error,
+        ));
+    }
+    assert_eq!(
+        statuses,
+        vec![
+            (
+                1,
+                proto::update_dev_server_project_status::Status::Healthy,
+                None
+            ),
+            (
+                2,
+                proto::update_dev_server_project_status::Status::Errored,
+                Some("/file.txt is not a directory".into())
+            ),
+            (
+                3,
+                proto::update_dev_server_project_status::Status::Errored,
+                Some("/missing does not exist".into())
+            ),
+        ]
+    );
+
+    // Nothing is shared.
+    cx.executor().run_until_parked();
+    assert_eq!(dev_server.shared_remote_project_ids(cx), Vec::<u64>::new());
+    assert!(dev_server
+        .dev_server
+        .read_with(cx, |dev_server, _| dev_server.pending_shares.is_empty()));
+}
+
+#[gpui::test]
+async fn test_access_log(cx: &mut TestAppContext) {
+    #[derive(Default)]
+    struct TestAccessLog(Mutex<Vec<AccessEvent>>);
+
+    impl AccessLog for TestAccessLog {
+        fn record(&self, event: AccessEvent) {
+            self.0.lock().push(event);
+        }
+    }
+
+    let access_log = Arc::new(TestAccessLog::default());
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.access_log = Some(access_log.clone());
+    })
+    .await;
+    dev_server
+        .fs
+        .insert_tree("/remote", json!({ "a.txt": "a" }))
+        .await;
+
+    dev_server.send_instructions(&[(1, "/remote")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().run_until_parked();
+
+    dev_server.add_collaborator(101, 7);
+    cx.executor().run_until_parked();
+    dev_server.server.send(proto::RemoveProjectCollaborator {
+        project_id: 101,
+        peer_id: Some(proto::PeerId { owner_id: 2, id: 7 }),
+    });
+    cx.executor().run_until_parked();
+
+    let events = access_log
+        .0
+        .lock()
+        .iter()
+        .map(|event| {
+            (
+                event.kind,
+                event.remote_project_id,
+                event.project_id,
+                event.user_id,
+                event.peer_id,
+            )
+        })
+        .collect::<Vec<_>>();
+    let peer_id = proto::PeerId { owner_id: 2, id: 7 };
+    assert_eq!(
+        events,
+        &[
+            (
+                AccessKind::Joined,
+                RemoteProjectId(1),
+                Some(101),
+                2,
+                peer_id
+            ),
+            (AccessKind::Left, RemoteProjectId(1), Some(101), 2, peer_id),
+        ]
+    );
+}
+
+#[gpui::test]
+async fn test_project_log(cx: &mut TestAppContext) {
+    let mut fake_servers = None;
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.project_log_dir = Some("/logs".into());
+        app_state.options.project_log_retention = ProjectLogRetention::Rotate;
+        app_state.languages.add(Arc::new(Language::new(
+            LanguageConfig {
+                name: "Rust".into(),
+                matcher: LanguageMatcher {
+                    path_suffixes: vec!["rs".to_string()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            None,
+        )));
+        fake_servers = Some(
+            app_state
+                .languages
+                .register_fake_lsp_adapter("Rust", FakeLspAdapter::default()),
+        );
+    })
+    .await;
+    let mut fake_servers = fake_servers.unwrap();
+    dev_server
+        .fs
+        .insert_tree("/remote", json!({ "main.rs": "fn main() {}" }))
+        .await;
+
+    dev_server.send_instructions(&[(1, "/remote")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().run_until_parked();
+
+    let project = dev_server.shared_project(1, cx);
+    let _buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/remote/main.rs", cx)
+        })
+        .await
+        .unwrap();
+    let fake_server = fake_servers.next().await.unwrap();
+    fake_server.notify::<lsp::notification::LogMessage>(lsp::LogMessageParams {
+        typ: lsp::MessageType::INFO,
+        message: "indexing complete".to_string(),
+    });
+    cx.executor().run_until_parked();
+
+    let log = dev_server.fs.load(Path::new("/logs/1.log")).await.unwrap();
+    assert!(log.contains("shared /remote as project 101"), "{log}");
+    assert!(log.contains("indexing complete"), "{log}");
+
+    dev_server.send_instructions(&[]);
+    cx.executor().run_until_parked();
+
+    assert!(!dev_server.fs.is_file(Path::new("/logs/1.log")).await);
+    let log = dev_server
+        .fs
+        .load(Path::new("/logs/1.log.old"))
+        .await
+        .unwrap();
+    assert!(log.contains("indexing complete"), "{log}");
+    assert!(log.contains("project unshared"), "{log}");
+}
+
+#[gpui::test]
+async fn test_project_log_rotation(cx: &mut TestAppContext) {
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/logs", json!({})).await;
+    let log = cx.update(|cx| {
+        ProjectLog::new(
+            fs.clone(),
+            Arc::new(FakeSystemClock::default()),
+            "/logs/1.log".into(),
+            ProjectLogRetention::Delete,
+            cx,
+        )
+    });
+
+    let line = "x".repeat(1024);
+    for batch in 0..3 {
+        for _ in 0..MAX_PROJECT_LOG_LEN / 3 / line.len() {
+            log.log(format!("{batch} {line}"));
+        }
+        cx.executor().run_until_parked();
+    }
+
+    // The first two batches fit in one file, so the log was rotated before the third.
+    let log_contents = fs.load(Path::new("/logs/1.log")).await.unwrap();
+    let rotated_contents = fs.load(Path::new("/logs/1.log.1")).await.unwrap();
+    assert!(log_contents.len() <= MAX_PROJECT_LOG_LEN);
+    assert!(log_contents.lines().all(|line| line.contains(" 2 x")));
+    assert!(rotated_contents.lines().any(|line| line.contains(" 0 x")));
+    assert!(rotated_contents.lines().any(|line| line.contains(" 1 x")));
+
+    log.close();
+    cx.executor().run_until_parked();
+    assert!(!fs.is_file(Path::new("/logs/1.log")).await);
+    assert!(!fs.is_file(Path::new("/logs/1.log.1")).await);
+}
+
+#[gpui::test]
+async fn test_language_servers_stopped_on_unshare(cx: &mut TestAppContext) {
+    let mut fake_servers = None;
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.languages.add(Arc::new(Language::new(
+            LanguageConfig {
+                name: "Rust".into(),
+                matcher: LanguageMatcher {
+                    path_suffixes: vec!["rs".to_string()],
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            None,
+        )));
+        fake_servers = Some(
+            app_state
+                .languages
+                .register_fake_lsp_adapter("Rust", FakeLspAdapter::default()),
+        );
+    })
+    .await;
+    let mut fake_servers = fake_servers.unwrap();
+    dev_server
+        .fs
+        .insert_tree("/remote", json!({ "main.rs": "fn main() {}" }))
+        .await;
+
+    dev_server.send_instructions(&[(1, "/remote")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().run_until_parked();
+
+    let project = dev_server.shared_project(1, cx);
+    let _buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/remote/main.rs", cx)
+        })
+        .await
+        .unwrap();
+    let mut fake_server = fake_servers.next().await.unwrap();
+    let mut shutdown_requests =
+        fake_server.handle_request::<lsp::request::Shutdown, _, _>(|_, _| future::ready(Ok(())));
+    cx.executor().run_until_parked();
+    project.read_with(cx, |project, _| {
+        assert_eq!(project.language_servers().count(), 1)
+    });
+
+    dev_server.send_instructions(&[]);
+    shutdown_requests.next().await.unwrap();
+    fake_server
+        .receive_notification::<lsp::notification::Exit>()
+        .await;
+    cx.executor().run_until_parked();
+
+    project.read_with(cx, |project, _| {
+        assert_eq!(project.language_servers().count(), 0)
+    });
+}
+
+#[gpui::test]
+async fn test_keepalive(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    assert_eq!(dev_server.client.keepalive(), Keepalive::default());
+}
+
+#[gpui::test]
+async fn test_configured_keepalive(cx: &mut TestAppContext) {
+    let keepalive = Keepalive {
+        interval: Duration::from_secs(15),
+        timeout: Duration::from_secs(45),
+    };
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.keepalive = Some(keepalive);
+    })
+    .await;
+    assert_eq!(dev_server.client.keepalive(), keepalive);
+}
+
+#[gpui::test]
+async fn test_connection_history(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.connection_history_len = Some(3);
+    })
+    .await;
+
+    let statuses = [
+        Status::ConnectionLost,
+        Status::Reconnecting,
+        Status::ConnectionError,
+        Status::Reconnecting,
+        Status::Connected {
+            peer_id: proto::PeerId { owner_id: 0, id: 1 },
+            connection_id: client::ConnectionId { owner_id: 0, id: 2 },
+        },
+    ];
+    for status in statuses {
+        dev_server.clock.advance(chrono::Duration::seconds(1));
+        dev_server.dev_server.update(cx, |dev_server, _| {
+            dev_server.record_connection_status(status)
+        });
+    }
+
+    let history = dev_server.dev_server.read_with(cx, |dev_server, _| {
+        dev_server.connection_history().cloned().collect::<Vec<_>>()
+    });
+    assert_eq!(
+        history.iter().map(|event| &event.kind).collect::<Vec<_>>(),
+        [
+            &ConnectionEventKind::ConnectFailed {
+                status: "connection_error".into()
+            },
+            &ConnectionEventKind::Connecting {
+                status: "reconnecting".into()
+            },
+            &ConnectionEventKind::Connected,
+        ]
+    );
+    assert!(history
+        .windows(2)
+        .all(|events| events[0].timestamp < events[1].timestamp));
+    assert_eq!(
+        dev_server
+            .dev_server
+            .read_with(cx, |dev_server, _| dev_server.status_file())
+            .connection_history,
+        history
+    );
+}
+
+#[gpui::test]
+async fn test_overlapping_projects(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.overlapping_projects = OverlappingProjectPolicy::Refuse;
+    })
+    .await;
+    dev_server
+        .fs
+        .insert_tree(
+            "/code",
+            json!({
+                "nested": { "a.txt": "a" },
+                "b.txt": "b",
+            }),
+        )
+        .await;
+    dev_server
+        .fs
+        .insert_tree("/code-other", json!({ "c.txt": "c" }))
+        .await;
+
+    dev_server.send_instructions(&[(1, "/code")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().run_until_parked();
+
+    // The nested project is refused, but a sibling whose name merely starts
+    // with the same characters is not nested and is shared.
+    dev_server.send_instructions(&[(1, "/code"), (2, "/code/nested"), (3, "/code-other")]);
+    assert_eq!(dev_server.respond_to_share(102).await, 3);
+    cx.executor().run_until_parked();
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1, 3]);
+    assert!(dev_server
+        .dev_server
+        .read_with(cx, |dev_server, _| dev_server.pending_shares.is_empty()));
+}
+
+#[gpui::test]
+async fn test_metrics(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    dev_server
+        .fs
+        .insert_tree("/remote", json!({ "a.txt": "a" }))
+        .await;
+
+    dev_server
+        .fs
+        .insert_tree("/slow", json!({ "b.txt": "b" }))
+        .await;
+    dev_server
+        .fs
+        .set_metadata_delay("/slow", SHARE_FS_TIMEOUT * 2);
+
+    // One project is shared and another times out.
+    dev_server.send_instructions(&[(1, "/remote"), (2, "/slow")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().advance_clock(SHARE_FS_TIMEOUT);
+    cx.executor().run_until_parked();
+    dev_server.add_collaborator(101, 7);
+    cx.executor().run_until_parked();
+
+    let metrics = dev_server
+        .dev_server
+        .read_with(cx, |dev_server, _| dev_server.metrics());
+    assert_eq!(
+        metrics,
+        Metrics {
+            connected: true,
+            shared_projects: 1,
+            pending_shares: 0,
+            collaborators: 1,
+            connections: 0,
+            shares_succeeded: 1,
+            shares_failed: 1,
+            idle_unshares: 0,
+        }
+    );
+
+    let output = metrics.to_prometheus();
+    for line in output.lines() {
+        if line.starts_with("# HELP ") || line.starts_with("# TYPE ") {
+            continue;
+        }
+        let (name, value) = line.split_once(' ').unwrap();
+        assert!(name.starts_with("zed_dev_server_"), "{line}");
+        assert!(value.parse::<u64>().is_ok(), "{line}");
+    }
+    assert!(output.contains(
+        "# TYPE zed_dev_server_shared_projects gauge\nzed_dev_server_shared_projects 1\n"
+    ));
+    assert!(output.contains(
+        "# TYPE zed_dev_server_shares_failed_total counter\nzed_dev_server_shares_failed_total 1\n"
+    ));
+    assert!(output.contains("zed_dev_server_collaborators 1\n"));
+    assert!(output.contains("zed_dev_server_connected 1\n"));
+}
+
+#[gpui::test]
+async fn test_health_checks(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    dev_server
+        .fs
+        .insert_tree("/remote", json!({ "a.txt": "a" }))
+        .await;
+    let checks = |cx: &TestAppContext| {
+        dev_server.dev_server.read_with(cx, |dev_server, _| {
+            (dev_server.liveness(), dev_server.readiness())
+        })
+    };
+
+    dev_server.send_instructions(&[(1, "/remote")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().run_until_parked();
+    let connected_since = Some(dev_server.clock.utc_now());
+    assert_eq!(
+        checks(cx),
+        (
+            HealthCheck {
+                ok: true,
+                shared_projects: 1,
+                connected_since,
+                reconnect_count: 0,
+            },
+            HealthCheck {
+                ok: true,
+                shared_projects: 1,
+                connected_since,
+                reconnect_count: 0,
+            }
+        )
+    );
+
+    // The dev server stays alive while it is disconnected, but isn't ready.
+    dev_server.server.forbid_connections();
+    dev_server.server.disconnect();
+    cx.executor().run_until_parked();
+    let (liveness, readiness) = checks(cx);
+    assert!(liveness.ok);
+    assert!(!readiness.ok);
+    assert_eq!(
+        serde_json::to_value(&readiness).unwrap(),
+        json!({
+            "ok": false,
+            "shared_projects": 1,
+            "connected_since": null,
+            "reconnect_count": 0,
+        })
+    );
+}
+
+#[gpui::test]
+async fn test_connection_uptime(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    let uptime = |cx: &TestAppContext| {
+        dev_server.dev_server.read_with(cx, |dev_server, _| {
+            (dev_server.connected_since(), dev_server.reconnect_count())
+        })
+    };
+    let started_at = dev_server.clock.utc_now();
+    assert_eq!(uptime(cx), (Some(started_at), 0));
+
+    // Losing the connection clears the uptime.
+    dev_server.server.forbid_connections();
+    dev_server.server.disconnect();
+    cx.executor().run_until_parked();
+    assert_eq!(uptime(cx), (None, 0));
+
+    // Reconnecting starts it again and counts the reconnect.
+    dev_server.clock.advance(chrono::Duration::minutes(5));
+    dev_server.server.allow_connections();
+    cx.executor().advance_clock(Duration::from_secs(10));
+    cx.executor().run_until_parked();
+    let request = dev_server
+        .server
+        .receive::<proto::ReconnectDevServer>()
+        .await
+        .unwrap();
+    dev_server.server.respond(
+        request.receipt(),
+        proto::ReconnectDevServerResponse {
+            reshared_projects: Vec::new(),
+            ..Default::default()
+        },
+    );
+    cx.executor().run_until_parked();
+    assert_eq!(
+        uptime(cx),
+        (Some(started_at + chrono::Duration::minutes(5)), 1)
+    );
+
+    dev_server.reconnect().await;
+    cx.executor().run_until_parked();
+    assert_eq!(uptime(cx).1, 2);
+}
+
+#[gpui::test]
+async fn test_invalid_project_settings(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    let events = dev_server.record_events(cx);
+    dev_server
+        .fs
+        .insert_tree(
+            "/remote",
+            json!({
+                ".zed": { "settings.json": r#"{ "git": { "git_gutter": "hide" } }"# },
+                "a.txt": "a",
+            }),
+        )
+        .await;
+
+    dev_server.send_instructions(&[(1, "/remote")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().run_until_parked();
+
+    let project = dev_server.shared_project(1, cx);
+    let git_gutter = |cx: &mut TestAppContext| {
+        project.update(cx, |project, cx| {
+            let worktree_id = project.worktrees().next().unwrap().read(cx).id();
+            ProjectSettings::get(
+                Some(SettingsLocation {
+                    worktree_id: worktree_id.to_usize(),
+                    path: Path::new("a.txt"),
+                }),
+                cx,
+            )
+            .git
+            .git_gutter
+        })
+    };
+    assert!(matches!(git_gutter(cx), Some(GitGutterSetting::Hide)));
+
+    dev_server
+        .fs
+        .insert_file(
+            "/remote/.zed/settings.json",
+            r#"{ "git": { "git_gutter": 5 } }"#.into(),
+        )
+        .await;
+    cx.executor().run_until_parked();
+
+    assert!(matches!(git_gutter(cx), Some(GitGutterSetting::Hide)));
+    assert!(matches!(
+        events.lock().as_slice(),
+        [Event::SettingsLoadFailed {
+            remote_project_id: RemoteProjectId(1),
+            ..
+        }]
+    ));
+}
+
+#[gpui::test]
+async fn test_project_status_updates(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    dev_server
+        .fs
+        .insert_tree("/remote", json!({ "a.txt": "a" }))
+        .await;
+
+    dev_server.send_instructions(&[(1, "/remote")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().run_until_parked();
+
+    dev_server
+        .fs
+        .insert_tree(
+            "/remote/.zed",
+            json!({ "settings.json": r#"{ "git": { "git_gutter": 5 } }"# }),
+        )
+        .await;
+    let update = dev_server
+        .server
+        .receive::<proto::UpdateDevServerProjectStatus>()
+        .await
+        .unwrap();
+    assert_eq!(update.payload.remote_project_id, 1);
+    assert_eq!(
+        update.payload.status(),
+        proto::update_dev_server_project_status::Status::Errored
+    );
+    let error = update.payload.error.unwrap();
+    assert!(error.contains("failed to load settings"), "{error}");
+
+    dev_server
+        .shared_project(1, cx)
+        .update(cx, |project, cx| project.close(cx));
+    let update = dev_server
+        .server
+        .receive::<proto::UpdateDevServerProjectStatus>()
+        .await
+        .unwrap();
+    assert_eq!(update.payload.remote_project_id, 1);
+    assert_eq!(
+        update.payload.status(),
+        proto::update_dev_server_project_status::Status::Disconnected
+    );
+    assert_eq!(update.payload.error, None);
+}
+
+#[gpui::test]
+async fn test_per_project_settings(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    dev_server
+        .fs
+        .insert_tree("/project-1", json!({ "a.txt": "a" }))
+        .await;
+    dev_server
+        .fs
+        .insert_tree("/project-2", json!({ "b.txt": "b" }))
+        .await;
+
+    dev_server.server.send(proto::DevServerInstructions {
+        projects: vec![
+            proto::RemoteProject {
+                id: 1,
+                path: "/project-1".into(),
+                settings: Some(r#"{ "git": { "git_gutter": "hide" } }"#.into()),
+                ..Default::default()
+            },
+            proto::RemoteProject {
+                id: 2,
+                path: "/project-2".into(),
+                settings: Some(r#"{ "git": { "git_gutter": "tracked_files" } }"#.into()),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    });
+    for _ in 0..2 {
+        let request = dev_server
+            .server
+            .receive::<proto::ShareRemoteProject>()
+            .await
+            .unwrap();
+        let project_id = 100 + request.payload.remote_project_id;
+        dev_server.server.respond(
+            request.receipt(),
+            proto::ShareProjectResponse { project_id },
+        );
+    }
+    cx.executor().run_until_parked();
+
+    let git_gutter = |remote_project_id, path: &str, cx: &mut TestAppContext| {
+        dev_server
+            .shared_project(remote_project_id, cx)
+            .update(cx, |project, cx| {
+                let worktree_id = project.worktrees().next().unwrap().read(cx).id();
+                ProjectSettings::get(
+                    Some(SettingsLocation {
+                        worktree_id: worktree_id.to_usize(),
+                        path: Path::new(path),
+                    }),
+                    cx,
+                )
+                .git
+                .git_gutter
+            })
+    };
+    assert!(matches!(
+        git_gutter(1, "a.txt", cx),
+        Some(GitGutterSetting::Hide)
+    ));
+    assert!(matches!(
+        git_gutter(2, "b.txt", cx),
+        Some(GitGutterSetting::TrackedFiles)
+    ));
+
+    // The project's own settings file still takes precedence.
+    dev_server
+        .fs
+        .insert_tree(
+            "/project-1/.zed",
+            json!({ "settings.json": r#"{ "git": { "git_gutter": "tracked_files" } }"# }),
+        )
+        .await;
+    cx.executor().run_until_parked();
+    assert!(matches!(
+        git_gutter(1, "a.txt", cx),
+        Some(GitGutterSetting::TrackedFiles)
+    ));
+}
+
+#[gpui::test]
+async fn test_per_project_settings_removed_on_unshare(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    dev_server
+        .fs
+        .insert_tree("/remote", json!({ "a.txt": "a" }))
+        .await;
+
+    dev_server.server.send(proto::DevServerInstructions {
+        projects: vec![proto::RemoteProject {
+            id: 1,
+            path: "/remote".into(),
+            settings: Some(r#"{ "git": { "git_gutter": "hide" } }"#.into()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    });
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().run_until_parked();
+
+    // Keep the project alive, so that its worktree isn't released when it's unshared.
+    let project = dev_server.shared_project(1, cx);
+    let git_gutter = |cx: &mut TestAppContext| {
+        project.update(cx, |project, cx| {
+            let worktree_id = project.worktrees().next().unwrap().read(cx).id();
+            ProjectSettings::get(
+                Some(SettingsLocation {
+                    worktree_id: worktree_id.to_usize(),
+                    path: Path::new("a.txt"),
+                }),
+                cx,
+            )
+            .git
+            .git_gutter
+        })
+    };
+    assert!(matches!(git_gutter(cx), Some(GitGutterSetting::Hide)));
+
+    dev_server.send_instructions(&[]);
+    cx.executor().run_until_parked();
+    assert!(dev_server.shared_remote_project_ids(cx).is_empty());
+    assert!(!matches!(git_gutter(cx), Some(GitGutterSetting::Hide)));
+}
+
+#[gpui::test]
+async fn test_languages_without_node(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        for (name, suffix, requires_node_runtime) in
+            [("TypeScript", "ts", true), ("Rust", "rs", false)]
+        {
+            app_state.languages.add(Arc::new(Language::new(
+                LanguageConfig {
+                    name: name.into(),
+                    matcher: LanguageMatcher {
+                        path_suffixes: vec![suffix.to_string()],
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                None,
+            )));
+            app_state.languages.register_fake_lsp_adapter(
+                name,
+                FakeLspAdapter {
+                    requires_node_runtime,
+                    ..Default::default()
+                },
+            );
+        }
+    })
+    .await;
+    dev_server
+        .fs
+        .insert_tree(
+            "/web",
+            json!({ "index.ts": "", "src": { "app.ts": "" }, "build.rs": "" }),
+        )
+        .await;
+    dev_server
+        .fs
+        .insert_tree("/native", json!({ "main.rs": "fn main() {}" }))
+        .await;
+
+    // The fake Node runtime is unavailable, so only the project with
+    // TypeScript files is affected.
+    dev_server.send_instructions(&[(1, "/web"), (2, "/native")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    assert_eq!(dev_server.respond_to_share(102).await, 2);
+    cx.executor().run_until_parked();
+
+    let manifest = dev_server
+        .dev_server
+        .read_with(cx, |dev_server, cx| dev_server.share_manifest(cx));
+    assert_eq!(
+        manifest
+            .projects
+            .iter()
+            .map(|project| (
+                project.remote_project_id,
+                project.languages_without_node.clone()
+            ))
+            .collect::<Vec<_>>(),
+        vec![(1, vec!["TypeScript".to_string()]), (2, Vec::new())]
+    );
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1, 2]);
+}
+
+#[gpui::test]
+async fn test_project_factory(cx: &mut TestAppContext) {
+    let created_projects = Arc::new(Mutex::new(Vec::new()));
+    let dev_server = start_dev_server(cx, |app_state| {
+        let created_projects = created_projects.clone();
+        app_state.project_factory = Some(Arc::new(
+            move |remote_project: &proto::RemoteProject,
+                  client: &Arc<Client>,
+                  app_state: &AppState,
+                  cx: &mut AppContext| {
+                created_projects.lock().push(remote_project.id);
+                Project::local(
+                    client.clone(),
+                    app_state.node_runtime.clone(),
+                    app_state.user_store.clone(),
+                    app_state.languages.clone(),
+                    app_state.fs.clone(),
+                    cx,
+                )
+            },
+        ));
+    })
+    .await;
+    dev_server
+        .fs
+        .insert_tree("/remote", json!({ "a.txt": "a" }))
+        .await;
+
+    dev_server.send_instructions(&[(1, "/remote")]);
+    assert_eq!(dev_server.respond_to_share(101).await, 1);
+    cx.executor().run_until_parked();
+
+    assert_eq!(*created_projects.lock(), vec![1]);
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1]);
+    assert_eq!(
+        dev_server
+            .shared_project(1, cx)
+            .read_with(cx, |project, _| project.remote_id()),
+        Some(101)
+    );
+}
+
+#[gpui::test]
+async fn test_share_priority(cx: &mut TestAppContext) {
+    let created_projects = Arc::new(Mutex::new(Vec::new()));
+    let dev_server = start_dev_server(cx, |app_state| {
+        let created_projects = created_projects.clone();
+        app_state.project_factory = Some(Arc::new(
+            move |remote_project: &proto::RemoteProject,
+                  client: &Arc<Client>,
+                  app_state: &AppState,
+                  cx: &mut AppContext| {
+                created_projects.lock().push(remote_project.id);
+                Project::local(
+                    client.clone(),
+                    app_state.node_runtime.clone(),
+                    app_state.user_store.clone(),
+                    app_state.languages.clone(),
+                    app_state.fs.clone(),
+                    cx,
+                )
+            },
+        ));
+    })
+    .await;
+    let priorities = [
+        (1, None),
+        (2, Some(5)),
+        (3, None),
+        (4, Some(10)),
+        (5, Some(5)),
+    ];
+    for (id, _) in priorities {
+        dev_server
+            .fs
+            .insert_tree(format!("/project-{id}"), json!({ "a.txt": "a" }))
+            .await;
+    }
+
+    dev_server.server.send(proto::DevServerInstructions {
+        projects: priorities
+            .iter()
+            .map(|(id, priority)| proto::RemoteProject {
+                id: *id,
+                path: format!("/project-{id}"),
+                priority: *priority,
+                ..Default::default()
+            })
+            .collect(),
+        ..Default::default()
+    });
+    for _ in priorities {
+        let request = dev_server
+            .server
+            .receive::<proto::ShareRemoteProject>()
+            .await
+            .unwrap();
+        let project_id = 100 + request.payload.remote_project_id;
+        dev_server.server.respond(
+            request.receipt(),
+            proto::ShareProjectResponse { project_id },
+        );
+    }
+    cx.executor().run_until_parked();
+
+    assert_eq!(*created_projects.lock(), vec![4, 2, 5, 1, 3]);
+    assert_eq!(
+        dev_server.shared_remote_project_ids(cx),
+        vec![1, 2, 3, 4, 5]
+    );
+    let manifest = dev_server
+        .dev_server
+        .read_with(cx, |dev_server, cx| dev_server.share_manifest(cx));
+    let manifest_priority = |remote_project_id| {
+        manifest
+            .projects
+            .iter()
+            .find(|project| project.remote_project_id == remote_project_id)
+            .unwrap()
+            .priority
+    };
+    assert_eq!(manifest_priority(4), Some(10));
+    assert_eq!(manifest_priority(1), None);
+}
+
+#[gpui::test]
+async fn test_max_projects(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.max_projects = Some(2);
+    })
+    .await;
+    let events = dev_server.record_events(cx);
+    for path in ["/one", "/two", "/three"] {
+        dev_server
+            .fs
+            .insert_tree(path, json!({ "a.txt": "a" }))
+            .await;
+    }
+
+    dev_server.send_instructions(&[(1, "/one"), (2, "/two"), (3, "/three")]);
+    let status = dev_server
+        .server
+        .receive::<proto::UpdateDevServerProjectStatus>()
+        .await
+        .unwrap();
+    assert_eq!(status.payload.remote_project_id, 3);
+    assert_eq!(
+        status.payload.status(),
+        proto::update_dev_server_project_status::Status::Errored
+    );
+    for _ in 0..2 {
+        let request = dev_server
+            .server
+            .receive::<proto::ShareRemoteProject>()
+            .await
+            .unwrap();
+        let project_id = 100 + request.payload.remote_project_id;
+        dev_server.server.respond(
+            request.receipt(),
+            proto::ShareProjectResponse { project_id },
+        );
+    }
+    cx.executor().run_until_parked();
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![1, 2]);
+    assert_eq!(
+        &*events.lock(),
+        &[Event::ProjectsSkipped(vec![RemoteProjectId(3)])]
+    );
+
+    // Removing a project makes room for the one that was skipped.
+    dev_server.send_instructions(&[(2, "/two"), (3, "/three")]);
+    let unshare = dev_server
+        .server
+        .receive::<proto::UnshareProject>()
+        .await
+        .unwrap();
+    assert_eq!(unshare.payload.project_id, 101);
+    assert_eq!(dev_server.respond_to_share(103).await, 3);
+    let status = dev_server
+        .server
+        .receive::<proto::UpdateDevServerProjectStatus>()
+        .await
+        .unwrap();
+    assert_eq!(status.payload.remote_project_id, 3);
+    assert_eq!(
+        status.payload.status(),
+        proto::update_dev_server_project_status::Status::Healthy
+    );
+    cx.executor().run_until_parked();
+    assert_eq!(dev_server.shared_remote_project_ids(cx), vec![2, 3]);
+    assert_eq!(events.lock().len(), 1);
+}
+
+#[gpui::test]
+async fn test_max_projects_refuses_extra_projects(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.max_projects = Some(100);
+    })
+    .await;
+    let paths = (1..=101)
+        .map(|id| (id, format!("/project-{id}")))
+        .collect::<Vec<_>>();
+    for (_, path) in &paths {
+        dev_server
+            .fs
+            .insert_tree(path, json!({ "a.txt": "a" }))
+            .await;
+    }
+
+    dev_server.send_instructions(
+        &paths
+            .iter()
+            .map(|(id, path)| (*id, path.as_str()))
+            .collect::<Vec<_>>(),
+    );
+    let status = dev_server
+        .server
+        .receive::<proto::UpdateDevServerProjectStatus>()
+        .await
+        .unwrap();
+    assert_eq!(status.payload.remote_project_id, 101);
+    assert_eq!(
+        status.payload.status(),
+        proto::update_dev_server_project_status::Status::Errored
+    );
+    assert_eq!(
+        status.payload.error.as_deref(),
+        Some("the dev server already shares the maximum of 100 projects")
+    );
+    for _ in 0..100 {
+        let request = dev_server
+            .server
+            .receive::<proto::ShareRemoteProject>()
+            .await
+            .unwrap();
+        let project_id = 1000 + request.payload.remote_project_id;
+        dev_server.server.respond(
+            request.receipt(),
+            proto::ShareProjectResponse { project_id },
+        );
+    }
+    cx.executor().run_until_parked();
+    assert_eq!(
+        dev_server.shared_remote_project_ids(cx),
+        (1..=100).collect::<Vec<_>>()
+    );
+}
+
+#[gpui::test]
+async fn test_interrupt_without_ctrlc_handler(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    let events = dev_server.record_events(cx);
+    let interrupt_tx = dev_server
+        .dev_server
+        .read_with(cx, |dev_server, _| dev_server.interrupt_tx.clone());
+    *INTERRUPT_TX.lock().unwrap() = Some(interrupt_tx.clone());
+    forward_interrupts(interrupt_tx, |_| Err(ctrlc::Error::MultipleHandlers));
+    cx.executor().run_until_parked();
+    assert!(events.lock().is_empty());
+
+    request_interrupt();
+    cx.executor().run_until_parked();
+    assert_eq!(&*events.lock(), &[Event::Interrupted]);
+}
+
+#[gpui::test]
+async fn test_interrupt_handler(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |_| {}).await;
+    let events = dev_server.record_events(cx);
+    let interrupt_tx = dev_server
+        .dev_server
+        .read_with(cx, |dev_server, _| dev_server.interrupt_tx.clone());
+
+    let mut handler = None;
+    forward_interrupts(interrupt_tx, |ctrlc_handler| {
+        handler = Some(ctrlc_handler);
+        Ok(())
+    });
+    cx.executor().run_until_parked();
+    assert!(events.lock().is_empty());
+
+    handler.unwrap()();
+    cx.executor().run_until_parked();
+    assert_eq!(&*events.lock(), &[Event::Interrupted]);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_termination_signals() {
+    let (tx, rx) = std::sync::mpsc::channel();
+    handle_termination_signals(move || tx.send(()).unwrap()).unwrap();
+    signal_hook::low_level::raise(signal_hook::consts::SIGTERM).unwrap();
+    rx.recv_timeout(Duration::from_secs(5)).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_reload_signals() {
+    let (tx, rx) = std::sync::mpsc::channel();
+    handle_reload_signals(move || tx.send(()).unwrap()).unwrap();
+    for _ in 0..2 {
+        signal_hook::low_level::raise(signal_hook::consts::SIGHUP).unwrap();
+        rx.recv_timeout(Duration::from_secs(5)).unwrap();
+    }
+}
+
+#[gpui::test]
+async fn test_reload_token(cx: &mut TestAppContext) {
+    let dev_server = start_dev_server(cx, |app_state| {
+        app_state.options.token_path = Some("/secrets/token".into());
+    })
+    .await;
+    let reload_token = |cx: &mut TestAppContext| {
+        dev_server
+            .dev_server
+            .update(cx, |dev_server, cx| dev_server.reload_token(cx))
+    };
+
+    dev_server
+        .fs
+        .insert_tree("/secrets", json!({ "token": "first-token\n" }))
+        .await;
+    reload_token(cx).await.unwrap();
+    assert_eq!(
+        dev_server.client.dev_server_token(),
+        Some(DevServerToken("first-token".into()))
+    );
+
+    dev_server
+        .fs
+        .insert_file("/secrets/token", "second-token".into())
+        .await;
+    reload_token(cx).await.unwrap();
+    assert_eq!(
+        dev_server.client.dev_server_token(),
+        Some(DevServerToken("second-token".into()))
+    );
+
+    // A token file that is empty or missing keeps the previous token.
+    dev_server
+        .fs
+        .insert_file("/secrets/token", " \n".into())
+        .await;
+    let error = reload_token(cx).await.unwrap_err();
+    assert!(error.to_string().contains("is empty"), "{error:#}");
+    dev_server
+        .fs
+        .remove_file(Path::new("/secrets/token"), Default::default())
+        .await
+        .unwrap();
+    let error = reload_token(cx).await.unwrap_err();
+    assert!(error.to_string().contains("failed to read"), "{error:#}");
+    assert_eq!(
+        dev_server.client.dev_server_token(),
+        Some(DevServerToken("second-token".into()))
+    );
+}
+
+struct TestDevServer {
+    client: Arc<Client>,
+    server: FakeServer,
+    dev_server: Model<DevServer>,
+    fs: Arc<FakeFs>,
+    clock: Arc<FakeSystemClock>,
+}
+
+async fn start_dev_server(
+    cx: &mut TestAppContext,
+    configure: impl FnOnce(&mut AppState),
+) -> TestDevServer {
+    cx.update(|cx| {
+        let settings_store = SettingsStore::test(cx);
+        cx.set_global(settings_store);
+        release_channel::init("0.0.0", cx);
+        language::init(cx);
+        client::init_settings(cx);
+    });
+
+    let client = cx.update(|cx| {
+        Client::new(
+            Arc::new(FakeSystemClock::default()),
+            FakeHttpClient::with_404_response(),
+            cx,
+        )
+    });
+    cx.update(|cx| Project::init(&client, cx));
+    let server = FakeServer::for_client(1, &client, cx).await;
+    let user_store = server.build_user_store(client.clone(), cx).await;
+    let fs = FakeFs::new(cx.executor());
+    let clock = Arc::new(FakeSystemClock::default());
+
+    let mut app_state = AppState {
+        node_runtime: FakeNodeRuntime::new(),
+        user_store,
+        languages: Arc::new(LanguageRegistry::test(cx.executor())),
+        fs: fs.clone(),
+        clock: clock.clone(),
+        access_log: None,
+        project_factory: None,
+        options: DevServerOptions {
+            instruction_debounce: Some(Duration::ZERO),
+            ..Default::default()
+        },
+    };
+    configure(&mut app_state);
+
+    let dev_server = cx.new_model(|cx| DevServer::new(client.clone(), app_state, cx));
+    TestDevServer {
+        client,
+        server,
+        dev_server,
+        fs,
+        clock,
+    }
+}
+
+impl TestDevServer {
+    fn record_events(&self, cx: &mut TestAppContext) -> Arc<Mutex<Vec<Event>>> {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        cx.update(|cx| {
+            let events = events.clone();
+            cx.subscribe(&self.dev_server, move |_, event: &Event, _| {
+                events.lock().push(event.clone());
+            })
+            .detach();
+        });
+        events
+    }
+
+    /// Disconnects the dev server and returns the request it sends to rejoin
+    /// once it has reconnected.
+    async fn reconnect(&self) -> TypedEnvelope<proto::ReconnectDevServer> {
+        self.server.disconnect();
+        let request = self
+            .server
+            .receive::<proto::ReconnectDevServer>()
+            .await
+            .unwrap();
+        self.server.respond(
+            request.receipt(),
+            proto::ReconnectDevServerResponse {
+                reshared_projects: Vec::new(),
+                ..Default::default()
+            },
+        );
+        request
+    }
+
+    fn send_instructions(&self, projects: &[(u64, &str)]) {
+        self.server.send(proto::DevServerInstructions {
+            projects: projects
+                .iter()
+                .map(|(id, path)| proto::RemoteProject {
+                    id: *id,
+                    path: path.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        });
+    }
+
+    /// Waits for the dev server to share a project, assigns it the given
+    /// `project_id`, and returns the id of the remote project that was shared.
+    async fn respond_to_share(&self, project_id: u64) -> u64 {
+        let request = self
+            .server
+            .receive::<proto::ShareRemoteProject>()
+            .await
+            .unwrap();
+        self.server.respond(
+            request.receipt(),
+            proto::ShareProjectResponse { project_id },
+        );
+        request.payload.remote_project_id
+    }
+
+    fn shared_remote_project_ids(&self, cx: &TestAppContext) -> Vec<u64> {
+        self.dev_server.read_with(cx, |dev_server, _| {
+            let mut ids = dev_server
+                .projects
+                .keys()
+                .map(|id| id.0)
+                .collect::<Vec<_>>();
+            ids.sort_unstable();
+            ids
+        })
+    }
+
+    fn shared_project(&self, remote_project_id: u64, cx: &TestAppContext) -> Model<Project> {
+        self.dev_server.read_with(cx, |dev_server, _| {
+            dev_server.projects[&RemoteProjectId(remote_project_id)]
+                .project
+                .clone()
+        })
+    }
+
+    fn add_collaborator(&self, project_id: u64, peer_id: u32) {
+        self.server.send(proto::AddProjectCollaborator {
+            project_id,
+            collaborator: Some(proto::Collaborator {
+                peer_id: Some(proto::PeerId {
+                    owner_id: 2,
+                    id: peer_id,
+                }),
+                replica_id: peer_id,
+                user_id: 2,
+            }),
+        });
+    }
+
+    async fn read_status_file(&self) -> StatusFile {
+        let contents = self.fs.load(Path::new("/status.json")).await.unwrap();
+        serde_json::from_str(&contents).unwrap()
+    }
+}
@@ -0,0 +1,75 @@
+use crate::DevServer;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use futures::{channel::mpsc, StreamExt};
+use gpui::{AsyncAppContext, WeakModel};
+use serde::{Deserialize, Serialize};
+use util::ResultExt;
+
+/// The answer to a liveness or readiness probe, see [`DevServer::liveness`]
+/// and [`DevServer::readiness`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub ok: bool,
+    pub shared_projects: usize,
+    pub connected_since: Option<DateTime<Utc>>,
+    pub reconnect_count: usize,
+}
+
+impl DevServer {
+    /// Whether the dev server is alive: it has connected to the server since it
+    /// started.
+    pub fn liveness(&self) -> HealthCheck {
+        HealthCheck {
+            ok: self.has_connected,
+            shared_projects: self.projects.len(),
+            connected_since: self.connected_since,
+            reconnect_count: self.reconnect_count,
+        }
+    }
+
+    /// Whether the dev server is ready to serve collaborators: it is currently
+    /// connected to the server and isn't draining.
+    pub fn readiness(&self) -> HealthCheck {
+        HealthCheck {
+            ok: self.client.status().borrow().is_connected() && !self.draining,
+            shared_projects: self.projects.len(),
+            connected_since: self.connected_since,
+            reconnect_count: self.reconnect_count,
+        }
+    }
+
+    pub(crate) async fn serve_health_checks(
+        this: WeakModel<Self>,
+        mut requests: mpsc::UnboundedReceiver<tiny_http::Request>,
+        mut cx: AsyncAppContext,
+    ) -> Result<()> {
+        while let Some(request) = requests.next().await {
+            let Some(this) = this.upgrade() else {
+                return Ok(());
+            };
+            let check = this.read_with(&cx, |this, _| match request.url() {
+                "/healthz" => Some(this.liveness()),
+                "/readyz" => Some(this.readiness()),
+                _ => None,
+            })?;
+            let response = match check {
+                Some(check) => tiny_http::Response::from_string(serde_json::to_string(&check)?)
+                    .with_status_code(if check.ok { 200 } else { 503 })
+                    .with_header(
+                        tiny_http::Header::from_bytes(
+                            &b"Content-Type"[..],
+                            &b"application/json"[..],
+                        )
+                        .unwrap(),
+                    ),
+                None => tiny_http::Response::from_string("not found").with_status_code(404),
+            };
+            cx.background_executor()
+                .spawn(async move { request.respond(response) })
+                .await
+                .log_err();
+        }
+        Ok(())
+    }
+}
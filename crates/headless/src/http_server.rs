@@ -0,0 +1,48 @@
+use anyhow::{anyhow, Result};
+use futures::channel::mpsc;
+use gpui::Task;
+use std::{net::SocketAddr, sync::Arc};
+
+/// An HTTP listener whose requests are answered on the main thread.
+pub(crate) struct HttpServer {
+    server: Arc<tiny_http::Server>,
+    _serve: Task<Option<()>>,
+}
+
+impl HttpServer {
+    /// Listens on `address`, handing the incoming requests to `serve`.
+    pub fn new(
+        address: SocketAddr,
+        serve: impl FnOnce(mpsc::UnboundedReceiver<tiny_http::Request>) -> Task<Option<()>>,
+    ) -> Result<Self> {
+        let server = tiny_http::Server::http(address)
+            .map_err(|error| anyhow!("failed to listen on {}: {}", address, error))?;
+        let server = Arc::new(server);
+        let requests = incoming_requests(server.clone());
+        Ok(Self {
+            server,
+            _serve: serve(requests),
+        })
+    }
+
+    /// Stops accepting requests, so that the thread waiting for them exits.
+    pub fn close(&self) {
+        self.server.unblock();
+    }
+}
+
+/// Accepts requests on a dedicated thread, as tiny_http blocks while waiting
+/// for them, so that they can be answered on the main thread.
+fn incoming_requests(
+    server: Arc<tiny_http::Server>,
+) -> mpsc::UnboundedReceiver<tiny_http::Request> {
+    let (requests_tx, requests_rx) = mpsc::unbounded();
+    std::thread::spawn(move || {
+        for request in server.incoming_requests() {
+            if requests_tx.unbounded_send(request).is_err() {
+                break;
+            }
+        }
+    });
+    requests_rx
+}
@@ -0,0 +1,472 @@
+use crate::{DevServer, Event, ProjectStatus};
+use anyhow::{anyhow, Context as _, Result};
+use chrono::{DateTime, Utc};
+use client::{Client, RemoteProjectId};
+use fs::{Fs, RemoveOptions};
+use futures::{channel::mpsc, future, select_biased, FutureExt, StreamExt};
+use gpui::{AsyncAppContext, Model, ModelContext, WeakModel};
+use rpc::{proto, TypedEnvelope};
+use std::{
+    cmp::Reverse,
+    collections::HashSet,
+    mem,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use util::{ResultExt, TryFutureExt};
+
+/// How long to wait for more instructions when
+/// [`crate::DevServerOptions::instruction_debounce`] is unset.
+const DEFAULT_INSTRUCTION_DEBOUNCE: Duration = Duration::from_millis(250);
+
+impl DevServer {
+    pub(crate) async fn handle_dev_server_instructions(
+        this: Model<Self>,
+        envelope: TypedEnvelope<proto::DevServerInstructions>,
+        _: Arc<Client>,
+        mut cx: AsyncAppContext,
+    ) -> Result<()> {
+        this.update(&mut cx, |this, _| {
+            this.instructions_tx.unbounded_send(envelope.payload).ok();
+        })
+    }
+
+    /// Applies instructions one batch at a time, so that each batch starts
+    /// from the projects that the previous one left behind. Instructions that
+    /// arrive within [`crate::DevServerOptions::instruction_debounce`] of each
+    /// other replace the ones before them, and only the latest are applied.
+    pub(crate) async fn apply_instructions(
+        this: WeakModel<Self>,
+        mut instructions_rx: mpsc::UnboundedReceiver<proto::DevServerInstructions>,
+        mut cx: AsyncAppContext,
+    ) {
+        while let Some(instructions) = instructions_rx.next().await {
+            let Some(this) = this.upgrade() else {
+                return;
+            };
+            let Ok((Some(mut projects), debounce)) = this.update(&mut cx, |this, _| {
+                let debounce = this
+                    .app_state
+                    .options
+                    .instruction_debounce
+                    .unwrap_or(DEFAULT_INSTRUCTION_DEBOUNCE);
+                (this.collect_instruction_pages(instructions), debounce)
+            }) else {
+                continue;
+            };
+
+            if !debounce.is_zero() {
+                loop {
+                    let mut timer = cx.background_executor().timer(debounce).fuse();
+                    let instructions = select_biased! {
+                        instructions = instructions_rx.next() => instructions,
+                        _ = timer => break,
+                    };
+                    let Some(instructions) = instructions else {
+                        break;
+                    };
+                    let newer_projects = this
+                        .update(&mut cx, |this, _| {
+                            this.collect_instruction_pages(instructions)
+                        })
+                        .ok()
+                        .flatten();
+                    if let Some(newer_projects) = newer_projects {
+                        log::info!(
+                            "superseding instructions received less than {:?} earlier",
+                            debounce
+                        );
+                        projects = newer_projects;
+                    }
+                }
+            }
+
+            Self::apply_instruction_batch(this, projects, cx.clone())
+                .await
+                .log_err();
+        }
+    }
+
+    async fn apply_instruction_batch(
+        this: Model<Self>,
+        projects: Vec<proto::RemoteProject>,
+        mut cx: AsyncAppContext,
+    ) -> Result<()> {
+        if this.read_with(&cx, |this, _| this.app_state.options.dry_run)? {
+            return Self::check_instruction_batch(this, projects, cx).await;
+        }
+
+        let (added_projects, removed_projects_ids, previous_desired_projects) =
+            this.update(&mut cx, |this, cx| {
+                let previous_desired_projects = mem::replace(
+                    &mut this.desired_projects,
+                    projects
+                        .iter()
+                        .map(|project| RemoteProjectId(project.id))
+                        .collect(),
+                );
+
+                let removed_projects = this
+                    .projects
+                    .keys()
+                    .filter(|remote_project_id| !this.desired_projects.contains(remote_project_id))
+                    .cloned()
+                    .collect::<Vec<_>>();
+
+                let desired_projects = &this.desired_projects;
+                this.failed_shares
+                    .retain(|remote_project_id, _| desired_projects.contains(remote_project_id));
+                this.refused_projects
+                    .retain(|remote_project_id| desired_projects.contains(remote_project_id));
+
+                this.clean_up_managed_dirs(&projects, cx);
+
+                let kept_project_count = this
+                    .projects
+                    .keys()
+                    .chain(&this.pending_shares)
+                    .filter(|remote_project_id| this.desired_projects.contains(remote_project_id))
+                    .count();
+
+                // Projects that are still being shared again after reconnecting are
+                // not shared twice; that share keeps them if they are still desired
+                // when it completes.
+                let mut added_projects = projects
+                    .into_iter()
+                    .filter(|project| {
+                        let remote_project_id = RemoteProjectId(project.id);
+                        !this.projects.contains_key(&remote_project_id)
+                            && this.pending_shares.insert(remote_project_id)
+                    })
+                    .collect::<Vec<_>>();
+                // Start sharing high-priority projects first. The sort is stable, so
+                // projects with the same priority keep the server's order.
+                added_projects.sort_by_key(|project| Reverse(project.priority.unwrap_or(0)));
+
+                if this.draining && !added_projects.is_empty() {
+                    for project in &added_projects {
+                        this.pending_shares.remove(&RemoteProjectId(project.id));
+                    }
+                    log::info!(
+                        "draining, not sharing remote projects {:?}",
+                        added_projects
+                            .iter()
+                            .map(|project| project.id)
+                            .collect::<Vec<_>>()
+                    );
+                    added_projects.clear();
+                }
+
+                if let Some(max_projects) = this.app_state.options.max_projects {
+                    let capacity = max_projects.saturating_sub(kept_project_count);
+                    if added_projects.len() > capacity {
+                        let skipped_projects = added_projects
+                            .split_off(capacity)
+                            .into_iter()
+                            .map(|project| RemoteProjectId(project.id))
+                            .collect::<Vec<_>>();
+                        let error = format!(
+                            "the dev server already shares the maximum of {} projects",
+                            max_projects
+                        );
+                        for remote_project_id in &skipped_projects {
+                            this.pending_shares.remove(remote_project_id);
+                            if this.refused_projects.insert(*remote_project_id) {
+                                Self::send_project_status(
+                                    &this.client,
+                                    *remote_project_id,
+                                    &ProjectStatus::Errored(error.clone()),
+                                );
+                            }
+                        }
+                        log::warn!(
+                            "not sharing remote projects {:?}: at most {} projects can be shared",
+                            skipped_projects.iter().map(|id| id.0).collect::<Vec<_>>(),
+                            max_projects
+                        );
+                        cx.emit(Event::ProjectsSkipped(skipped_projects));
+                    }
+                }
+
+                log::info!(
+                    event = "instructions",
+                    added = added_projects.len(),
+                    removed = removed_projects.len();
+                    "received instructions to share {} and unshare {} remote projects",
+                    added_projects.len(),
+                    removed_projects.len()
+                );
+                (added_projects, removed_projects, previous_desired_projects)
+            })?;
+
+        // Finish unsharing removed projects before sharing anything, so that a
+        // project that is removed and then re-added is never shared twice.
+        // Transactional batches only unshare them once everything is shared.
+        let transactional = this.read_with(&cx, |this, _| {
+            this.app_state.options.transactional_instructions
+        })?;
+        if !transactional {
+            Self::unshare_removed_projects(&this, &removed_projects_ids, &mut cx)?;
+        }
+
+        // Share projects concurrently, so that one on a slow mount doesn't hold up the rest.
+        let results = future::join_all(added_projects.iter().map(|remote_project| {
+            let this = this.clone();
+            let mut cx = cx.clone();
+            async move {
+                let result = DevServer::share_project(this, remote_project, &mut cx).await;
+                if let Err(error) = &result {
+                    log::error!(
+                        event = "share_failed",
+                        remote_project_id = remote_project.id;
+                        "failed to share remote project {}: {:?}",
+                        remote_project.id,
+                        error
+                    );
+                }
+                result
+            }
+        }))
+        .await;
+        let result = results.into_iter().collect::<Result<()>>();
+        if !transactional {
+            return result;
+        }
+
+        match result {
+            Ok(()) => Self::unshare_removed_projects(&this, &removed_projects_ids, &mut cx),
+            Err(error) => {
+                this.update(&mut cx, |this, cx| {
+                    this.rollback_instruction_batch(&added_projects, previous_desired_projects, cx)
+                })?;
+                Err(error)
+            }
+        }
+    }
+
+    fn unshare_removed_projects(
+        this: &Model<Self>,
+        removed_projects_ids: &[RemoteProjectId],
+        cx: &mut AsyncAppContext,
+    ) -> Result<()> {
+        this.update(cx, |this, cx| {
+            for old_project_id in removed_projects_ids {
+                this.unshare_project(old_project_id, cx)?;
+            }
+            Ok(())
+        })?
+    }
+
+    /// Restores the projects that were desired before a transactional batch
+    /// of instructions, unsharing the projects the batch managed to share.
+    fn rollback_instruction_batch(
+        &mut self,
+        added_projects: &[proto::RemoteProject],
+        previous_desired_projects: HashSet<RemoteProjectId>,
+        cx: &mut ModelContext<Self>,
+    ) {
+        self.desired_projects = previous_desired_projects;
+        let desired_projects = &self.desired_projects;
+        self.failed_shares
+            .retain(|remote_project_id, _| desired_projects.contains(remote_project_id));
+
+        let rolled_back_projects = added_projects
+            .iter()
+            .map(|project| RemoteProjectId(project.id))
+            .filter(|remote_project_id| self.projects.contains_key(remote_project_id))
+            .collect::<Vec<_>>();
+        for remote_project_id in &rolled_back_projects {
+            self.unshare_project(remote_project_id, cx).log_err();
+        }
+        log::warn!(
+            event = "instructions_rolled_back",
+            rolled_back = rolled_back_projects.len();
+            "failed to apply instructions, unshared remote projects {:?}",
+            rolled_back_projects.iter().map(|id| id.0).collect::<Vec<_>>()
+        );
+        cx.emit(Event::InstructionsRolledBack(rolled_back_projects));
+    }
+
+    /// Removes the directories in the managed root that none of `projects`
+    /// use. This only happens for the first instructions after startup, as
+    /// those describe every project that an earlier run could have left behind.
+    fn clean_up_managed_dirs(
+        &mut self,
+        projects: &[proto::RemoteProject],
+        cx: &mut ModelContext<Self>,
+    ) {
+        if mem::replace(&mut self.cleaned_up_managed_dirs, true) {
+            return;
+        }
+        let Some(managed_dirs) = self.app_state.options.managed_dirs.clone() else {
+            return;
+        };
+
+        let fs = self.app_state.fs.clone();
+        let now = self.app_state.clock.utc_now();
+        let referenced_paths = projects
+            .iter()
+            .map(|project| PathBuf::from(&project.path))
+            .collect::<Vec<_>>();
+        cx.background_executor()
+            .spawn(
+                async move {
+                    if !fs.is_dir(&managed_dirs.root).await {
+                        return Ok(());
+                    }
+
+                    let stale_after = chrono::Duration::from_std(managed_dirs.stale_after)?;
+                    let mut entries = fs.read_dir(&managed_dirs.root).await?;
+                    while let Some(path) = entries.next().await {
+                        let path = path?;
+                        let Some(metadata) = fs.metadata(&path).await? else {
+                            continue;
+                        };
+                        let referenced = referenced_paths.iter().any(|project_path| {
+                            project_path.starts_with(&path) || path.starts_with(project_path)
+                        });
+                        let modified_at = DateTime::<Utc>::from(metadata.mtime);
+                        if !metadata.is_dir || referenced || now - modified_at < stale_after {
+                            continue;
+                        }
+
+                        log::info!("removing orphaned managed directory {:?}", path);
+                        fs.remove_dir(
+                            &path,
+                            RemoveOptions {
+                                recursive: true,
+                                ignore_if_not_exists: true,
+                            },
+                        )
+                        .await
+                        .log_err();
+                    }
+                    anyhow::Ok(())
+                }
+                .log_err(),
+            )
+            .detach();
+    }
+
+    /// Logs what applying instructions would do and reports whether each
+    /// added project's path can be shared, without sharing it. See
+    /// [`crate::DevServerOptions::dry_run`].
+    async fn check_instruction_batch(
+        this: Model<Self>,
+        projects: Vec<proto::RemoteProject>,
+        mut cx: AsyncAppContext,
+    ) -> Result<()> {
+        let (fs, added_projects, removed_project_ids) = this.read_with(&cx, |this, _| {
+            let added_projects = projects
+                .iter()
+                .filter(|project| !this.projects.contains_key(&RemoteProjectId(project.id)))
+                .cloned()
+                .collect::<Vec<_>>();
+            let mut removed_project_ids = this
+                .projects
+                .keys()
+                .filter(|remote_project_id| {
+                    !projects
+                        .iter()
+                        .any(|project| project.id == remote_project_id.0)
+                })
+                .map(|remote_project_id| remote_project_id.0)
+                .collect::<Vec<_>>();
+            removed_project_ids.sort_unstable();
+            (
+                this.app_state.fs.clone(),
+                added_projects,
+                removed_project_ids,
+            )
+        })?;
+        log::info!(
+            event = "dry_run",
+            added = added_projects.len(),
+            removed = removed_project_ids.len();
+            "dry run: would share remote projects {:?} and unshare remote projects {:?}",
+            added_projects.iter().map(|project| project.id).collect::<Vec<_>>(),
+            removed_project_ids
+        );
+
+        for remote_project in &added_projects {
+            let status = match resolve_project_dir(fs.as_ref(), &remote_project.path).await {
+                Ok(path) => {
+                    log::info!(
+                        event = "dry_run_project",
+                        remote_project_id = remote_project.id;
+                        "dry run: would share {} for remote project {}",
+                        path.display(),
+                        remote_project.id
+                    );
+                    ProjectStatus::Healthy
+                }
+                Err(error) => {
+                    log::warn!(
+                        event = "dry_run_project",
+                        remote_project_id = remote_project.id;
+                        "dry run: could not share remote project {}: {:?}",
+                        remote_project.id,
+                        error
+                    );
+                    ProjectStatus::Errored(error.to_string())
+                }
+            };
+            this.update(&mut cx, |this, _| {
+                Self::send_project_status(&this.client, RemoteProjectId(remote_project.id), &status)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Accumulates paginated instructions, returning the full project list once
+    /// the final page has arrived. Unpaginated instructions are returned as-is.
+    fn collect_instruction_pages(
+        &mut self,
+        instructions: proto::DevServerInstructions,
+    ) -> Option<Vec<proto::RemoteProject>> {
+        if instructions.page_count <= 1 {
+            self.instruction_pages.clear();
+            return Some(instructions.projects);
+        }
+
+        if instructions.page == 0 {
+            self.instruction_pages.clear();
+        } else if instructions.page != self.next_instruction_page {
+            log::warn!(
+                "received instructions page {} of {}, expected page {}",
+                instructions.page,
+                instructions.page_count,
+                self.next_instruction_page
+            );
+            self.instruction_pages.clear();
+            self.next_instruction_page = 0;
+            return None;
+        }
+
+        self.instruction_pages.extend(instructions.projects);
+        if instructions.page + 1 < instructions.page_count {
+            self.next_instruction_page = instructions.page + 1;
+            None
+        } else {
+            self.next_instruction_page = 0;
+            Some(mem::take(&mut self.instruction_pages))
+        }
+    }
+}
+
+/// Resolves the path of a project to share, checking that it is a directory.
+async fn resolve_project_dir(fs: &dyn Fs, path: &str) -> Result<PathBuf> {
+    let resolved_path = fs
+        .canonicalize(Path::new(path))
+        .await
+        .with_context(|| format!("{} does not exist", path))?;
+    let metadata = fs
+        .metadata(&resolved_path)
+        .await?
+        .with_context(|| format!("{} does not exist", path))?;
+    if !metadata.is_dir {
+        return Err(anyhow!("{} is not a directory", path));
+    }
+    Ok(resolved_path)
+}
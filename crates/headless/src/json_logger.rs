@@ -0,0 +1,108 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use log::{
+    kv::{self, Key, Value, Visitor},
+    LevelFilter, Log, Metadata, Record, SetLoggerError,
+};
+use serde_json::{Map, Value as JsonValue};
+use std::io::Write;
+
+/// Logs one JSON object per line to stderr, for log collectors that ingest
+/// structured logs.
+///
+/// Besides `level`, `ts`, `target` and `message`, each object has a field for
+/// every key-value pair on the record, such as the `event`, `project_id` and
+/// `remote_project_id` that the dev server attaches to its logs.
+pub fn init_json_logger(level: LevelFilter) -> Result<(), SetLoggerError> {
+    log::set_boxed_logger(Box::new(JsonLogger { level }))?;
+    log::set_max_level(level);
+    Ok(())
+}
+
+struct JsonLogger {
+    level: LevelFilter,
+}
+
+impl Log for JsonLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let line = format_record(record, Utc::now());
+        writeln!(std::io::stderr().lock(), "{}", line).ok();
+    }
+
+    fn flush(&self) {
+        std::io::stderr().flush().ok();
+    }
+}
+
+fn format_record(record: &Record, timestamp: DateTime<Utc>) -> String {
+    let mut fields = Map::new();
+    fields.insert("level".into(), record.level().as_str().into());
+    fields.insert(
+        "ts".into(),
+        timestamp
+            .to_rfc3339_opts(SecondsFormat::Millis, true)
+            .into(),
+    );
+    fields.insert("target".into(), record.target().into());
+    fields.insert("message".into(), record.args().to_string().into());
+    record
+        .key_values()
+        .visit(&mut FieldVisitor(&mut fields))
+        .ok();
+    JsonValue::Object(fields).to_string()
+}
+
+struct FieldVisitor<'a>(&'a mut Map<String, JsonValue>);
+
+impl<'kvs> Visitor<'kvs> for FieldVisitor<'_> {
+    fn visit_pair(&mut self, key: Key<'kvs>, value: Value<'kvs>) -> Result<(), kv::Error> {
+        let value = serde_json::to_value(&value).unwrap_or_else(|_| value.to_string().into());
+        self.0.insert(key.as_str().to_string(), value);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use log::Level;
+    use serde_json::json;
+
+    #[test]
+    fn test_format_record() {
+        let key_values = [
+            ("event", Value::from("project_shared")),
+            ("remote_project_id", Value::from(7u64)),
+        ];
+        let line = format_record(
+            &Record::builder()
+                .args(format_args!("shared {}", "/remote"))
+                .level(Level::Info)
+                .target("headless")
+                .key_values(&key_values)
+                .build(),
+            Utc.with_ymd_and_hms(2024, 4, 1, 12, 30, 0).unwrap(),
+        );
+
+        assert_eq!(
+            serde_json::from_str::<JsonValue>(&line).unwrap(),
+            json!({
+                "level": "INFO",
+                "ts": "2024-04-01T12:30:00.000Z",
+                "target": "headless",
+                "message": "shared /remote",
+                "event": "project_shared",
+                "remote_project_id": 7,
+            })
+        );
+        assert!(!line.contains('\n'));
+    }
+}
@@ -0,0 +1,164 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use client::{RemoteProjectId, UserId};
+use clock::SystemClock;
+use fs::{Fs, RemoveOptions, RenameOptions};
+use futures::{channel::mpsc, StreamExt};
+use gpui::AppContext;
+use rpc::proto;
+use std::{path::PathBuf, sync::Arc};
+use util::TryFutureExt;
+
+/// Per-project log files are moved to `<remote-project-id>.log.1`, replacing any
+/// previous one, once they grow past this size.
+pub(crate) const MAX_PROJECT_LOG_LEN: usize = 1024 * 1024;
+
+/// What happens to a project's log file once the project is unshared.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProjectLogRetention {
+    /// Delete the log file, along with any file it was rotated to.
+    #[default]
+    Delete,
+    /// Move the log file to `<remote-project-id>.log.old`, replacing any
+    /// previous one, so that the last session can still be inspected.
+    Rotate,
+}
+
+enum ProjectLogMessage {
+    Line(String),
+    Close,
+}
+
+/// Appends lines to a shared project's file in [`crate::DevServerOptions::project_log_dir`].
+#[derive(Clone)]
+pub(crate) struct ProjectLog {
+    clock: Arc<dyn SystemClock>,
+    tx: mpsc::UnboundedSender<ProjectLogMessage>,
+}
+
+impl ProjectLog {
+    pub fn new(
+        fs: Arc<dyn Fs>,
+        clock: Arc<dyn SystemClock>,
+        path: PathBuf,
+        retention: ProjectLogRetention,
+        cx: &AppContext,
+    ) -> Self {
+        let (tx, rx) = mpsc::unbounded();
+        cx.background_executor()
+            .spawn(Self::maintain(fs, path, retention, rx).log_err())
+            .detach();
+        Self { clock, tx }
+    }
+
+    pub fn log(&self, message: String) {
+        let line = format!("{} {}", self.clock.utc_now().to_rfc3339(), message);
+        self.tx.unbounded_send(ProjectLogMessage::Line(line)).ok();
+    }
+
+    /// Applies the retention policy once everything logged so far is written.
+    pub fn close(&self) {
+        self.tx.unbounded_send(ProjectLogMessage::Close).ok();
+    }
+
+    async fn maintain(
+        fs: Arc<dyn Fs>,
+        path: PathBuf,
+        retention: ProjectLogRetention,
+        mut messages: mpsc::UnboundedReceiver<ProjectLogMessage>,
+    ) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs.create_dir(dir).await?;
+        }
+
+        // Each time a project is shared, its log starts out empty.
+        fs.atomic_write(path.clone(), String::new()).await?;
+        let rotated_path = path.with_extension("log.1");
+        let mut len = 0;
+        let mut closed = false;
+        while !closed {
+            // If the dev server goes away without unsharing, leave the log in place.
+            let Some(mut message) = messages.next().await else {
+                return Ok(());
+            };
+            let mut contents = String::new();
+            // Write everything that has queued up at once, rather than a line at a time.
+            loop {
+                match message {
+                    ProjectLogMessage::Line(line) => {
+                        contents.push_str(&line);
+                        contents.push('\n');
+                    }
+                    ProjectLogMessage::Close => closed = true,
+                }
+                match messages.try_next() {
+                    Ok(Some(next)) => message = next,
+                    _ => break,
+                }
+            }
+
+            if len > 0 && len + contents.len() > MAX_PROJECT_LOG_LEN {
+                fs.rename(
+                    &path,
+                    &rotated_path,
+                    RenameOptions {
+                        overwrite: true,
+                        ..Default::default()
+                    },
+                )
+                .await?;
+                len = 0;
+            }
+            len += contents.len();
+            fs.append(&path, contents).await?;
+        }
+
+        match retention {
+            ProjectLogRetention::Delete => {
+                for path in [&path, &rotated_path] {
+                    fs.remove_file(
+                        path,
+                        RemoveOptions {
+                            ignore_if_not_exists: true,
+                            ..Default::default()
+                        },
+                    )
+                    .await?;
+                }
+                Ok(())
+            }
+            ProjectLogRetention::Rotate => {
+                fs.rename(
+                    &path,
+                    &path.with_extension("log.old"),
+                    RenameOptions {
+                        overwrite: true,
+                        ..Default::default()
+                    },
+                )
+                .await
+            }
+        }
+    }
+}
+
+/// Receives a record of every collaborator joining or leaving a shared project.
+pub trait AccessLog: Send + Sync {
+    fn record(&self, event: AccessEvent);
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AccessEvent {
+    pub kind: AccessKind,
+    pub remote_project_id: RemoteProjectId,
+    pub project_id: Option<u64>,
+    pub user_id: UserId,
+    pub peer_id: proto::PeerId,
+    pub timestamp: DateTime<Utc>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessKind {
+    Joined,
+    Left,
+}
@@ -0,0 +1,127 @@
+use crate::DevServer;
+use anyhow::Result;
+use futures::{channel::mpsc, StreamExt};
+use gpui::{AsyncAppContext, WeakModel};
+use std::fmt::Write as _;
+use util::ResultExt;
+
+/// A snapshot of the dev server's activity, for monitoring.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Metrics {
+    pub connected: bool,
+    pub shared_projects: usize,
+    pub pending_shares: usize,
+    pub collaborators: usize,
+    pub connections: u64,
+    pub shares_succeeded: u64,
+    pub shares_failed: u64,
+    pub idle_unshares: u64,
+}
+
+impl Metrics {
+    /// Serializes the metrics in Prometheus' text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let metrics = [
+            (
+                "connected",
+                "gauge",
+                "Whether the dev server is connected to the server.",
+                self.connected as u64,
+            ),
+            (
+                "shared_projects",
+                "gauge",
+                "Projects currently shared.",
+                self.shared_projects as u64,
+            ),
+            (
+                "pending_shares",
+                "gauge",
+                "Projects currently being shared.",
+                self.pending_shares as u64,
+            ),
+            (
+                "collaborators",
+                "gauge",
+                "Collaborators in all shared projects.",
+                self.collaborators as u64,
+            ),
+            (
+                "connections_total",
+                "counter",
+                "Times the dev server has connected to the server.",
+                self.connections,
+            ),
+            (
+                "shares_succeeded_total",
+                "counter",
+                "Projects shared successfully.",
+                self.shares_succeeded,
+            ),
+            (
+                "shares_failed_total",
+                "counter",
+                "Projects that failed to be shared.",
+                self.shares_failed,
+            ),
+            (
+                "idle_unshares_total",
+                "counter",
+                "Projects unshared for being idle.",
+                self.idle_unshares,
+            ),
+        ];
+
+        let mut output = String::new();
+        for (name, kind, help, value) in metrics {
+            writeln!(output, "# HELP zed_dev_server_{name} {help}").unwrap();
+            writeln!(output, "# TYPE zed_dev_server_{name} {kind}").unwrap();
+            writeln!(output, "zed_dev_server_{name} {value}").unwrap();
+        }
+        output
+    }
+}
+
+impl DevServer {
+    pub fn metrics(&self) -> Metrics {
+        Metrics {
+            connected: self.client.status().borrow().is_connected(),
+            shared_projects: self.projects.len(),
+            pending_shares: self.pending_shares.len(),
+            collaborators: self
+                .projects
+                .values()
+                .map(|shared_project| shared_project.collaborators.len())
+                .sum(),
+            connections: self.counters.connections,
+            shares_succeeded: self.counters.shares_succeeded,
+            shares_failed: self.counters.shares_failed,
+            idle_unshares: self.counters.idle_unshares,
+        }
+    }
+
+    pub(crate) async fn serve_metrics(
+        this: WeakModel<Self>,
+        mut requests: mpsc::UnboundedReceiver<tiny_http::Request>,
+        mut cx: AsyncAppContext,
+    ) -> Result<()> {
+        while let Some(request) = requests.next().await {
+            let Some(this) = this.upgrade() else {
+                return Ok(());
+            };
+            let body = this.read_with(&cx, |this, _| this.metrics().to_prometheus())?;
+            let response = tiny_http::Response::from_string(body).with_header(
+                tiny_http::Header::from_bytes(
+                    &b"Content-Type"[..],
+                    &b"text/plain; version=0.0.4"[..],
+                )
+                .unwrap(),
+            );
+            cx.background_executor()
+                .spawn(async move { request.respond(response) })
+                .await
+                .log_err();
+        }
+        Ok(())
+    }
+}
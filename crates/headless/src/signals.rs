@@ -0,0 +1,99 @@
+#[cfg(unix)]
+use anyhow::Result;
+use futures::channel::mpsc;
+use std::sync::Arc;
+#[cfg(unix)]
+use util::ResultExt;
+
+/// Where [`request_interrupt`] delivers interrupts, set by [`crate::init`].
+pub(crate) static INTERRUPT_TX: std::sync::Mutex<Option<mpsc::UnboundedSender<()>>> =
+    std::sync::Mutex::new(None);
+
+/// Asks the dev server to shut down, as if Ctrl-C had been pressed.
+///
+/// The dev server can't install its own Ctrl-C handler in a process that
+/// already has one, so that handler should call this instead.
+pub fn request_interrupt() {
+    if let Some(interrupt_tx) = INTERRUPT_TX.lock().unwrap().as_ref() {
+        interrupt_tx.unbounded_send(()).ok();
+    } else {
+        log::warn!("interrupt requested before the dev server was started");
+    }
+}
+
+/// Delivers interrupts to `interrupt_tx`, both from [`request_interrupt`] and
+/// from the process receiving Ctrl-C or, on Unix, SIGTERM.
+pub(crate) fn handle_interrupts(interrupt_tx: mpsc::UnboundedSender<()>) {
+    *INTERRUPT_TX.lock().unwrap() = Some(interrupt_tx.clone());
+    forward_interrupts(interrupt_tx, |handler| set_interrupt_handler(handler));
+}
+
+/// Sends to `interrupt_tx` when the process is interrupted, using the handler
+/// installed by `set_handler`.
+pub(crate) fn forward_interrupts(
+    interrupt_tx: mpsc::UnboundedSender<()>,
+    set_handler: impl FnOnce(Box<dyn FnOnce() + Send>) -> Result<(), ctrlc::Error>,
+) {
+    if let Err(error) = set_handler(Box::new(move || {
+        interrupt_tx.unbounded_send(()).ok();
+    })) {
+        log::error!(
+            "failed to set Ctrl-C handler, waiting for request_interrupt instead: {}",
+            error
+        );
+    }
+}
+
+/// Calls `f` the first time the process receives Ctrl-C or, on Unix, SIGTERM.
+fn set_interrupt_handler<F>(f: F) -> Result<(), ctrlc::Error>
+where
+    F: FnOnce() + 'static + Send,
+{
+    let f = Arc::new(std::sync::Mutex::new(Some(f)));
+    let handler = move || {
+        if let Some(f) = f.lock().ok().and_then(|mut guard| guard.take()) {
+            f();
+        }
+    };
+    ctrlc::set_handler(handler.clone())?;
+    #[cfg(unix)]
+    handle_termination_signals(handler).log_err();
+    Ok(())
+}
+
+#[cfg(unix)]
+pub(crate) fn handle_termination_signals(handler: impl Fn() + 'static + Send) -> Result<()> {
+    handle_signals("termination signals", signal_hook::consts::SIGTERM, handler)
+}
+
+/// Calls `handler` each time the process receives SIGHUP, which asks the dev
+/// server to read its token file again.
+#[cfg(unix)]
+pub(crate) fn handle_reload_signals(handler: impl Fn() + 'static + Send) -> Result<()> {
+    handle_signals("reload signals", signal_hook::consts::SIGHUP, handler)
+}
+
+/// Calls `handler` each time the process receives SIGUSR1, which asks the dev
+/// server to drain before a restart.
+#[cfg(unix)]
+pub(crate) fn handle_drain_signals(handler: impl Fn() + 'static + Send) -> Result<()> {
+    handle_signals("drain signals", signal_hook::consts::SIGUSR1, handler)
+}
+
+#[cfg(unix)]
+fn handle_signals(
+    thread_name: &str,
+    signal: std::ffi::c_int,
+    handler: impl Fn() + 'static + Send,
+) -> Result<()> {
+    let mut signals = signal_hook::iterator::Signals::new([signal])?;
+    std::thread::Builder::new()
+        .name(thread_name.into())
+        .spawn(move || {
+            for signal in signals.forever() {
+                log::info!("received signal {}", signal);
+                handler();
+            }
+        })?;
+    Ok(())
+}
@@ -32,7 +32,7 @@ use parking_lot::Mutex;
 use regex::Regex;
 use schemars::{
     gen::SchemaGenerator,
-    schema::{InstanceType, Schema, SchemaObject},
+    schema::{InstanceType, RootSchema, Schema, SchemaObject},
     JsonSchema,
 };
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
@@ -209,6 +209,10 @@ impl CachedLspAdapter {
         self.adapter.code_action_kinds()
     }
 
+    pub fn requires_node_runtime(&self) -> bool {
+        self.adapter.requires_node_runtime()
+    }
+
     pub fn process_diagnostics(&self, params: &mut lsp::PublishDiagnosticsParams) {
         self.adapter.process_diagnostics(params)
     }
@@ -388,6 +392,12 @@ pub trait LspAdapter: 'static + Send + Sync {
         true
     }
 
+    /// Returns `true` if the language server runs on Node, so it can't start
+    /// when no Node runtime is available.
+    fn requires_node_runtime(&self) -> bool {
+        false
+    }
+
     async fn installation_test_binary(
         &self,
         container_dir: PathBuf,
@@ -463,6 +473,12 @@ pub trait LspAdapter: 'static + Send + Sync {
         Ok(serde_json::json!({}))
     }
 
+    /// Returns a JSON schema for the server's `settings` in the `lsp` settings,
+    /// which they are checked against whenever they change.
+    fn settings_schema(&self) -> Option<RootSchema> {
+        None
+    }
+
     /// Returns a list of code actions supported by a given LspAdapter
     fn code_action_kinds(&self) -> Option<Vec<CodeActionKind>> {
         Some(vec![
@@ -733,6 +749,7 @@ pub struct FakeLspAdapter {
     pub disk_based_diagnostics_sources: Vec<String>,
     pub prettier_plugins: Vec<&'static str>,
     pub language_server_binary: LanguageServerBinary,
+    pub requires_node_runtime: bool,
 }
 
 /// Configuration of handling bracket pairs for a given language.
@@ -1526,6 +1543,7 @@ impl Default for FakeLspAdapter {
                 arguments: vec![],
                 env: Default::default(),
             },
+            requires_node_runtime: false,
         }
     }
 }
@@ -1576,6 +1594,10 @@ impl LspAdapter for FakeLspAdapter {
         unreachable!();
     }
 
+    fn requires_node_runtime(&self) -> bool {
+        self.requires_node_runtime
+    }
+
     fn process_diagnostics(&self, _: &mut lsp::PublishDiagnosticsParams) {}
 
     fn disk_based_diagnostic_sources(&self) -> Vec<String> {
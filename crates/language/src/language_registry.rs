@@ -703,6 +703,8 @@ impl LanguageRegistry {
         adapter: Arc<CachedLspAdapter>,
         root_path: Arc<Path>,
         delegate: Arc<dyn LspAdapterDelegate>,
+        env_overrides: HashMap<String, Option<String>>,
+        binary_paths: Vec<PathBuf>,
         cx: &mut AppContext,
     ) -> Option<PendingLanguageServer> {
         let server_id = self.state.write().next_language_server_id();
@@ -741,7 +743,8 @@ impl LanguageRegistry {
 
                 delegate.update_status(adapter.name.clone(), LanguageServerBinaryStatus::None);
 
-                let binary = binary_result?;
+                let mut binary = binary_result?;
+                let unset_env = apply_env_overrides(&mut binary, env_overrides);
                 let options = adapter
                     .adapter
                     .clone()
@@ -802,12 +805,22 @@ impl LanguageRegistry {
                     return Ok((server, options));
                 }
 
+                // When the configured binary has other candidates, fall back to
+                // the ones after it if it can't be spawned.
+                let fallback_paths = binary_paths
+                    .iter()
+                    .skip_while(|path| **path != binary.path)
+                    .skip(1)
+                    .cloned()
+                    .collect::<Vec<_>>();
                 drop(this);
                 Ok((
-                    lsp::LanguageServer::new(
+                    lsp::LanguageServer::new_with_fallbacks(
                         stderr_capture,
                         server_id,
                         binary,
+                        &fallback_paths,
+                        &unset_env,
                         &root_path,
                         adapter.code_action_kinds(),
                         cx,
@@ -936,3 +949,31 @@ impl LspBinaryStatusSender {
         txs.retain(|tx| tx.unbounded_send((name.clone(), status.clone())).is_ok());
     }
 }
+
+/// Applies the environment variables configured for a language server to its
+/// binary, returning the names of the variables to remove from the environment
+/// the server inherits.
+fn apply_env_overrides(
+    binary: &mut lsp::LanguageServerBinary,
+    env_overrides: HashMap<String, Option<String>>,
+) -> Vec<String> {
+    let mut unset_env = Vec::new();
+    for (name, value) in env_overrides {
+        match value {
+            Some(value) => {
+                binary
+                    .env
+                    .get_or_insert_with(Default::default)
+                    .insert(name, value);
+            }
+            None => {
+                if let Some(env) = binary.env.as_mut() {
+                    env.remove(&name);
+                }
+                unset_env.push(name);
+            }
+        }
+    }
+    unset_env.sort();
+    unset_env
+}
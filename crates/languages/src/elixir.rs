@@ -275,9 +275,8 @@ impl LspAdapter for ElixirLspAdapter {
     ) -> Result<Value> {
         let settings = cx.update(|cx| {
             ProjectSettings::get_global(cx)
-                .lsp
-                .get("elixir-ls")
-                .and_then(|s| s.settings.clone())
+                .lsp_settings("elixir-ls")
+                .and_then(|s| s.settings)
                 .unwrap_or_default()
         })?;
 
@@ -40,6 +40,10 @@ impl LspAdapter for ElmLspAdapter {
         LanguageServerName(SERVER_NAME.into())
     }
 
+    fn requires_node_runtime(&self) -> bool {
+        true
+    }
+
     async fn fetch_latest_server_version(
         &self,
         _: &dyn LspAdapterDelegate,
@@ -103,9 +107,8 @@ impl LspAdapter for ElmLspAdapter {
         // params to be the same as lsp initialization_options
         let override_options = cx.update(|cx| {
             ProjectSettings::get_global(cx)
-                .lsp
-                .get(SERVER_NAME)
-                .and_then(|s| s.initialization_options.clone())
+                .lsp_settings(SERVER_NAME)
+                .and_then(|s| s.initialization_options)
                 .unwrap_or_default()
         })?;
 
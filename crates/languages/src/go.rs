@@ -5,7 +5,7 @@ use gpui::{AsyncAppContext, Task};
 pub use language::*;
 use lazy_static::lazy_static;
 use lsp::LanguageServerBinary;
-use project::project_settings::{BinarySettings, ProjectSettings};
+use project::project_settings::ProjectSettings;
 use regex::Regex;
 use serde_json::json;
 use settings::Settings;
@@ -65,18 +65,22 @@ impl super::LspAdapter for GoLspAdapter {
         delegate: &dyn LspAdapterDelegate,
         cx: &AsyncAppContext,
     ) -> Option<LanguageServerBinary> {
-        let configured_binary = cx.update(|cx| {
-            ProjectSettings::get_global(cx)
-                .lsp
-                .get(Self::SERVER_NAME)
-                .and_then(|s| s.binary.clone())
+        let configured_binary = cx
+            .update(|cx| {
+                ProjectSettings::get_global(cx)
+                    .lsp_settings(Self::SERVER_NAME)
+                    .and_then(|s| s.binary)
+            })
+            .ok()
+            .flatten();
+
+        let configured_binary = configured_binary.and_then(|binary| {
+            let path = binary.resolved_path().log_err()??;
+            let arguments = binary.resolved_arguments().log_err()?;
+            Some((path, arguments))
         });
 
-        if let Ok(Some(BinarySettings {
-            path: Some(path),
-            arguments,
-        })) = configured_binary
-        {
+        if let Some((path, arguments)) = configured_binary {
             Some(LanguageServerBinary {
                 path: path.into(),
                 arguments: arguments
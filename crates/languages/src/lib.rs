@@ -97,6 +97,7 @@ pub fn init(
             // typeck helper
             let adapters: Vec<Arc<dyn LspAdapter>> = $adapters;
             for adapter in adapters {
+                register_lsp_settings_schema(&adapter, cx);
                 languages.register_lsp_adapter(config.name.clone(), adapter);
             }
             languages.register_language(
@@ -117,6 +118,7 @@ pub fn init(
             // typeck helper
             let adapters: Vec<Arc<dyn LspAdapter>> = $adapters;
             for adapter in adapters {
+                register_lsp_settings_schema(&adapter, cx);
                 languages.register_lsp_adapter(config.name.clone(), adapter);
             }
             languages.register_language(
@@ -314,6 +316,12 @@ pub fn init(
 }
 
 #[cfg(any(test, feature = "test-support"))]
+fn register_lsp_settings_schema(adapter: &Arc<dyn LspAdapter>, cx: &mut AppContext) {
+    if let Some(schema) = adapter.settings_schema() {
+        project::project_settings::register_lsp_settings_schema(adapter.name().0, schema, cx);
+    }
+}
+
 pub fn language(name: &str, grammar: tree_sitter::Language) -> Arc<Language> {
     Arc::new(
         Language::new(load_config(name), Some(grammar))
@@ -44,17 +44,16 @@ impl LspAdapter for RustLspAdapter {
         let binary = cx
             .update(|cx| {
                 ProjectSettings::get_global(cx)
-                    .lsp
-                    .get(Self::SERVER_NAME)
-                    .and_then(|s| s.binary.clone())
+                    .lsp_settings(Self::SERVER_NAME)
+                    .and_then(|s| s.binary)
             })
             .ok()??;
 
-        let path = binary.path?;
+        let path = binary.resolved_path().log_err()??;
+        let arguments = binary.resolved_arguments().log_err()?;
         Some(LanguageServerBinary {
             path: path.into(),
-            arguments: binary
-                .arguments
+            arguments: arguments
                 .unwrap_or_default()
                 .iter()
                 .map(|arg| arg.into())
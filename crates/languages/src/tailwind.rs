@@ -38,6 +38,10 @@ impl LspAdapter for TailwindLspAdapter {
         LanguageServerName("tailwindcss-language-server".into())
     }
 
+    fn requires_node_runtime(&self) -> bool {
+        true
+    }
+
     async fn fetch_latest_server_version(
         &self,
         _: &dyn LspAdapterDelegate,
@@ -8,6 +8,8 @@ use language::{LanguageServerName, LspAdapter, LspAdapterDelegate};
 use lsp::{CodeActionKind, LanguageServerBinary};
 use node_runtime::NodeRuntime;
 use project::project_settings::ProjectSettings;
+use schemars::{schema::RootSchema, JsonSchema};
+use serde_derive::Deserialize;
 use serde_json::{json, Value};
 use settings::Settings;
 use smol::{fs, io::BufReader, stream::StreamExt};
@@ -55,6 +57,10 @@ impl LspAdapter for TypeScriptLspAdapter {
         LanguageServerName("typescript-language-server".into())
     }
 
+    fn requires_node_runtime(&self) -> bool {
+        true
+    }
+
     async fn fetch_latest_server_version(
         &self,
         _: &dyn LspAdapterDelegate,
@@ -229,6 +235,39 @@ pub struct EsLintLspAdapter {
     node: Arc<dyn NodeRuntime>,
 }
 
+/// The `settings` that [`EsLintLspAdapter`] passes on to the ESLint server.
+#[allow(dead_code)]
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+struct EsLintSettings {
+    code_action_on_save: Option<EsLintCodeActionOnSave>,
+    problems: Option<EsLintProblems>,
+    node_path: Option<String>,
+}
+
+#[allow(dead_code)]
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct EsLintCodeActionOnSave {
+    enable: Option<bool>,
+    mode: Option<EsLintCodeActionMode>,
+    rules: Option<Vec<String>>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+enum EsLintCodeActionMode {
+    All,
+    Problems,
+}
+
+#[allow(dead_code)]
+#[derive(Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields, rename_all = "camelCase")]
+struct EsLintProblems {
+    shorten_to_single_line: Option<bool>,
+}
+
 impl EsLintLspAdapter {
     const CURRENT_VERSION: &'static str = "release/2.4.4";
 
@@ -254,9 +293,8 @@ impl LspAdapter for EsLintLspAdapter {
 
         let eslint_user_settings = cx.update(|cx| {
             ProjectSettings::get_global(cx)
-                .lsp
-                .get(Self::SERVER_NAME)
-                .and_then(|s| s.settings.clone())
+                .lsp_settings(Self::SERVER_NAME)
+                .and_then(|s| s.settings)
                 .unwrap_or_default()
         })?;
 
@@ -316,6 +354,14 @@ impl LspAdapter for EsLintLspAdapter {
         LanguageServerName(Self::SERVER_NAME.into())
     }
 
+    fn settings_schema(&self) -> Option<RootSchema> {
+        Some(schemars::schema_for!(EsLintSettings))
+    }
+
+    fn requires_node_runtime(&self) -> bool {
+        true
+    }
+
     async fn fetch_latest_server_version(
         &self,
         _delegate: &dyn LspAdapterDelegate,
@@ -39,7 +39,8 @@ use util::{ResultExt, TryFutureExt};
 const HEADER_DELIMITER: &'static [u8; 4] = b"\r\n\r\n";
 const JSON_RPC_VERSION: &str = "2.0";
 const CONTENT_LEN_HEADER: &str = "Content-Length: ";
-const LSP_REQUEST_TIMEOUT: Duration = Duration::from_secs(60 * 2);
+/// How long to wait for a response to a request before giving up on it.
+pub const LSP_REQUEST_TIMEOUT: Duration = Duration::from_secs(60 * 2);
 const SERVER_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
 
 type NotificationHandler = Box<dyn Send + FnMut(Option<RequestId>, &str, AsyncAppContext)>;
@@ -54,6 +55,18 @@ pub enum IoKind {
     StdErr,
 }
 
+/// The error returned when a language server doesn't respond to a request in time.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestTimeout(pub Duration);
+
+impl fmt::Display for RequestTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LSP request timeout")
+    }
+}
+
+impl std::error::Error for RequestTimeout {}
+
 /// Represents a launchable language server. This can either be a standalone binary or the path
 /// to a runtime with arguments to instruct it to launch the actual language server file.
 #[derive(Debug, Clone, Deserialize)]
@@ -230,10 +243,39 @@ impl lsp_types::notification::Notification for ServerStatus {
 
 impl LanguageServer {
     /// Starts a language server process.
+    ///
+    /// The process inherits Zed's environment, with `binary.env` added and the
+    /// variables in `unset_env` removed.
     pub fn new(
         stderr_capture: Arc<Mutex<Option<String>>>,
         server_id: LanguageServerId,
         binary: LanguageServerBinary,
+        unset_env: &[String],
+        root_path: &Path,
+        code_action_kinds: Option<Vec<CodeActionKind>>,
+        cx: AsyncAppContext,
+    ) -> Result<Self> {
+        Self::new_with_fallbacks(
+            stderr_capture,
+            server_id,
+            binary,
+            &[],
+            unset_env,
+            root_path,
+            code_action_kinds,
+            cx,
+        )
+    }
+
+    /// Starts a language server process like [`LanguageServer::new`], trying
+    /// each of `fallback_paths` in turn when `binary.path` can't be spawned.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new_with_fallbacks(
+        stderr_capture: Arc<Mutex<Option<String>>>,
+        server_id: LanguageServerId,
+        binary: LanguageServerBinary,
+        fallback_paths: &[PathBuf],
+        unset_env: &[String],
         root_path: &Path,
         code_action_kinds: Option<Vec<CodeActionKind>>,
         cx: AsyncAppContext,
@@ -251,18 +293,24 @@ impl LanguageServer {
             &binary.arguments
         );
 
-        let mut command = process::Command::new(&binary.path);
-        command
-            .current_dir(working_dir)
-            .args(binary.arguments)
-            .envs(binary.env.unwrap_or_default())
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .kill_on_drop(true);
-        #[cfg(windows)]
-        command.creation_flags(windows::Win32::System::Threading::CREATE_NO_WINDOW.0);
-        let mut server = command.spawn()?;
+        let env = binary.env.unwrap_or_default();
+        let (mut server, path) = spawn_with_fallbacks(&binary.path, fallback_paths, |path| {
+            let mut command = process::Command::new(path);
+            command
+                .current_dir(working_dir)
+                .args(&binary.arguments)
+                .envs(&env)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true);
+            for name in unset_env {
+                command.env_remove(name);
+            }
+            #[cfg(windows)]
+            command.creation_flags(windows::Win32::System::Threading::CREATE_NO_WINDOW.0);
+            command.spawn()
+        })?;
 
         let stdin = server.stdin.take().unwrap();
         let stdout = server.stdout.take().unwrap();
@@ -293,7 +341,7 @@ impl LanguageServer {
             },
         );
 
-        if let Some(name) = binary.path.file_name() {
+        if let Some(name) = path.file_name() {
             server.name = name.to_string_lossy().into();
         }
 
@@ -548,9 +596,16 @@ impl LanguageServer {
     /// Note that `options` is used directly to construct [`InitializeParams`], which is why it is owned.
     ///
     /// [LSP Specification](https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#initialize)
-    pub fn initialize(
+    pub fn initialize(self, options: Option<Value>, cx: &AppContext) -> Task<Result<Arc<Self>>> {
+        self.initialize_with_timeout(options, LSP_REQUEST_TIMEOUT, cx)
+    }
+
+    /// Like [`Self::initialize`], but waits up to `timeout` for the server's response
+    /// instead of the default [`LSP_REQUEST_TIMEOUT`].
+    pub fn initialize_with_timeout(
         mut self,
         options: Option<Value>,
+        timeout: Duration,
         cx: &AppContext,
     ) -> Task<Result<Arc<Self>>> {
         let root_uri = Url::from_file_path(&self.root_path).unwrap();
@@ -702,7 +757,9 @@ impl LanguageServer {
         };
 
         cx.spawn(|_| async move {
-            let response = self.request::<request::Initialize>(params).await?;
+            let response = self
+                .request_with_timeout::<request::Initialize>(params, timeout)
+                .await?;
             if let Some(info) = response.server_info {
                 self.name = info.name.into();
             }
@@ -726,6 +783,7 @@ impl LanguageServer {
                 &response_handlers,
                 &outbound_tx,
                 &executor,
+                LSP_REQUEST_TIMEOUT,
                 (),
             );
             let exit = Self::notify_internal::<notification::Exit>(&outbound_tx, ());
@@ -949,6 +1007,19 @@ impl LanguageServer {
         &self,
         params: T::Params,
     ) -> impl LspRequestFuture<Result<T::Result>>
+    where
+        T::Result: 'static + Send,
+    {
+        self.request_with_timeout::<T>(params, LSP_REQUEST_TIMEOUT)
+    }
+
+    /// Sends a RPC request to the language server, failing with [`RequestTimeout`]
+    /// if no response arrives within `timeout`.
+    pub fn request_with_timeout<T: request::Request>(
+        &self,
+        params: T::Params,
+        timeout: Duration,
+    ) -> impl LspRequestFuture<Result<T::Result>>
     where
         T::Result: 'static + Send,
     {
@@ -957,6 +1028,7 @@ impl LanguageServer {
             &self.response_handlers,
             &self.outbound_tx,
             &self.executor,
+            timeout,
             params,
         )
     }
@@ -966,6 +1038,7 @@ impl LanguageServer {
         response_handlers: &Mutex<Option<HashMap<RequestId, ResponseHandler>>>,
         outbound_tx: &channel::Sender<String>,
         executor: &BackgroundExecutor,
+        timeout: Duration,
         params: T::Params,
     ) -> impl LspRequestFuture<Result<T::Result>>
     where
@@ -1014,7 +1087,7 @@ impl LanguageServer {
             .context("failed to write to language server's stdin");
 
         let outbound_tx = outbound_tx.downgrade();
-        let mut timeout = executor.timer(LSP_REQUEST_TIMEOUT).fuse();
+        let mut timer = executor.timer(timeout).fuse();
         let started = Instant::now();
         LspRequest::new(id, async move {
             handle_response?;
@@ -1041,9 +1114,9 @@ impl LanguageServer {
                     response?
                 }
 
-                _ = timeout => {
-                    log::error!("Cancelled LSP request task for {method:?} id {id} which took over {LSP_REQUEST_TIMEOUT:?}");
-                    anyhow::bail!("LSP request timeout");
+                _ = timer => {
+                    log::error!("Cancelled LSP request task for {method:?} id {id} which took over {timeout:?}");
+                    Err(RequestTimeout(timeout).into())
                 }
             }
         })
@@ -1370,11 +1443,78 @@ where
     }
 }
 
+/// Calls `spawn` with `path` and then with each of `fallback_paths` until it
+/// succeeds, returning its result along with the path it succeeded with.
+fn spawn_with_fallbacks<T>(
+    path: &Path,
+    fallback_paths: &[PathBuf],
+    mut spawn: impl FnMut(&Path) -> std::io::Result<T>,
+) -> Result<(T, PathBuf)> {
+    let mut path = path;
+    let mut fallback_paths = fallback_paths.iter();
+    loop {
+        match spawn(path) {
+            Ok(value) => return Ok((value, path.to_path_buf())),
+            Err(error) => {
+                let Some(fallback_path) = fallback_paths.next() else {
+                    return Err(error.into());
+                };
+                log::warn!(
+                    "failed to start language server binary {:?}, trying {:?}: {}",
+                    path,
+                    fallback_path,
+                    error
+                );
+                path = fallback_path;
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use gpui::TestAppContext;
 
+    fn spawn_test_binary(path: &Path) -> std::io::Result<Child> {
+        process::Command::new(path)
+            .arg("--list")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .kill_on_drop(true)
+            .spawn()
+    }
+
+    #[test]
+    fn test_spawn_with_missing_binary() {
+        let current_exe = std::env::current_exe().unwrap();
+        let (_child, path) = spawn_with_fallbacks(
+            Path::new("/nonexistent/language-server"),
+            &[current_exe.clone()],
+            spawn_test_binary,
+        )
+        .unwrap();
+        assert_eq!(path, current_exe);
+
+        assert!(spawn_with_fallbacks(
+            Path::new("/nonexistent/language-server"),
+            &[PathBuf::from("/nonexistent/other-language-server")],
+            spawn_test_binary,
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_spawn_with_binary_that_fails_to_spawn() {
+        // A directory exists, but can't be executed.
+        let not_executable = std::env::temp_dir();
+        let current_exe = std::env::current_exe().unwrap();
+        let (_child, path) =
+            spawn_with_fallbacks(&not_executable, &[current_exe.clone()], spawn_test_binary)
+                .unwrap();
+        assert_eq!(path, current_exe);
+    }
+
     #[ctor::ctor]
     fn init_logger() {
         if std::env::var("RUST_LOG").is_ok() {
@@ -1453,6 +1593,44 @@ mod tests {
         fake.receive_notification::<notification::Exit>().await;
     }
 
+    #[gpui::test]
+    async fn test_initialize_timeout(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            release_channel::init("0.0.0", cx);
+        });
+        let (server, fake) = FakeLanguageServer::new(
+            LanguageServerId(0),
+            LanguageServerBinary {
+                path: "path/to/language-server".into(),
+                arguments: vec![],
+                env: None,
+            },
+            "the-lsp".to_string(),
+            Default::default(),
+            cx.to_async(),
+        );
+        fake.handle_request::<request::Initialize, _, _>(|_, cx| async move {
+            cx.background_executor()
+                .timer(Duration::from_secs(60))
+                .await;
+            Ok(InitializeResult::default())
+        });
+
+        let initialize =
+            cx.update(|cx| server.initialize_with_timeout(None, Duration::from_millis(500), cx));
+        cx.executor().run_until_parked();
+        cx.executor().advance_clock(Duration::from_secs(1));
+        cx.executor().run_until_parked();
+
+        let error = initialize.await.err().unwrap();
+        assert_eq!(
+            error
+                .downcast_ref::<RequestTimeout>()
+                .map(|timeout| timeout.0),
+            Some(Duration::from_millis(500))
+        );
+    }
+
     #[gpui::test]
     async fn test_read_headers() {
         let mut buf = Vec::new();
@@ -338,7 +338,7 @@ impl FakeNodeRuntime {
 #[async_trait::async_trait]
 impl NodeRuntime for FakeNodeRuntime {
     async fn binary_path(&self) -> anyhow::Result<PathBuf> {
-        unreachable!()
+        Err(anyhow::anyhow!("node is not available"))
     }
 
     async fn run_npm_subcommand(
@@ -197,6 +197,7 @@ impl Prettier {
                 arguments: vec![prettier_server.into(), prettier_dir.as_path().into()],
                 env: None,
             },
+            &[],
             &prettier_dir,
             None,
             cx.clone(),
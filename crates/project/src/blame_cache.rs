@@ -0,0 +1,42 @@
+use std::collections::VecDeque;
+
+use git::blame::Blame;
+
+use crate::ProjectPath;
+
+/// Identifies the blame of a file at a particular buffer version and git state.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct BlameCacheKey {
+    pub path: ProjectPath,
+    pub version: clock::Global,
+    pub git_dir_scan_id: usize,
+}
+
+/// A least-recently-used cache of blame results, so that blaming an unchanged
+/// file doesn't run `git blame` again.
+///
+/// Entries are keyed by the buffer version and by the scan id of the
+/// repository's git directory, so editing the buffer or changing the
+/// repository invalidates them. Only the latest blame of each file is kept.
+#[derive(Default)]
+pub(crate) struct BlameCache {
+    entries: VecDeque<(BlameCacheKey, Blame)>,
+}
+
+impl BlameCache {
+    pub fn get(&mut self, key: &BlameCacheKey) -> Option<Blame> {
+        let ix = self.entries.iter().position(|(k, _)| k == key)?;
+        let entry = self.entries.remove(ix)?;
+        let blame = entry.1.clone();
+        self.entries.push_back(entry);
+        Some(blame)
+    }
+
+    pub fn insert(&mut self, key: BlameCacheKey, blame: Blame, capacity: usize) {
+        self.entries.retain(|(k, _)| k.path != key.path);
+        self.entries.push_back((key, blame));
+        while self.entries.len() > capacity {
+            self.entries.pop_front();
+        }
+    }
+}
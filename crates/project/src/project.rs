@@ -1,3 +1,4 @@
+mod blame_cache;
 pub mod connection_manager;
 pub mod debounced_delay;
 pub mod lsp_command;
@@ -14,6 +15,7 @@ pub mod search_history;
 
 use anyhow::{anyhow, bail, Context as _, Result};
 use async_trait::async_trait;
+use blame_cache::{BlameCache, BlameCacheKey};
 use client::{
     proto, Client, Collaborator, PendingEntitySubscription, ProjectId, TypedEnvelope, UserStore,
 };
@@ -64,7 +66,7 @@ use node_runtime::NodeRuntime;
 use parking_lot::{Mutex, RwLock};
 use postage::watch;
 use prettier_support::{DefaultPrettier, PrettierInstance};
-use project_settings::{LspSettings, ProjectSettings};
+use project_settings::{resolve_lsp_settings, LspSettings, ProjectSettings};
 use rand::prelude::*;
 use search_history::SearchHistory;
 use worktree::LocalSnapshot;
@@ -107,7 +109,7 @@ use util::{
     },
     post_inc, ResultExt, TryFutureExt as _,
 };
-use worktree::{Snapshot, Traversal};
+use worktree::{GitRepositoryChange, Snapshot, Traversal};
 
 pub use fs::*;
 pub use language::Location;
@@ -124,6 +126,9 @@ const MAX_SERVER_REINSTALL_ATTEMPT_COUNT: u64 = 4;
 const SERVER_REINSTALL_DEBOUNCE_TIMEOUT: Duration = Duration::from_secs(1);
 const SERVER_LAUNCHING_BEFORE_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
 pub const SERVER_PROGRESS_DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(100);
+/// How long settings must stay unchanged before the language servers whose
+/// settings changed are restarted.
+pub const LSP_SETTINGS_RESTART_DEBOUNCE: Duration = Duration::from_millis(500);
 
 const MAX_PROJECT_SEARCH_HISTORY_SIZE: usize = 500;
 
@@ -195,6 +200,10 @@ pub struct Project {
     buffers_being_formatted: HashSet<BufferId>,
     buffers_needing_diff: HashSet<WeakModel<Buffer>>,
     git_diff_debouncer: DebouncedDelay,
+    blame_cache: Arc<Mutex<BlameCache>>,
+    language_server_restart_debouncer: DebouncedDelay,
+    pending_language_server_restarts:
+        HashMap<(WorktreeId, Arc<str>), (Model<Worktree>, Arc<Language>)>,
     nonce: u128,
     _maintain_buffer_languages: Task<()>,
     _maintain_workspace_config: Task<Result<()>>,
@@ -202,6 +211,13 @@ pub struct Project {
     copilot_lsp_subscription: Option<gpui::Subscription>,
     copilot_log_subscription: Option<lsp::Subscription>,
     current_lsp_settings: HashMap<Arc<str>, LspSettings>,
+    /// The problems found in the language server settings that have already
+    /// been reported, so that each is only reported once.
+    lsp_settings_errors: Vec<String>,
+    /// Whether a local settings file with any invalid setting is rejected as a
+    /// whole, keeping the settings that were loaded before it.
+    keep_last_good_local_settings: bool,
+    diff_base_revisions: HashMap<WorktreeId, Option<String>>,
     node: Option<Arc<dyn NodeRuntime>>,
     default_prettier: DefaultPrettier,
     prettiers_per_worktree: HashMap<WorktreeId, HashSet<Option<PathBuf>>>,
@@ -333,6 +349,13 @@ pub enum Event {
     CollaboratorLeft(proto::PeerId),
     RefreshInlayHints,
     RevealInProjectPanel(ProjectEntryId),
+    /// A local settings file couldn't be loaded, so the settings it previously
+    /// contained remain in effect.
+    LocalSettingsFailed {
+        worktree_id: WorktreeId,
+        path: Arc<Path>,
+        message: String,
+    },
 }
 
 pub enum LanguageServerState {
@@ -527,6 +550,23 @@ impl Hover {
 #[derive(Default)]
 pub struct ProjectTransaction(pub HashMap<Model<Buffer>, language::Transaction>);
 
+/// The git blame of a single line, with the details needed to describe and
+/// open the commit that last changed it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LineBlame {
+    /// The line was last changed by a commit.
+    Committed {
+        /// The commit's SHA, author, date and summary.
+        entry: git::blame::BlameEntry,
+        /// The full message of the commit, if known.
+        message: Option<String>,
+        /// A link to the commit on the repository's hosting provider, if known.
+        permalink: Option<Url>,
+    },
+    /// The line has changes that aren't committed yet.
+    NotCommittedYet,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FormatTrigger {
     Save,
@@ -665,6 +705,11 @@ impl Project {
             let (tx, rx) = mpsc::unbounded();
             cx.spawn(move |this, cx| Self::send_buffer_ordered_messages(this, rx, cx))
                 .detach();
+            cx.spawn(|this, mut cx| async move {
+                this.update(&mut cx, |this, cx| this.report_lsp_settings_errors(cx))
+                    .ok();
+            })
+            .detach();
             let copilot_lsp_subscription =
                 Copilot::global(cx).map(|copilot| subscribe_for_copilot_events(&copilot, cx));
             let tasks = Inventory::new(cx);
@@ -711,6 +756,9 @@ impl Project {
                 buffers_being_formatted: Default::default(),
                 buffers_needing_diff: Default::default(),
                 git_diff_debouncer: DebouncedDelay::new(),
+                blame_cache: Default::default(),
+                language_server_restart_debouncer: DebouncedDelay::new(),
+                pending_language_server_restarts: HashMap::default(),
                 nonce: StdRng::from_entropy().gen(),
                 terminals: Terminals {
                     local_handles: Vec::new(),
@@ -718,6 +766,9 @@ impl Project {
                 copilot_lsp_subscription,
                 copilot_log_subscription: None,
                 current_lsp_settings: ProjectSettings::get_global(cx).lsp.clone(),
+                lsp_settings_errors: Vec::new(),
+                keep_last_good_local_settings: false,
+                diff_base_revisions: HashMap::default(),
                 node: Some(node),
                 default_prettier: DefaultPrettier::default(),
                 prettiers_per_worktree: HashMap::default(),
@@ -864,6 +915,9 @@ impl Project {
                 buffers_being_formatted: Default::default(),
                 buffers_needing_diff: Default::default(),
                 git_diff_debouncer: DebouncedDelay::new(),
+                blame_cache: Default::default(),
+                language_server_restart_debouncer: DebouncedDelay::new(),
+                pending_language_server_restarts: HashMap::default(),
                 buffer_snapshots: Default::default(),
                 nonce: StdRng::from_entropy().gen(),
                 terminals: Terminals {
@@ -872,6 +926,9 @@ impl Project {
                 copilot_lsp_subscription,
                 copilot_log_subscription: None,
                 current_lsp_settings: ProjectSettings::get_global(cx).lsp.clone(),
+                lsp_settings_errors: Vec::new(),
+                keep_last_good_local_settings: false,
+                diff_base_revisions: HashMap::default(),
                 node: None,
                 default_prettier: DefaultPrettier::default(),
                 prettiers_per_worktree: HashMap::default(),
@@ -1058,6 +1115,19 @@ impl Project {
         project
     }
 
+    /// Notifies about problems in the language server settings that haven't
+    /// been reported yet. See [`ProjectSettings::lsp_settings_errors`].
+    fn report_lsp_settings_errors(&mut self, cx: &mut ModelContext<Self>) {
+        let errors = ProjectSettings::get_global(cx).lsp_settings_errors(cx);
+        for error in &errors {
+            if !self.lsp_settings_errors.contains(error) {
+                log::warn!("{error}");
+                cx.emit(Event::Notification(error.clone()));
+            }
+        }
+        self.lsp_settings_errors = errors;
+    }
+
     fn on_settings_changed(&mut self, cx: &mut ModelContext<Self>) {
         let mut language_servers_to_start = Vec::new();
         let mut language_formatters_to_check = Vec::new();
@@ -1104,28 +1174,56 @@ impl Project {
                 let file = worktree.as_ref().and_then(|tree| {
                     tree.update(cx, |tree, cx| tree.root_file(cx).map(|f| f as _))
                 });
-                if !language_settings(Some(language), file.as_ref(), cx).enable_language_server {
+                let server_name = &adapter.name.0;
+                let new_settings =
+                    resolve_lsp_settings(&new_lsp_settings, server_name).unwrap_or_default();
+                if !new_settings.is_enabled()
+                    || !language_settings(Some(language), file.as_ref(), cx).enable_language_server
+                {
                     language_servers_to_stop.push((*worktree_id, started_lsp_name.clone()));
                 } else if let Some(worktree) = worktree {
-                    let server_name = &adapter.name.0;
-                    match (
-                        current_lsp_settings.get(server_name),
-                        new_lsp_settings.get(server_name),
-                    ) {
-                        (None, None) => {}
-                        (Some(_), None) | (None, Some(_)) => {
-                            language_servers_to_restart.push((worktree, Arc::clone(language)));
-                        }
-                        (Some(current_lsp_settings), Some(new_lsp_settings)) => {
-                            if current_lsp_settings != new_lsp_settings {
-                                language_servers_to_restart.push((worktree, Arc::clone(language)));
-                            }
-                        }
+                    let current_settings =
+                        resolve_lsp_settings(current_lsp_settings, server_name).unwrap_or_default();
+                    if current_settings.requires_restart(&new_settings) {
+                        language_servers_to_restart.push((worktree, Arc::clone(language)));
                     }
                 }
             }
         }
         self.current_lsp_settings = new_lsp_settings;
+        self.report_lsp_settings_errors(cx);
+
+        // Reload the diff bases of the worktrees whose diff base revision changed.
+        let mut worktrees_to_rediff = Vec::new();
+        let mut diff_base_revisions = HashMap::default();
+        for worktree in self.worktrees() {
+            if !worktree.read(cx).is_local() {
+                continue;
+            }
+            let worktree_id = worktree.read(cx).id();
+            let revision = diff_base_revision(&worktree, Path::new(""), cx);
+            let previous_revision = self.diff_base_revisions.get(&worktree_id).cloned();
+            if previous_revision.flatten() != revision {
+                worktrees_to_rediff.push(worktree);
+            }
+            diff_base_revisions.insert(worktree_id, revision);
+        }
+        self.diff_base_revisions = diff_base_revisions;
+        for worktree in worktrees_to_rediff {
+            let repositories: UpdatedGitRepositoriesSet = worktree
+                .read(cx)
+                .repositories()
+                .map(|(work_directory, _)| {
+                    (
+                        work_directory.clone(),
+                        GitRepositoryChange {
+                            old_repository: None,
+                        },
+                    )
+                })
+                .collect();
+            self.update_local_worktree_buffers_git_repos(worktree, &repositories, cx);
+        }
 
         // Stop all newly-disabled language servers.
         for (worktree_id, adapter_name) in language_servers_to_stop {
@@ -1153,9 +1251,26 @@ impl Project {
             self.start_language_servers(&worktree, language, cx);
         }
 
-        // Restart all language servers with changed initialization options.
-        for (worktree, language) in language_servers_to_restart {
-            self.restart_language_servers(worktree, language, cx);
+        // Restart all language servers with changed initialization options,
+        // once the settings stop changing.
+        if !language_servers_to_restart.is_empty() {
+            for (worktree, language) in language_servers_to_restart {
+                self.pending_language_server_restarts.insert(
+                    (worktree.read(cx).id(), language.name()),
+                    (worktree, language),
+                );
+            }
+            self.language_server_restart_debouncer.fire_new(
+                LSP_SETTINGS_RESTART_DEBOUNCE,
+                cx,
+                |this, cx| {
+                    let restarts = mem::take(&mut this.pending_language_server_restarts);
+                    for (worktree, language) in restarts.into_values() {
+                        this.restart_language_servers(worktree, language, cx);
+                    }
+                    Task::ready(())
+                },
+            );
         }
 
         if self.copilot_lsp_subscription.is_none() {
@@ -1264,6 +1379,14 @@ impl Project {
         &mut self.search_history
     }
 
+    /// Makes local settings files that contain any invalid setting be rejected
+    /// as a whole, instead of applying the settings that are valid. The settings
+    /// that were last loaded from such a file stay in effect, and
+    /// [`Event::LocalSettingsFailed`] is emitted.
+    pub fn set_keep_last_good_local_settings(&mut self, keep: bool) {
+        self.keep_last_good_local_settings = keep;
+    }
+
     pub fn collaborators(&self) -> &HashMap<proto::PeerId, Collaborator> {
         &self.collaborators
     }
@@ -1717,6 +1840,29 @@ impl Project {
         Ok(())
     }
 
+    /// Like [`Project::reshared`], for dev servers, which send the worktrees'
+    /// metadata along with the request to rejoin. Only the worktrees' entries
+    /// are shared again, rather than sending the metadata a second time.
+    pub fn reshared_with_worktree_metadata(
+        &mut self,
+        message: proto::ResharedProject,
+        cx: &mut ModelContext<Self>,
+    ) -> Result<()> {
+        self.shared_buffers.clear();
+        self.set_collaborators_from_proto(message.collaborators, cx)?;
+
+        let project_id = message.id;
+        for worktree in self.worktrees().collect::<Vec<_>>() {
+            worktree.update(cx, |worktree, cx| {
+                if let Some(worktree) = worktree.as_local_mut() {
+                    worktree.share(project_id, cx).detach_and_log_err(cx);
+                }
+            });
+        }
+        cx.notify();
+        Ok(())
+    }
+
     pub fn rejoined(
         &mut self,
         message: proto::RejoinedProject,
@@ -2014,9 +2160,10 @@ impl Project {
         worktree: Model<Worktree>,
         cx: &mut ModelContext<Self>,
     ) -> Task<Result<Model<Buffer>>> {
+        let diff_base_revision = diff_base_revision(&worktree, &path, cx);
         let load_buffer = worktree.update(cx, |worktree, cx| {
             let worktree = worktree.as_local_mut().unwrap();
-            worktree.load_buffer(&path, cx)
+            worktree.load_buffer(&path, diff_base_revision, cx)
         });
         fn is_not_found_error(error: &anyhow::Error) -> bool {
             error
@@ -2763,10 +2910,8 @@ impl Project {
         self.buffers_needing_diff.insert(buffer.downgrade());
         let first_insertion = self.buffers_needing_diff.len() == 1;
 
-        let settings = ProjectSettings::get_global(cx);
-        let delay = if let Some(delay) = settings.git.gutter_debounce {
-            delay
-        } else {
+        let delay = ProjectSettings::get_global(cx).git.git_update_debounce();
+        if delay.is_zero() {
             if first_insertion {
                 let this = cx.weak_model();
                 cx.defer(move |cx| {
@@ -2778,16 +2923,10 @@ impl Project {
                 });
             }
             return;
-        };
-
-        const MIN_DELAY: u64 = 50;
-        let delay = delay.max(MIN_DELAY);
-        let duration = Duration::from_millis(delay);
+        }
 
         self.git_diff_debouncer
-            .fire_new(duration, cx, move |this, cx| {
-                this.recalculate_buffer_diffs(cx)
-            });
+            .fire_new(delay, cx, move |this, cx| this.recalculate_buffer_diffs(cx));
     }
 
     fn recalculate_buffer_diffs(&mut self, cx: &mut ModelContext<Self>) -> Task<()> {
@@ -2943,7 +3082,13 @@ impl Project {
                 })?;
 
                 for (adapter, server, delegate) in servers {
-                    let settings = adapter.workspace_configuration(&delegate, &mut cx).await?;
+                    let settings = adapter
+                        .clone()
+                        .workspace_configuration(&delegate, &mut cx)
+                        .await?;
+                    let settings = cx.update(|cx| {
+                        with_configuration_sections(settings, &adapter.name(), &delegate, cx)
+                    })?;
 
                     server
                         .notify::<lsp::notification::DidChangeConfiguration>(
@@ -3032,8 +3177,25 @@ impl Project {
             return;
         }
 
-        for adapter in self.languages.clone().lsp_adapters(&language) {
-            self.start_language_server(worktree, adapter.clone(), language.clone(), cx);
+        let project_settings = ProjectSettings::get(
+            Some(SettingsLocation {
+                worktree_id: worktree.read(cx).id().to_proto() as usize,
+                path: Path::new(""),
+            }),
+            cx,
+        );
+        let enabled_adapters = self
+            .languages
+            .lsp_adapters(&language)
+            .into_iter()
+            .filter(|adapter| {
+                project_settings
+                    .lsp_settings(&adapter.name.0)
+                    .map_or(true, |lsp| lsp.is_enabled())
+            })
+            .collect::<Vec<_>>();
+        for adapter in enabled_adapters {
+            self.start_language_server(worktree, adapter, language.clone(), cx);
         }
     }
 
@@ -3058,28 +3220,45 @@ impl Project {
 
         let stderr_capture = Arc::new(Mutex::new(Some(String::new())));
         let lsp_adapter_delegate = ProjectLspAdapterDelegate::new(self, worktree_handle, cx);
+        let project_settings = ProjectSettings::get(
+            Some(SettingsLocation {
+                worktree_id: worktree_id.to_proto() as usize,
+                path: Path::new(""),
+            }),
+            cx,
+        );
+        let lsp = project_settings.lsp_settings(&adapter.name.0);
+        let lsp = lsp.as_ref();
+        let override_options = lsp.and_then(|s| s.initialization_options.clone());
+        let initialization_timeout = lsp
+            .and_then(|s| s.initialization_timeout_ms)
+            .map(Duration::from_millis);
+        let env_overrides = lsp
+            .and_then(|s| s.binary.as_ref())
+            .and_then(|binary| binary.env.clone())
+            .unwrap_or_default();
+        let binary_paths = lsp
+            .and_then(|s| s.binary.as_ref())
+            .and_then(|binary| binary.resolved_paths().log_err())
+            .unwrap_or_default()
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+
         let pending_server = match self.languages.create_pending_language_server(
             stderr_capture.clone(),
             language.clone(),
             adapter.clone(),
             Arc::clone(&worktree_path),
             lsp_adapter_delegate.clone(),
+            env_overrides,
+            binary_paths,
             cx,
         ) {
             Some(pending_server) => pending_server,
             None => return,
         };
 
-        let project_settings = ProjectSettings::get(
-            Some(SettingsLocation {
-                worktree_id: worktree_id.to_proto() as usize,
-                path: Path::new(""),
-            }),
-            cx,
-        );
-        let lsp = project_settings.lsp.get(&adapter.name.0);
-        let override_options = lsp.and_then(|s| s.initialization_options.clone());
-
         let server_id = pending_server.server_id;
         let container_dir = pending_server.container_dir.clone();
         let state = LanguageServerState::Starting({
@@ -3093,6 +3272,7 @@ impl Project {
                     this.clone(),
                     lsp_adapter_delegate,
                     override_options,
+                    initialization_timeout,
                     pending_server,
                     adapter.clone(),
                     language.clone(),
@@ -3217,6 +3397,7 @@ impl Project {
         this: WeakModel<Self>,
         delegate: Arc<dyn LspAdapterDelegate>,
         override_initialization_options: Option<serde_json::Value>,
+        initialization_timeout: Option<Duration>,
         pending_server: PendingLanguageServer,
         adapter: Arc<CachedLspAdapter>,
         language: Arc<Language>,
@@ -3227,6 +3408,7 @@ impl Project {
         let language_server = Self::setup_pending_language_server(
             this.clone(),
             override_initialization_options,
+            initialization_timeout,
             pending_server,
             delegate,
             adapter.clone(),
@@ -3257,6 +3439,7 @@ impl Project {
     async fn setup_pending_language_server(
         this: WeakModel<Self>,
         override_options: Option<serde_json::Value>,
+        initialization_timeout: Option<Duration>,
         pending_server: PendingLanguageServer,
         delegate: Arc<dyn LspAdapterDelegate>,
         adapter: Arc<CachedLspAdapter>,
@@ -3268,6 +3451,9 @@ impl Project {
             .clone()
             .workspace_configuration(&delegate, cx)
             .await?;
+        let workspace_config = cx.update(|cx| {
+            with_configuration_sections(workspace_config, &adapter.name, &delegate, cx)
+        })?;
         let (language_server, mut initialization_options) = pending_server.task.await?;
 
         let name = language_server.name();
@@ -3302,8 +3488,18 @@ impl Project {
                     let adapter = adapter.clone();
                     let delegate = delegate.clone();
                     async move {
-                        let workspace_config =
-                            adapter.workspace_configuration(&delegate, &mut cx).await?;
+                        let workspace_config = adapter
+                            .clone()
+                            .workspace_configuration(&delegate, &mut cx)
+                            .await?;
+                        let workspace_config = cx.update(|cx| {
+                            with_configuration_sections(
+                                workspace_config,
+                                &adapter.name(),
+                                &delegate,
+                                cx,
+                            )
+                        })?;
                         Ok(params
                             .items
                             .into_iter()
@@ -3493,17 +3689,20 @@ impl Project {
             .detach();
 
         language_server
-            .on_notification::<lsp::notification::Progress, _>(move |params, mut cx| {
-                if let Some(this) = this.upgrade() {
-                    this.update(&mut cx, |this, cx| {
-                        this.on_lsp_progress(
-                            params,
-                            server_id,
-                            disk_based_diagnostics_progress_token.clone(),
-                            cx,
-                        );
-                    })
-                    .ok();
+            .on_notification::<lsp::notification::Progress, _>({
+                let this = this.clone();
+                move |params, mut cx| {
+                    if let Some(this) = this.upgrade() {
+                        this.update(&mut cx, |this, cx| {
+                            this.on_lsp_progress(
+                                params,
+                                server_id,
+                                disk_based_diagnostics_progress_token.clone(),
+                                cx,
+                            );
+                        })
+                        .ok();
+                    }
                 }
             })
             .detach();
@@ -3515,9 +3714,38 @@ impl Project {
             (None, override_options) => initialization_options = override_options,
             _ => {}
         }
-        let language_server = cx
-            .update(|cx| language_server.initialize(initialization_options, cx))?
-            .await?;
+        let initialize = cx.update(|cx| match initialization_timeout {
+            Some(timeout) => {
+                language_server.initialize_with_timeout(initialization_options, timeout, cx)
+            }
+            None => language_server.initialize(initialization_options, cx),
+        })?;
+        let language_server = match initialize.await {
+            Ok(language_server) => language_server,
+            Err(error) => {
+                let Some(timeout) = initialization_timeout
+                    .filter(|_| error.downcast_ref::<lsp::RequestTimeout>().is_some())
+                else {
+                    return Err(error);
+                };
+                let error = format!(
+                    "language server {} did not respond to initialize within the configured \
+                    initialization_timeout_ms of {}",
+                    adapter.name.0,
+                    timeout.as_millis()
+                );
+                this.update(cx, |this, _| {
+                    this.languages.update_lsp_status(
+                        adapter.name.clone(),
+                        language::LanguageServerBinaryStatus::Failed {
+                            error: error.clone(),
+                        },
+                    )
+                })
+                .ok();
+                return Err(anyhow!(error));
+            }
+        };
 
         language_server
             .notify::<lsp::notification::DidChangeConfiguration>(
@@ -3752,6 +3980,21 @@ impl Project {
         }
     }
 
+    /// Stops every language server running for this project, resolving once
+    /// they have all shut down.
+    pub fn stop_language_servers(&mut self, cx: &mut ModelContext<Self>) -> Task<()> {
+        let keys = self.language_server_ids.keys().cloned().collect::<Vec<_>>();
+        let stops = keys
+            .into_iter()
+            .map(|(worktree_id, adapter_name)| {
+                self.stop_language_server(worktree_id, adapter_name, cx)
+            })
+            .collect::<Vec<_>>();
+        cx.background_executor().spawn(async move {
+            futures::future::join_all(stops).await;
+        })
+    }
+
     pub fn restart_language_servers_for_buffers(
         &mut self,
         buffers: impl IntoIterator<Item = Model<Buffer>>,
@@ -4634,7 +4877,13 @@ impl Project {
             })?;
 
             let settings = buffer.update(&mut cx, |buffer, cx| {
-                language_settings(buffer.language(), buffer.file(), cx).clone()
+                let mut settings = language_settings(buffer.language(), buffer.file(), cx).clone();
+                let location = buffer.file().map(|file| file.as_ref().into());
+                let language_name = buffer.language().map(|language| language.name());
+                ProjectSettings::get(location, cx)
+                    .formatting
+                    .apply(language_name.as_deref(), &mut settings);
+                settings
             })?;
 
             let remove_trailing_whitespace = settings.remove_trailing_whitespace_on_save;
@@ -7358,11 +7607,12 @@ impl Project {
                 let receiver = receiver.clone();
                 let path = path.clone();
                 let abs_path = worktree_handle.read(cx).absolutize(&path).ok()?;
+                let diff_base_revision = diff_base_revision(&worktree_handle, &path, cx);
                 Some(async move {
                     wait_for_loading_buffer(receiver)
                         .await
                         .ok()
-                        .map(|buffer| (buffer, path, abs_path))
+                        .map(|buffer| (buffer, path, abs_path, diff_base_revision))
                 })
             })
             .collect::<FuturesUnordered<_>>();
@@ -7381,7 +7631,8 @@ impl Project {
                 changed_repos
                     .iter()
                     .find(|(work_dir, _)| path.starts_with(work_dir))?;
-                Some((buffer, path.clone(), file.abs_path(cx)))
+                let diff_base_revision = diff_base_revision(&worktree_handle, path, cx);
+                Some((buffer, path.clone(), file.abs_path(cx), diff_base_revision))
             })
             .collect::<Vec<_>>();
 
@@ -7406,34 +7657,54 @@ impl Project {
                         .into_iter()
                         .flatten()
                         .chain(current_buffers)
-                        .filter_map(|(buffer, path, abs_path)| {
+                        .filter_map(|(buffer, path, abs_path, diff_base_revision)| {
                             let (work_directory, repo) =
                                 snapshot.repository_and_work_directory_for_path(&path)?;
                             let repo_entry = snapshot.get_local_repo(&repo)?;
-                            Some((buffer, path, abs_path, work_directory, repo_entry))
-                        })
-                        .map(|(buffer, path, abs_path, work_directory, repo_entry)| {
-                            let fs = fs.clone();
-                            async move {
-                                let abs_path_metadata = fs
-                                    .metadata(&abs_path)
-                                    .await
-                                    .with_context(|| {
-                                        format!("loading file and FS metadata for {path:?}")
-                                    })
-                                    .log_err()
-                                    .flatten()?;
-                                let base_text = if abs_path_metadata.is_dir
-                                    || abs_path_metadata.is_symlink
-                                {
-                                    None
-                                } else {
-                                    let relative_path = path.strip_prefix(&work_directory).ok()?;
-                                    repo_entry.repo().lock().load_index_text(relative_path)
-                                };
-                                Some((buffer, base_text))
-                            }
+                            Some((
+                                buffer,
+                                path,
+                                abs_path,
+                                diff_base_revision,
+                                work_directory,
+                                repo_entry,
+                            ))
                         })
+                        .map(
+                            |(
+                                buffer,
+                                path,
+                                abs_path,
+                                diff_base_revision,
+                                work_directory,
+                                repo_entry,
+                            )| {
+                                let fs = fs.clone();
+                                async move {
+                                    let abs_path_metadata = fs
+                                        .metadata(&abs_path)
+                                        .await
+                                        .with_context(|| {
+                                            format!("loading file and FS metadata for {path:?}")
+                                        })
+                                        .log_err()
+                                        .flatten()?;
+                                    let base_text = if abs_path_metadata.is_dir
+                                        || abs_path_metadata.is_symlink
+                                    {
+                                        None
+                                    } else {
+                                        let relative_path =
+                                            path.strip_prefix(&work_directory).ok()?;
+                                        repo_entry.repo().lock().load_diff_base(
+                                            relative_path,
+                                            diff_base_revision.as_deref(),
+                                        )
+                                    };
+                                    Some((buffer, base_text))
+                                }
+                            },
+                        )
                         .collect::<FuturesUnordered<_>>();
 
                     let mut diff_bases = Vec::with_capacity(diff_base_tasks.len());
@@ -7567,33 +7838,60 @@ impl Project {
         }
 
         let client = self.client.clone();
-        cx.spawn(move |_, cx| async move {
+        let keep_last_good_local_settings = self.keep_last_good_local_settings;
+        cx.spawn(move |this, mut cx| async move {
             let settings_contents: Vec<(Arc<Path>, _)> =
                 futures::future::join_all(settings_contents).await;
-            cx.update(|cx| {
-                cx.update_global::<SettingsStore, _>(|store, cx| {
-                    for (directory, file_content) in settings_contents {
-                        let file_content = file_content.and_then(|content| content.log_err());
-                        store
-                            .set_local_settings(
-                                worktree_id.as_u64() as usize,
-                                directory.clone(),
-                                file_content.as_deref(),
-                                cx,
-                            )
-                            .log_err();
-                        if let Some(remote_id) = project_id {
-                            client
-                                .send(proto::UpdateWorktreeSettings {
-                                    project_id: remote_id,
-                                    worktree_id: remote_worktree_id.to_proto(),
-                                    path: directory.to_string_lossy().into_owned(),
-                                    content: file_content,
-                                })
-                                .log_err();
+            let failures = cx
+                .update(|cx| {
+                    cx.update_global::<SettingsStore, _>(|store, cx| {
+                        let mut failures = Vec::new();
+                        for (directory, file_content) in settings_contents {
+                            let file_content = file_content.and_then(|content| content.log_err());
+                            let result = match &file_content {
+                                Some(content) if keep_last_good_local_settings => {
+                                    store.validate_local_settings(content)
+                                }
+                                _ => Ok(()),
+                            }
+                            .and_then(|_| {
+                                store.set_local_settings(
+                                    worktree_id.as_u64() as usize,
+                                    directory.clone(),
+                                    file_content.as_deref(),
+                                    cx,
+                                )
+                            });
+                            if let Err(error) = result {
+                                log::error!("failed to load settings in {directory:?}: {error:#}");
+                                // Collaborators keep the last settings that were loaded.
+                                failures.push((directory, format!("{error:#}")));
+                                continue;
+                            }
+                            if let Some(remote_id) = project_id {
+                                client
+                                    .send(proto::UpdateWorktreeSettings {
+                                        project_id: remote_id,
+                                        worktree_id: remote_worktree_id.to_proto(),
+                                        path: directory.to_string_lossy().into_owned(),
+                                        content: file_content,
+                                    })
+                                    .log_err();
+                            }
                         }
-                    }
-                });
+                        failures
+                    })
+                })
+                .unwrap_or_default();
+
+            this.update(&mut cx, |_, cx| {
+                for (path, message) in failures {
+                    cx.emit(Event::LocalSettingsFailed {
+                        worktree_id: remote_worktree_id,
+                        path,
+                        message,
+                    });
+                }
             })
             .ok();
         })
@@ -7769,20 +8067,39 @@ impl Project {
                     .strip_prefix(&work_directory)?
                     .to_path_buf();
 
-                let content = match version {
-                    Some(version) => buffer.rope_for_version(&version).clone(),
-                    None => buffer.as_rope().clone(),
+                let (version, content) = match version {
+                    Some(version) => {
+                        let content = buffer.rope_for_version(&version).clone();
+                        (version, content)
+                    }
+                    None => (buffer.version(), buffer.as_rope().clone()),
+                };
+                let cache_key = BlameCacheKey {
+                    path: buffer_project_path,
+                    version,
+                    git_dir_scan_id: repo_entry.git_dir_scan_id(),
                 };
                 let repo = repo_entry.repo().clone();
 
-                anyhow::Ok((repo, relative_path, content))
+                anyhow::Ok((repo, relative_path, content, cache_key))
             });
+            let blame_cache = self.blame_cache.clone();
+            let cache_size = ProjectSettings::get_global(cx).git.blame_cache_size();
 
             cx.background_executor().spawn(async move {
-                let (repo, relative_path, content) = blame_params?;
-                let lock = repo.lock();
-                lock.blame(&relative_path, content)
-                    .with_context(|| format!("Failed to blame {relative_path:?}"))
+                let (repo, relative_path, content, cache_key) = blame_params?;
+                if let Some(blame) = blame_cache.lock().get(&cache_key) {
+                    return Ok(blame);
+                }
+
+                let blame = repo
+                    .lock()
+                    .blame(&relative_path, content)
+                    .with_context(|| format!("Failed to blame {relative_path:?}"))?;
+                blame_cache
+                    .lock()
+                    .insert(cache_key, blame.clone(), cache_size);
+                Ok(blame)
             })
         } else {
             let project_id = self.remote_id();
@@ -7805,6 +8122,35 @@ impl Project {
         }
     }
 
+    /// Returns the git blame of the given row of a buffer, for showing details
+    /// about the commit that last changed it.
+    pub fn blame_line(
+        &self,
+        buffer: &Model<Buffer>,
+        row: u32,
+        cx: &AppContext,
+    ) -> Task<Result<LineBlame>> {
+        let blame = self.blame_buffer(buffer, None, cx);
+        cx.background_executor().spawn(async move {
+            let Blame {
+                entries,
+                mut messages,
+                mut permalinks,
+            } = blame.await?;
+            let Some(entry) = entries
+                .into_iter()
+                .find(|entry| entry.range.contains(&row) && !entry.sha.is_zero())
+            else {
+                return Ok(LineBlame::NotCommittedYet);
+            };
+            Ok(LineBlame::Committed {
+                message: messages.remove(&entry.sha),
+                permalink: permalinks.remove(&entry.sha),
+                entry,
+            })
+        })
+    }
+
     // RPC message handlers
 
     async fn handle_blame_buffer(
@@ -10222,6 +10568,39 @@ async fn search_ignored_entry(
     }
 }
 
+/// Replaces sections of a language server's workspace configuration with the
+/// `configuration_sections` from its [`LspSettings`], if any.
+fn with_configuration_sections(
+    mut workspace_config: serde_json::Value,
+    server_name: &LanguageServerName,
+    delegate: &Arc<dyn LspAdapterDelegate>,
+    cx: &AppContext,
+) -> serde_json::Value {
+    let project_settings = ProjectSettings::get(
+        Some(SettingsLocation {
+            worktree_id: delegate.worktree_id() as usize,
+            path: Path::new(""),
+        }),
+        cx,
+    );
+    let Some(sections) = project_settings
+        .lsp_settings(&server_name.0)
+        .and_then(|lsp| lsp.configuration_sections)
+    else {
+        return workspace_config;
+    };
+
+    if !workspace_config.is_object() {
+        workspace_config = serde_json::Value::Object(Default::default());
+    }
+    if let Some(workspace_config) = workspace_config.as_object_mut() {
+        for (section, value) in sections {
+            workspace_config.insert(section, value);
+        }
+    }
+    workspace_config
+}
+
 fn subscribe_for_copilot_events(
     copilot: &Model<Copilot>,
     cx: &mut ModelContext<'_, Project>,
@@ -10580,6 +10959,21 @@ impl Completion {
     }
 }
 
+/// Returns the revision that the file at `path` should be diffed against, if
+/// the project settings name one.
+fn diff_base_revision(worktree: &Model<Worktree>, path: &Path, cx: &AppContext) -> Option<String> {
+    ProjectSettings::get(
+        Some(SettingsLocation {
+            worktree_id: worktree.read(cx).id().to_proto() as usize,
+            path,
+        }),
+        cx,
+    )
+    .git
+    .diff_base
+    .clone()
+}
+
 async fn wait_for_loading_buffer(
     mut receiver: postage::watch::Receiver<Option<Result<Model<Buffer>, Arc<anyhow::Error>>>>,
 ) -> Result<Model<Buffer>, Arc<anyhow::Error>> {
@@ -1,9 +1,16 @@
+use anyhow::{bail, Context as _, Result};
 use collections::HashMap;
-use gpui::AppContext;
-use schemars::JsonSchema;
+use gpui::{AppContext, Global};
+use language::language_settings::{FormatOnSave, Formatter, LanguageSettings};
+use schemars::{
+    schema::{InstanceType, RootSchema, Schema, SingleOrVec},
+    JsonSchema,
+};
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use settings::{Settings, SettingsSources};
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
+use util::{merge_non_null_json_value_into, ResultExt as _};
 
 #[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct ProjectSettings {
@@ -13,6 +20,14 @@ pub struct ProjectSettings {
     /// - initialization_options
     /// To override settings for a language, add an entry for that language server's
     /// name to the lsp value.
+    ///
+    /// Keys may also be glob patterns, where `*` matches any run of characters
+    /// and `?` matches a single character, such as `"*"` for every server or
+    /// `"typescript*"`. A server's settings are merged from every matching
+    /// pattern, from least to most specific (more literal characters is more
+    /// specific, and ties are ordered alphabetically), and then from the entry
+    /// with the server's exact name, which takes precedence over all patterns.
+    /// Use [`ProjectSettings::lsp_settings`] to look up a server's settings.
     /// Default: null
     #[serde(default)]
     pub lsp: HashMap<Arc<str>, LspSettings>,
@@ -20,15 +35,133 @@ pub struct ProjectSettings {
     /// Configuration for Git-related features
     #[serde(default)]
     pub git: GitSettings,
+
+    /// Project-wide formatting configuration, which takes precedence over the
+    /// `formatter` and `format_on_save` language settings.
+    #[serde(default)]
+    pub formatting: FormattingSettings,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct FormattingSettings {
+    /// Whether to format buffers when they're saved. When unset, the
+    /// `format_on_save` language setting is used.
+    ///
+    /// Default: null
+    pub on_save: Option<bool>,
+    /// The formatter to use for languages without an entry in `languages`.
+    /// When unset, the `formatter` language setting is used.
+    ///
+    /// Default: null
+    pub default: Option<FormatterKind>,
+    /// The formatter to use for specific languages, keyed by language name.
+    #[serde(default)]
+    pub languages: HashMap<Arc<str>, FormatterKind>,
+}
+
+/// Which formatter to use when formatting a buffer.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FormatterKind {
+    /// Format code using the current language server.
+    LanguageServer,
+    /// Format code using an external command.
+    External {
+        /// The external program to run.
+        command: Arc<str>,
+        /// The arguments to pass to the program.
+        #[serde(default)]
+        arguments: Arc<[String]>,
+    },
+    /// Format code using Zed's Prettier integration.
+    Prettier,
+}
+
+impl From<FormatterKind> for Formatter {
+    fn from(kind: FormatterKind) -> Self {
+        match kind {
+            FormatterKind::LanguageServer => Formatter::LanguageServer,
+            FormatterKind::External { command, arguments } => {
+                Formatter::External { command, arguments }
+            }
+            FormatterKind::Prettier => Formatter::Prettier,
+        }
+    }
+}
+
+impl FormattingSettings {
+    /// Applies these settings on top of the language settings of a buffer in
+    /// the given language.
+    pub fn apply(&self, language_name: Option<&str>, settings: &mut LanguageSettings) {
+        let formatter = language_name
+            .and_then(|name| self.languages.get(name))
+            .or(self.default.as_ref());
+        if let Some(formatter) = formatter {
+            settings.formatter = formatter.clone().into();
+            // `format_on_save` can name its own formatter, which would
+            // otherwise take precedence on save.
+            if settings.format_on_save != FormatOnSave::Off {
+                settings.format_on_save = FormatOnSave::On;
+            }
+        }
+        match self.on_save {
+            Some(true) if settings.format_on_save == FormatOnSave::Off => {
+                settings.format_on_save = FormatOnSave::On;
+            }
+            Some(false) => settings.format_on_save = FormatOnSave::Off,
+            _ => {}
+        }
+    }
 }
 
-#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema)]
 pub struct GitSettings {
     /// Whether or not to show the git gutter.
     ///
     /// Default: tracked_files
     pub git_gutter: Option<GitGutterSetting>,
+    /// How long to wait after a change before updating the git gutter and
+    /// git blame, in milliseconds. Non-zero delays are at least 50ms.
+    ///
+    /// Default: 0 (update right away)
     pub gutter_debounce: Option<u64>,
+    /// How inline git blame annotations are grouped.
+    ///
+    /// Default: line
+    pub blame_granularity: Option<BlameGranularity>,
+    /// The revision that the git gutter shows changes against, such as a
+    /// branch name or a commit SHA. If the revision can't be resolved, changes
+    /// are shown against the index.
+    ///
+    /// Default: null
+    pub diff_base: Option<String>,
+    /// How many files' git blame results to keep cached, so that blaming
+    /// an unchanged file doesn't run `git blame` again. 0 disables the cache.
+    ///
+    /// Default: 32
+    pub blame_cache_size: Option<usize>,
+    /// Whether to show the full commit details when hovering over git blame.
+    ///
+    /// Default: true
+    pub hover_details: Option<bool>,
+}
+
+impl GitSettings {
+    /// Returns how many files' blame results to keep cached.
+    pub fn blame_cache_size(&self) -> usize {
+        const DEFAULT_BLAME_CACHE_SIZE: usize = 32;
+        self.blame_cache_size.unwrap_or(DEFAULT_BLAME_CACHE_SIZE)
+    }
+
+    /// Returns how long to wait after a change before recomputing the git
+    /// gutter and git blame. Zero means they're recomputed right away.
+    pub fn git_update_debounce(&self) -> Duration {
+        const MIN_DELAY: u64 = 50;
+        match self.gutter_debounce {
+            None | Some(0) => Duration::ZERO,
+            Some(delay) => Duration::from_millis(delay.max(MIN_DELAY)),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, JsonSchema)]
@@ -37,22 +170,301 @@ pub enum GitGutterSetting {
     /// Show git gutter in tracked files.
     #[default]
     TrackedFiles,
+    /// Show git gutter in tracked files, and mark files that git doesn't track.
+    All,
     /// Hide git gutter
     Hide,
 }
 
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BlameGranularity {
+    /// Show a blame annotation on every line.
+    #[default]
+    Line,
+    /// Show a single blame annotation for each run of consecutive lines
+    /// from the same commit.
+    Hunk,
+}
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 pub struct BinarySettings {
+    /// The path of the language server binary.
+    ///
+    /// Environment variables in the path and in the arguments are expanded
+    /// when the server starts, written as `$VAR` or `${VAR}`, and `$$` stands
+    /// for a literal `$`. Using a variable that isn't set is an error.
     pub path: Option<String>,
+    /// Candidate paths for the language server binary, in order of preference.
+    ///
+    /// The first candidate that exists and is executable is used, and bare
+    /// names are looked up on the `PATH`. Takes precedence over `path` when
+    /// present.
+    pub paths: Option<Vec<String>>,
     pub arguments: Option<Vec<String>>,
+    /// Environment variables to set when starting the language server.
+    ///
+    /// The server inherits Zed's environment, along with any variables its
+    /// adapter provides. The variables configured here take precedence over
+    /// both, and a `null` value removes the variable from the server's
+    /// environment. Variables are merged across settings files, so a project
+    /// can override or remove a single variable set in the user settings.
+    pub env: Option<HashMap<String, Option<String>>>,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct LspSettings {
+    /// Whether to start this language server for the languages it supports.
+    /// Defaults to true.
+    pub enabled: Option<bool>,
     pub binary: Option<BinarySettings>,
     pub initialization_options: Option<serde_json::Value>,
     pub settings: Option<serde_json::Value>,
+    /// Sections of the server's workspace configuration, keyed by section
+    /// name. They are sent with `workspace/didChangeConfiguration` when the
+    /// server starts and whenever settings change, and replace any sections of
+    /// the same name that Zed would otherwise send.
+    pub configuration_sections: Option<HashMap<String, serde_json::Value>>,
+    /// How long to wait for the server to respond to its `initialize` request,
+    /// in milliseconds, before considering it failed. Defaults to two minutes.
+    pub initialization_timeout_ms: Option<u64>,
+}
+
+impl BinarySettings {
+    /// Returns the path of the binary to start, resolving `paths` to its first
+    /// executable candidate when present. Environment variables are expanded,
+    /// and candidates in `paths` that use a variable that isn't set are skipped.
+    pub fn resolved_path(&self) -> Result<Option<String>> {
+        let Some(paths) = &self.paths else {
+            return self.path.as_deref().map(expand_env_vars).transpose();
+        };
+        let resolved = self
+            .resolved_paths()?
+            .into_iter()
+            .find(|path| which::which(path).is_ok());
+        if resolved.is_none() {
+            log::warn!("none of the configured language server binaries are executable: {paths:?}");
+        }
+        Ok(resolved)
+    }
+
+    /// Returns every candidate path for the binary in order of preference:
+    /// those in `paths` when present, or else `path`. Environment variables
+    /// are expanded, and candidates that use a variable that isn't set are
+    /// skipped. The language server falls back to later candidates when an
+    /// earlier one can't be started.
+    pub fn resolved_paths(&self) -> Result<Vec<String>> {
+        let Some(paths) = &self.paths else {
+            return Ok(self.resolved_path()?.into_iter().collect());
+        };
+        Ok(paths
+            .iter()
+            .filter_map(|path| expand_env_vars(path).log_err())
+            .collect())
+    }
+
+    /// Returns the arguments to start the binary with, with environment
+    /// variables expanded.
+    pub fn resolved_arguments(&self) -> Result<Option<Vec<String>>> {
+        self.arguments
+            .as_ref()
+            .map(|arguments| {
+                arguments
+                    .iter()
+                    .map(|argument| expand_env_vars(argument))
+                    .collect()
+            })
+            .transpose()
+    }
+}
+
+/// Expands references to environment variables in `text`.
+fn expand_env_vars(text: &str) -> Result<String> {
+    expand_vars(text, |name| std::env::var(name).ok())
+}
+
+/// Replaces `$NAME` and `${NAME}` in `text` with the value of the variable,
+/// and `$$` with a literal `$`.
+fn expand_vars(text: &str, lookup: impl Fn(&str) -> Option<String>) -> Result<String> {
+    let mut expanded = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(char) = chars.next() {
+        if char != '$' {
+            expanded.push(char);
+            continue;
+        }
+
+        let mut name = String::new();
+        match chars.peek() {
+            Some('$') => {
+                chars.next();
+                expanded.push('$');
+                continue;
+            }
+            Some('{') => {
+                chars.next();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(char) => name.push(char),
+                        None => bail!("unterminated variable reference in {text:?}"),
+                    }
+                }
+            }
+            _ => {
+                while let Some(&char) = chars.peek() {
+                    if !char.is_ascii_alphanumeric() && char != '_' {
+                        break;
+                    }
+                    name.push(char);
+                    chars.next();
+                }
+                if name.is_empty() {
+                    expanded.push('$');
+                    continue;
+                }
+            }
+        }
+
+        let value = lookup(&name).with_context(|| {
+            format!("environment variable {name:?} used in {text:?} is not set")
+        })?;
+        expanded.push_str(&value);
+    }
+    Ok(expanded)
+}
+
+impl ProjectSettings {
+    /// Checks the `settings` of each language server against the schema
+    /// registered for it, returning a description of each problem found.
+    pub fn lsp_settings_errors(&self, cx: &AppContext) -> Vec<String> {
+        let Some(schemas) = cx.try_global::<LspSettingsSchemas>() else {
+            return Vec::new();
+        };
+        let mut errors = Vec::new();
+        for (server_name, lsp_settings) in &self.lsp {
+            let Some((schema, value)) = schemas
+                .0
+                .get(server_name)
+                .zip(lsp_settings.settings.as_ref())
+            else {
+                continue;
+            };
+            errors.extend(
+                validate_lsp_settings(value, schema)
+                    .into_iter()
+                    .map(|error| {
+                        format!("invalid settings for language server {server_name}: {error}")
+                    }),
+            );
+        }
+        errors.sort();
+        errors
+    }
+
+    /// Returns the settings for the language server with the given name,
+    /// merging the entries whose glob patterns match it into its own entry.
+    pub fn lsp_settings(&self, server_name: &str) -> Option<LspSettings> {
+        resolve_lsp_settings(&self.lsp, server_name)
+    }
+}
+
+/// Resolves the settings for a language server from `lsp` settings keyed by
+/// server names and glob patterns. See [`ProjectSettings::lsp`] for the order
+/// in which matching entries are merged.
+pub fn resolve_lsp_settings(
+    lsp: &HashMap<Arc<str>, LspSettings>,
+    server_name: &str,
+) -> Option<LspSettings> {
+    let exact = lsp.get(server_name);
+    let mut patterns = lsp
+        .keys()
+        .filter(|key| is_glob_pattern(key) && glob_matches(key, server_name))
+        .collect::<Vec<_>>();
+    if patterns.is_empty() {
+        return exact.cloned();
+    }
+
+    patterns.sort_by(|a, b| {
+        glob_specificity(a)
+            .cmp(&glob_specificity(b))
+            .then_with(|| a.cmp(b))
+    });
+    let mut merged = Value::Null;
+    let mut env = None::<HashMap<String, Option<String>>>;
+    for settings in patterns.into_iter().map(|key| &lsp[key]).chain(exact) {
+        let value = serde_json::to_value(settings).log_err()?;
+        merge_non_null_json_value_into(value, &mut merged);
+
+        // The JSON merge skips null values, so merge the environment separately
+        // to let a `null` remove a variable set by a less specific entry.
+        if let Some(settings_env) = settings
+            .binary
+            .as_ref()
+            .and_then(|binary| binary.env.as_ref())
+        {
+            env.get_or_insert_with(Default::default)
+                .extend(settings_env.clone());
+        }
+    }
+    let mut settings: LspSettings = serde_json::from_value(merged).log_err()?;
+    if let Some(env) = env {
+        settings.binary.get_or_insert_with(Default::default).env = Some(env);
+    }
+    Some(settings)
+}
+
+fn is_glob_pattern(key: &str) -> bool {
+    key.contains(['*', '?'])
+}
+
+fn glob_specificity(pattern: &str) -> usize {
+    pattern.chars().filter(|c| !matches!(c, '*' | '?')).count()
+}
+
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let name = name.chars().collect::<Vec<_>>();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    backtrack = Some((star, matched + 1));
+                    p = star + 1;
+                    n = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+impl LspSettings {
+    /// Returns whether the language server should be started.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.unwrap_or(true)
+    }
+
+    /// Returns whether a language server started with these settings must be
+    /// restarted to apply `new_settings`. Changes to `settings` and
+    /// `configuration_sections` are sent to the running server with
+    /// `workspace/didChangeConfiguration` instead.
+    pub fn requires_restart(&self, new_settings: &LspSettings) -> bool {
+        self.binary != new_settings.binary
+            || self.initialization_options != new_settings.initialization_options
+    }
 }
 
 impl Settings for ProjectSettings {
@@ -60,10 +472,642 @@ impl Settings for ProjectSettings {
 
     type FileContent = Self;
 
-    fn load(
-        sources: SettingsSources<Self::FileContent>,
-        _: &mut AppContext,
-    ) -> anyhow::Result<Self> {
-        sources.json_merge()
+    fn load(sources: SettingsSources<Self::FileContent>, cx: &mut AppContext) -> Result<Self> {
+        let mut settings: Self = sources.json_merge()?;
+
+        // The JSON merge skips null values, so merge each server's environment
+        // separately to let a `null` remove a variable set by an earlier source.
+        for source in sources.defaults_and_customizations() {
+            for (server_name, lsp_settings) in &source.lsp {
+                let Some(env) = lsp_settings
+                    .binary
+                    .as_ref()
+                    .and_then(|binary| binary.env.as_ref())
+                else {
+                    continue;
+                };
+                settings
+                    .lsp
+                    .entry(server_name.clone())
+                    .or_default()
+                    .binary
+                    .get_or_insert_with(Default::default)
+                    .env
+                    .get_or_insert_with(Default::default)
+                    .extend(env.clone());
+            }
+        }
+
+        Ok(settings)
+    }
+}
+
+#[derive(Default)]
+struct LspSettingsSchemas(HashMap<Arc<str>, RootSchema>);
+
+impl Global for LspSettingsSchemas {}
+
+/// Registers a JSON schema for the `settings` of the language server with the
+/// given name. Projects check the settings against it whenever they change,
+/// and notify about each unknown or mistyped key. The settings of servers
+/// without a schema aren't checked.
+pub fn register_lsp_settings_schema(
+    server_name: impl Into<Arc<str>>,
+    schema: RootSchema,
+    cx: &mut AppContext,
+) {
+    cx.default_global::<LspSettingsSchemas>()
+        .0
+        .insert(server_name.into(), schema);
+}
+
+/// Checks a language server's `settings` against its schema, returning a
+/// description of each problem found.
+///
+/// Only a subset of JSON schema is checked: types, enum values, object
+/// properties, array items, references and subschemas. Unknown keys are
+/// reported for objects that don't allow additional properties.
+pub fn validate_lsp_settings(value: &Value, schema: &RootSchema) -> Vec<String> {
+    let mut errors = Vec::new();
+    validate_value(
+        value,
+        &Schema::Object(schema.schema.clone()),
+        schema,
+        "settings",
+        &mut errors,
+    );
+    errors
+}
+
+fn validate_value(
+    value: &Value,
+    schema: &Schema,
+    root: &RootSchema,
+    path: &str,
+    errors: &mut Vec<String>,
+) {
+    let schema = match schema {
+        Schema::Bool(true) => return,
+        Schema::Bool(false) => {
+            errors.push(format!("{path}: not allowed"));
+            return;
+        }
+        Schema::Object(schema) => schema,
+    };
+
+    if let Some(reference) = &schema.reference {
+        let definition = reference
+            .strip_prefix("#/definitions/")
+            .and_then(|name| root.definitions.get(name));
+        if let Some(definition) = definition {
+            validate_value(value, definition, root, path, errors);
+        }
+        return;
+    }
+
+    if let Some(instance_type) = &schema.instance_type {
+        let matches = match instance_type {
+            SingleOrVec::Single(instance_type) => value_has_type(value, instance_type),
+            SingleOrVec::Vec(instance_types) => instance_types
+                .iter()
+                .any(|instance_type| value_has_type(value, instance_type)),
+        };
+        if !matches {
+            errors.push(format!(
+                "{path}: expected {}, found {}",
+                describe_instance_type(instance_type),
+                json_type_name(value)
+            ));
+            return;
+        }
+    }
+
+    if let Some(enum_values) = &schema.enum_values {
+        if !enum_values.contains(value) {
+            errors.push(format!("{path}: unexpected value {value}"));
+            return;
+        }
+    }
+
+    if let Some(subschemas) = &schema.subschemas {
+        if let Some(all_of) = &subschemas.all_of {
+            for subschema in all_of {
+                validate_value(value, subschema, root, path, errors);
+            }
+        }
+        for alternatives in [&subschemas.any_of, &subschemas.one_of]
+            .into_iter()
+            .flatten()
+        {
+            let alternative_errors = alternatives
+                .iter()
+                .map(|subschema| {
+                    let mut errors = Vec::new();
+                    validate_value(value, subschema, root, path, &mut errors);
+                    errors
+                })
+                .collect::<Vec<_>>();
+            if alternative_errors.iter().any(|errors| errors.is_empty()) {
+                continue;
+            }
+
+            // If no alternative matches, report the problems with the one that
+            // accepts the value itself and only rejects some of its contents.
+            let value_error_prefix = format!("{path}: ");
+            let closest_errors = alternative_errors
+                .iter()
+                .find(|errors| {
+                    !errors
+                        .iter()
+                        .any(|error| error.starts_with(&value_error_prefix))
+                })
+                .or_else(|| alternative_errors.iter().min_by_key(|errors| errors.len()));
+            errors.extend(closest_errors.cloned().unwrap_or_default());
+        }
+    }
+
+    match value {
+        Value::Object(properties) => {
+            let Some(validation) = &schema.object else {
+                return;
+            };
+            for (key, value) in properties {
+                let path = format!("{path}.{key}");
+                if let Some(property) = validation.properties.get(key) {
+                    validate_value(value, property, root, &path, errors);
+                } else if let Some(additional_properties) = &validation.additional_properties {
+                    if matches!(**additional_properties, Schema::Bool(false)) {
+                        errors.push(format!("{path}: unknown key"));
+                    } else {
+                        validate_value(value, additional_properties, root, &path, errors);
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            let Some(SingleOrVec::Single(item_schema)) =
+                schema.array.as_ref().and_then(|array| array.items.as_ref())
+            else {
+                return;
+            };
+            for (ix, item) in items.iter().enumerate() {
+                validate_value(item, item_schema, root, &format!("{path}[{ix}]"), errors);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn value_has_type(value: &Value, instance_type: &InstanceType) -> bool {
+    match instance_type {
+        InstanceType::Null => value.is_null(),
+        InstanceType::Boolean => value.is_boolean(),
+        InstanceType::Object => value.is_object(),
+        InstanceType::Array => value.is_array(),
+        InstanceType::Number => value.is_number(),
+        InstanceType::String => value.is_string(),
+        InstanceType::Integer => value.is_i64() || value.is_u64(),
+    }
+}
+
+fn describe_instance_type(instance_type: &SingleOrVec<InstanceType>) -> String {
+    let name = |instance_type: &InstanceType| match instance_type {
+        InstanceType::Null => "null",
+        InstanceType::Boolean => "a boolean",
+        InstanceType::Object => "an object",
+        InstanceType::Array => "an array",
+        InstanceType::Number => "a number",
+        InstanceType::String => "a string",
+        InstanceType::Integer => "an integer",
+    };
+    match instance_type {
+        SingleOrVec::Single(instance_type) => name(instance_type).to_string(),
+        SingleOrVec::Vec(instance_types) => instance_types
+            .iter()
+            .map(name)
+            .collect::<Vec<_>>()
+            .join(" or "),
+    }
+}
+
+fn json_type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_lsp_configuration_sections() {
+        let settings: LspSettings = serde_json::from_value(json!({
+            "configuration_sections": {
+                "yaml": { "validate": false },
+                "redhat.telemetry.enabled": false,
+            }
+        }))
+        .unwrap();
+        let sections = settings.configuration_sections.unwrap();
+        assert_eq!(sections["yaml"], json!({ "validate": false }));
+        assert_eq!(sections["redhat.telemetry.enabled"], json!(false));
+
+        let settings: LspSettings = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(settings.configuration_sections, None);
+    }
+
+    #[test]
+    fn test_lsp_binary_env() {
+        let settings: LspSettings = serde_json::from_value(json!({
+            "binary": {
+                "env": {
+                    "RUST_LOG": "debug",
+                    "CARGO_TARGET_DIR": null,
+                }
+            }
+        }))
+        .unwrap();
+        let env = settings.binary.unwrap().env.unwrap();
+        assert_eq!(env["RUST_LOG"], Some("debug".to_string()));
+        assert_eq!(env["CARGO_TARGET_DIR"], None);
+    }
+
+    #[test]
+    fn test_binary_resolved_path() {
+        let current_exe = std::env::current_exe().unwrap();
+        let current_exe = current_exe.to_str().unwrap();
+
+        let binary: BinarySettings = serde_json::from_value(json!({
+            "path": "/usr/bin/rust-analyzer",
+            "paths": ["/nonexistent/rust-analyzer", current_exe],
+        }))
+        .unwrap();
+        assert_eq!(
+            binary.resolved_path().unwrap().as_deref(),
+            Some(current_exe)
+        );
+        assert_eq!(
+            binary.resolved_paths().unwrap(),
+            ["/nonexistent/rust-analyzer", current_exe]
+        );
+
+        let binary: BinarySettings = serde_json::from_value(json!({
+            "path": "/usr/bin/rust-analyzer",
+            "paths": ["/nonexistent/rust-analyzer"],
+        }))
+        .unwrap();
+        assert_eq!(binary.resolved_path().unwrap(), None);
+
+        let binary: BinarySettings = serde_json::from_value(json!({
+            "path": "/usr/bin/rust-analyzer",
+            "arguments": ["--log-file=$$LOG"],
+        }))
+        .unwrap();
+        assert_eq!(
+            binary.resolved_path().unwrap().as_deref(),
+            Some("/usr/bin/rust-analyzer")
+        );
+        assert_eq!(
+            binary.resolved_arguments().unwrap(),
+            Some(vec!["--log-file=$LOG".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_expand_vars() {
+        let vars =
+            HashMap::from_iter([("HOME", "/home/user"), ("TOOLCHAIN_ROOT", "/opt/toolchain")]);
+        let expand =
+            |text: &str| expand_vars(text, |name| vars.get(name).map(|value| value.to_string()));
+
+        assert_eq!(
+            expand("$HOME/.cargo/bin/rust-analyzer").unwrap(),
+            "/home/user/.cargo/bin/rust-analyzer"
+        );
+        assert_eq!(
+            expand("${TOOLCHAIN_ROOT}/bin/clangd").unwrap(),
+            "/opt/toolchain/bin/clangd"
+        );
+        assert_eq!(expand("${HOME}_$HOME").unwrap(), "/home/user_/home/user");
+        assert_eq!(expand("cost: $$5, $").unwrap(), "cost: $5, $");
+        assert_eq!(expand("$$HOME").unwrap(), "$HOME");
+        assert_eq!(expand("--no-vars").unwrap(), "--no-vars");
+
+        let error = expand("$MISSING/bin/server").unwrap_err();
+        assert!(error.to_string().contains("\"MISSING\""), "{error}");
+        assert!(expand("${MISSING}").is_err());
+        assert!(expand("${HOME").is_err());
+    }
+
+    #[gpui::test]
+    fn test_lsp_binary_env_merging(cx: &mut AppContext) {
+        let settings = |value| serde_json::from_value::<ProjectSettings>(value).unwrap();
+        let default = settings(json!({}));
+        let user = settings(json!({
+            "lsp": {
+                "rust-analyzer": {
+                    "binary": {
+                        "path": "/usr/bin/rust-analyzer",
+                        "env": { "RUST_LOG": "info", "CARGO_TARGET_DIR": "/tmp/target" }
+                    }
+                }
+            }
+        }));
+        let project = settings(json!({
+            "lsp": {
+                "rust-analyzer": {
+                    "binary": {
+                        "env": { "RUST_LOG": "debug", "CARGO_TARGET_DIR": null }
+                    }
+                }
+            }
+        }));
+
+        let merged = ProjectSettings::load(
+            SettingsSources {
+                default: &default,
+                extensions: None,
+                user: Some(&user),
+                release_channel: None,
+                server: None,
+                project: &[&project],
+            },
+            cx,
+        )
+        .unwrap();
+        let binary = merged.lsp["rust-analyzer"].binary.clone().unwrap();
+        assert_eq!(binary.path.as_deref(), Some("/usr/bin/rust-analyzer"));
+        assert_eq!(
+            binary.env.unwrap(),
+            HashMap::from_iter([
+                ("RUST_LOG".to_string(), Some("debug".to_string())),
+                ("CARGO_TARGET_DIR".to_string(), None),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_git_gutter() {
+        let settings: GitSettings = serde_json::from_value(json!({ "git_gutter": "all" })).unwrap();
+        assert!(matches!(settings.git_gutter, Some(GitGutterSetting::All)));
+
+        let settings: GitSettings =
+            serde_json::from_value(json!({ "git_gutter": "tracked_files" })).unwrap();
+        assert!(matches!(
+            settings.git_gutter,
+            Some(GitGutterSetting::TrackedFiles)
+        ));
+        assert!(matches!(
+            GitGutterSetting::default(),
+            GitGutterSetting::TrackedFiles
+        ));
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("*", "rust-analyzer"));
+        assert!(glob_matches("rust*", "rust-analyzer"));
+        assert!(glob_matches("*-ls", "elixir-ls"));
+        assert!(glob_matches("*script*", "typescript-language-server"));
+        assert!(glob_matches("elm?ls", "elm-ls"));
+        assert!(!glob_matches("rust*", "typescript-language-server"));
+        assert!(!glob_matches("elm?ls", "elm--ls"));
+        assert!(!glob_matches("*-ls", "elixir-ls-2"));
+    }
+
+    #[test]
+    fn test_resolve_lsp_settings() {
+        let settings: ProjectSettings = serde_json::from_value(json!({
+            "lsp": {
+                "*": {
+                    "initialization_timeout_ms": 1000,
+                    "settings": { "source": "*", "star": true }
+                },
+                "typescript*": {
+                    "settings": { "source": "typescript*", "typescript": true }
+                },
+                "*-language-server": {
+                    "settings": { "source": "*-language-server" }
+                },
+                "typescript-language-server": {
+                    "enabled": false
+                }
+            }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            settings.lsp_settings("typescript-language-server"),
+            Some(LspSettings {
+                enabled: Some(false),
+                initialization_timeout_ms: Some(1000),
+                settings: Some(json!({
+                    "source": "*-language-server",
+                    "star": true,
+                    "typescript": true
+                })),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            settings.lsp_settings("rust-analyzer"),
+            Some(LspSettings {
+                initialization_timeout_ms: Some(1000),
+                settings: Some(json!({ "source": "*", "star": true })),
+                ..Default::default()
+            })
+        );
+
+        let settings: ProjectSettings =
+            serde_json::from_value(json!({ "lsp": { "rust*": { "enabled": false } } })).unwrap();
+        assert_eq!(settings.lsp_settings("gopls"), None);
+    }
+
+    #[test]
+    fn test_resolve_lsp_settings_env() {
+        let settings: ProjectSettings = serde_json::from_value(json!({
+            "lsp": {
+                "*": {
+                    "binary": { "env": { "RUST_LOG": "info", "NO_COLOR": "1" } }
+                },
+                "rust*": {
+                    "binary": { "env": { "RUST_LOG": null } }
+                },
+                "rust-analyzer": {
+                    "binary": { "path": "/usr/bin/rust-analyzer", "env": { "NO_COLOR": null } }
+                }
+            }
+        }))
+        .unwrap();
+
+        // A more specific entry can unset a variable set by a glob entry.
+        let binary = settings
+            .lsp_settings("rust-analyzer")
+            .unwrap()
+            .binary
+            .unwrap();
+        assert_eq!(binary.path.as_deref(), Some("/usr/bin/rust-analyzer"));
+        assert_eq!(
+            binary.env.unwrap(),
+            HashMap::from_iter([
+                ("RUST_LOG".to_string(), None),
+                ("NO_COLOR".to_string(), None),
+            ])
+        );
+
+        let binary = settings.lsp_settings("rustfmt-ls").unwrap().binary.unwrap();
+        assert_eq!(
+            binary.env.unwrap(),
+            HashMap::from_iter([
+                ("RUST_LOG".to_string(), None),
+                ("NO_COLOR".to_string(), Some("1".to_string())),
+            ])
+        );
+    }
+
+    #[gpui::test]
+    fn test_formatting_settings(cx: &mut AppContext) {
+        let store = settings::SettingsStore::test(cx);
+        cx.set_global(store);
+        language::init(cx);
+
+        let formatting: FormattingSettings = serde_json::from_value(json!({
+            "default": "prettier",
+            "languages": {
+                "Rust": "language_server",
+                "Python": { "external": { "command": "black", "arguments": ["-"] } }
+            }
+        }))
+        .unwrap();
+
+        let mut settings = language::language_settings::language_settings(None, None, cx).clone();
+        settings.format_on_save = FormatOnSave::LanguageServer;
+        formatting.apply(Some("Rust"), &mut settings);
+        assert_eq!(settings.formatter, Formatter::LanguageServer);
+        assert_eq!(settings.format_on_save, FormatOnSave::On);
+
+        formatting.apply(Some("Python"), &mut settings);
+        assert_eq!(
+            settings.formatter,
+            Formatter::External {
+                command: "black".into(),
+                arguments: vec!["-".to_string()].into(),
+            }
+        );
+
+        formatting.apply(Some("Go"), &mut settings);
+        assert_eq!(settings.formatter, Formatter::Prettier);
+
+        formatting.apply(None, &mut settings);
+        assert_eq!(settings.formatter, Formatter::Prettier);
+
+        // `on_save` overrides the language's `format_on_save` in both directions.
+        let formatting: FormattingSettings =
+            serde_json::from_value(json!({ "on_save": false })).unwrap();
+        formatting.apply(Some("Rust"), &mut settings);
+        assert_eq!(settings.format_on_save, FormatOnSave::Off);
+        assert_eq!(settings.formatter, Formatter::Prettier);
+
+        let formatting: FormattingSettings =
+            serde_json::from_value(json!({ "on_save": true })).unwrap();
+        formatting.apply(Some("Rust"), &mut settings);
+        assert_eq!(settings.format_on_save, FormatOnSave::On);
+    }
+
+    #[test]
+    fn test_git_update_debounce() {
+        let debounce = |gutter_debounce: Option<u64>| {
+            GitSettings {
+                gutter_debounce,
+                ..Default::default()
+            }
+            .git_update_debounce()
+        };
+        assert_eq!(debounce(None), Duration::ZERO);
+        assert_eq!(debounce(Some(0)), Duration::ZERO);
+        assert_eq!(debounce(Some(1)), Duration::from_millis(50));
+        assert_eq!(debounce(Some(200)), Duration::from_millis(200));
+    }
+
+    #[test]
+    fn test_validate_lsp_settings() {
+        #[allow(dead_code)]
+        #[derive(JsonSchema)]
+        #[serde(deny_unknown_fields)]
+        struct CheckSettings {
+            command: Option<String>,
+            enable: Option<bool>,
+        }
+
+        #[allow(dead_code)]
+        #[derive(JsonSchema)]
+        #[serde(deny_unknown_fields, rename_all = "camelCase")]
+        struct ServerSettings {
+            check_on_save: Option<CheckSettings>,
+            features: Option<Vec<String>>,
+        }
+
+        let schema = schemars::schema_for!(ServerSettings);
+        let validate = |value| validate_lsp_settings(&value, &schema);
+
+        assert_eq!(
+            validate(json!({
+                "checkOnSave": { "command": "clippy", "enable": true },
+                "features": ["all"],
+            })),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            validate(json!({
+                "checkOnsave": { "command": "clippy" },
+                "features": "all",
+            })),
+            vec![
+                "settings.checkOnsave: unknown key".to_string(),
+                "settings.features: expected an array or null, found a string".to_string(),
+            ]
+        );
+        assert_eq!(
+            validate(json!({
+                "checkOnSave": { "command": 5, "enabled": true },
+                "features": ["all", 1],
+            })),
+            vec![
+                "settings.checkOnSave.command: expected a string or null, found a number"
+                    .to_string(),
+                "settings.checkOnSave.enabled: unknown key".to_string(),
+                "settings.features[1]: expected a string, found a number".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_blame_granularity() {
+        let settings: GitSettings = serde_json::from_value(json!({})).unwrap();
+        assert_eq!(settings.blame_granularity, None);
+        assert_eq!(
+            settings.blame_granularity.unwrap_or_default(),
+            BlameGranularity::Line
+        );
+
+        let settings: GitSettings =
+            serde_json::from_value(json!({ "blame_granularity": "hunk" })).unwrap();
+        assert_eq!(settings.blame_granularity, Some(BlameGranularity::Hunk));
+
+        let settings: GitSettings =
+            serde_json::from_value(json!({ "blame_granularity": "line" })).unwrap();
+        assert_eq!(settings.blame_granularity, Some(BlameGranularity::Line));
+
+        assert!(
+            serde_json::from_value::<GitSettings>(json!({ "blame_granularity": "commit" }))
+                .is_err()
+        );
     }
 }
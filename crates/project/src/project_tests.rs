@@ -190,6 +190,44 @@ async fn test_managing_project_specific_settings(cx: &mut gpui::TestAppContext)
     });
 }
 
+#[gpui::test]
+async fn test_invalid_project_specific_setting(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/the-root",
+        json!({
+            ".zed": {
+                "settings.json": r#"{ "tab_size": 8, "git": { "git_gutter": 5 } }"#,
+            },
+            "a.rs": "fn a() {\n    A\n}",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/the-root".as_ref()], cx).await;
+    let worktree = project.update(cx, |project, _| project.worktrees().next().unwrap());
+
+    cx.executor().run_until_parked();
+    cx.update(|cx| {
+        let tree = worktree.read(cx);
+        let settings = language_settings(
+            None,
+            Some(
+                &(File::for_entry(
+                    tree.entry_for_path("a.rs").unwrap().clone(),
+                    worktree.clone(),
+                ) as _),
+            ),
+            cx,
+        );
+
+        // The invalid git setting doesn't prevent the valid tab size from applying.
+        assert_eq!(settings.tab_size.get(), 8);
+    });
+}
+
 #[gpui::test]
 async fn test_managing_language_servers(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -561,6 +599,121 @@ async fn test_managing_language_servers(cx: &mut gpui::TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_lsp_configuration_sections(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let set_sections = |sections: serde_json::Value, cx: &mut gpui::TestAppContext| {
+        cx.update(|cx| {
+            cx.update_global(|settings: &mut SettingsStore, cx| {
+                settings.update_user_settings::<ProjectSettings>(cx, |settings| {
+                    settings.lsp.insert(
+                        Arc::from("the-language-server"),
+                        LspSettings {
+                            configuration_sections: serde_json::from_value(sections).unwrap(),
+                            ..Default::default()
+                        },
+                    );
+                });
+            })
+        });
+    };
+    set_sections(json!({ "rust": { "checkOnSave": false } }), cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "" })).await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            name: "the-language-server",
+            ..Default::default()
+        },
+    );
+
+    let _buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let mut fake_server = fake_servers.next().await.unwrap();
+    assert_eq!(
+        fake_server
+            .receive_notification::<lsp::notification::DidChangeConfiguration>()
+            .await
+            .settings,
+        json!({ "rust": { "checkOnSave": false } })
+    );
+
+    set_sections(json!({ "rust": { "checkOnSave": true } }), cx);
+    assert_eq!(
+        fake_server
+            .receive_notification::<lsp::notification::DidChangeConfiguration>()
+            .await
+            .settings,
+        json!({ "rust": { "checkOnSave": true } })
+    );
+}
+
+#[gpui::test]
+async fn test_disabled_language_server(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let set_enabled = |enabled: bool, cx: &mut gpui::TestAppContext| {
+        cx.update(|cx| {
+            cx.update_global(|settings: &mut SettingsStore, cx| {
+                settings.update_user_settings::<ProjectSettings>(cx, |settings| {
+                    settings.lsp.insert(
+                        Arc::from("the-disabled-server"),
+                        LspSettings {
+                            enabled: Some(enabled),
+                            ..Default::default()
+                        },
+                    );
+                });
+            })
+        });
+    };
+    set_enabled(false, cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "" })).await;
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut enabled_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            name: "the-enabled-server",
+            ..Default::default()
+        },
+    );
+    let mut disabled_servers = language_registry.register_fake_lsp_adapter(
+        "Rust",
+        FakeLspAdapter {
+            name: "the-disabled-server",
+            ..Default::default()
+        },
+    );
+
+    let _buffer = project
+        .update(cx, |project, cx| project.open_local_buffer("/dir/a.rs", cx))
+        .await
+        .unwrap();
+    let enabled_server = enabled_servers.next().await.unwrap();
+    assert_eq!(enabled_server.server.name(), "the-enabled-server");
+    cx.executor().run_until_parked();
+    assert!(disabled_servers.try_next().is_err());
+
+    // Enabling the server starts it for the open buffer.
+    set_enabled(true, cx);
+    let disabled_server = disabled_servers.next().await.unwrap();
+    assert_eq!(disabled_server.server.name(), "the-disabled-server");
+}
+
 #[gpui::test]
 async fn test_reporting_fs_changes_to_language_servers(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -4226,6 +4379,313 @@ async fn test_search_multiple_worktrees_with_inclusions(cx: &mut gpui::TestAppCo
     );
 }
 
+#[gpui::test]
+async fn test_invalid_lsp_settings_notification(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    #[allow(dead_code)]
+    #[derive(schemars::JsonSchema)]
+    #[serde(deny_unknown_fields, rename_all = "camelCase")]
+    struct ServerSettings {
+        check_on_save: Option<bool>,
+    }
+    cx.update(|cx| {
+        project_settings::register_lsp_settings_schema(
+            "the-server",
+            schemars::schema_for!(ServerSettings),
+            cx,
+        )
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ "a.rs": "" })).await;
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let notifications = Arc::new(Mutex::new(Vec::new()));
+    cx.update({
+        let notifications = notifications.clone();
+        |cx| {
+            cx.subscribe(&project, move |_, event, _| {
+                if let Event::Notification(message) = event {
+                    notifications.lock().push(message.clone());
+                }
+            })
+            .detach()
+        }
+    });
+
+    let set_server_settings = |settings: serde_json::Value, cx: &mut gpui::TestAppContext| {
+        cx.update(|cx| {
+            cx.update_global(|store: &mut SettingsStore, cx| {
+                store.update_user_settings::<ProjectSettings>(cx, |project_settings| {
+                    project_settings.lsp.insert(
+                        "the-server".into(),
+                        LspSettings {
+                            settings: Some(settings),
+                            ..Default::default()
+                        },
+                    );
+                });
+            })
+        });
+    };
+
+    set_server_settings(json!({ "checkOnSave": true }), cx);
+    cx.executor().run_until_parked();
+    assert_eq!(notifications.lock().as_slice(), &[] as &[String]);
+
+    set_server_settings(json!({ "checkOnsave": true }), cx);
+    cx.executor().run_until_parked();
+    assert_eq!(
+        notifications.lock().as_slice(),
+        &["invalid settings for language server the-server: settings.checkOnsave: unknown key"]
+    );
+
+    // The same problem isn't reported again when other settings change.
+    cx.update(|cx| {
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<ProjectSettings>(cx, |project_settings| {
+                project_settings.git.diff_base = Some("main".into());
+            });
+        })
+    });
+    cx.executor().run_until_parked();
+    assert_eq!(notifications.lock().len(), 1);
+}
+
+#[gpui::test]
+async fn test_diff_base_revision(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ ".git": {}, "a.txt": "working\n" }))
+        .await;
+    fs.set_index_for_repo(
+        Path::new("/dir/.git"),
+        &[(Path::new("a.txt"), "index\n".into())],
+    );
+    fs.set_committed_contents_for_repo(
+        Path::new("/dir/.git"),
+        "main",
+        &[(Path::new("a.txt"), "main\n".into())],
+    );
+
+    let set_diff_base = |diff_base: Option<&str>, cx: &mut gpui::TestAppContext| {
+        cx.update(|cx| {
+            cx.update_global(|settings: &mut SettingsStore, cx| {
+                settings.update_user_settings::<ProjectSettings>(cx, |settings| {
+                    settings.git.diff_base = diff_base.map(ToString::to_string);
+                });
+            })
+        });
+    };
+    set_diff_base(Some("main"), cx);
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/a.txt", cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(buffer.diff_base(), Some("main\n"))
+    });
+
+    // An unknown revision falls back to the index.
+    set_diff_base(Some("missing"), cx);
+    cx.executor().run_until_parked();
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(buffer.diff_base(), Some("index\n"))
+    });
+
+    set_diff_base(Some("main"), cx);
+    cx.executor().run_until_parked();
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(buffer.diff_base(), Some("main\n"))
+    });
+
+    set_diff_base(None, cx);
+    cx.executor().run_until_parked();
+    buffer.read_with(cx, |buffer, _| {
+        assert_eq!(buffer.diff_base(), Some("index\n"))
+    });
+}
+
+#[gpui::test]
+async fn test_git_update_debounce(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ ".git": {}, "a.txt": "one\n" }))
+        .await;
+    fs.set_index_for_repo(
+        Path::new("/dir/.git"),
+        &[(Path::new("a.txt"), "one\n".into())],
+    );
+
+    let set_gutter_debounce = |gutter_debounce: Option<u64>, cx: &mut gpui::TestAppContext| {
+        cx.update(|cx| {
+            cx.update_global(|settings: &mut SettingsStore, cx| {
+                settings.update_user_settings::<ProjectSettings>(cx, |settings| {
+                    settings.git.gutter_debounce = gutter_debounce;
+                });
+            })
+        });
+    };
+    let hunk_count = |buffer: &Model<Buffer>, cx: &mut gpui::TestAppContext| {
+        buffer.read_with(cx, |buffer, _| {
+            buffer
+                .snapshot()
+                .git_diff_hunks_in_row_range(0..u32::MAX)
+                .count()
+        })
+    };
+
+    // A debounce of zero updates the diff without waiting.
+    set_gutter_debounce(Some(0), cx);
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/a.txt", cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    assert_eq!(hunk_count(&buffer, cx), 0);
+
+    buffer.update(cx, |buffer, cx| buffer.edit([(4..4, "two\n")], None, cx));
+    cx.executor().run_until_parked();
+    assert_eq!(hunk_count(&buffer, cx), 1);
+
+    // A non-zero debounce waits for the delay to elapse.
+    set_gutter_debounce(Some(500), cx);
+    buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "zero\n")], None, cx));
+    cx.executor().run_until_parked();
+    assert_eq!(hunk_count(&buffer, cx), 1);
+
+    cx.executor().advance_clock(Duration::from_millis(500));
+    cx.executor().run_until_parked();
+    assert_eq!(hunk_count(&buffer, cx), 2);
+}
+
+#[gpui::test]
+async fn test_blame_cache(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let blame = |summary: &str| Blame {
+        entries: vec![git::blame::BlameEntry {
+            range: 0..1,
+            summary: Some(summary.to_string()),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let summary = |blame: Result<Blame>| blame.unwrap().entries[0].summary.clone().unwrap();
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ ".git": {}, "a.txt": "one\n" }))
+        .await;
+    fs.set_blame_for_repo(
+        Path::new("/dir/.git"),
+        vec![(Path::new("a.txt"), blame("first"))],
+    );
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/a.txt", cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    let blame_buffer = |cx: &mut gpui::TestAppContext| {
+        project.update(cx, |project, cx| project.blame_buffer(&buffer, None, cx))
+    };
+    assert_eq!(summary(blame_buffer(cx).await), "first");
+
+    // Blaming the unchanged file again doesn't ask the repository.
+    fs.with_git_state(Path::new("/dir/.git"), false, |state| state.blames.clear());
+    assert_eq!(summary(blame_buffer(cx).await), "first");
+
+    // Editing the buffer invalidates its cached blame.
+    buffer.update(cx, |buffer, cx| buffer.edit([(0..0, "zero\n")], None, cx));
+    assert!(blame_buffer(cx).await.is_err());
+
+    // So does a change to the repository.
+    fs.set_blame_for_repo(
+        Path::new("/dir/.git"),
+        vec![(Path::new("a.txt"), blame("second"))],
+    );
+    cx.executor().run_until_parked();
+    assert_eq!(summary(blame_buffer(cx).await), "second");
+
+    fs.set_blame_for_repo(
+        Path::new("/dir/.git"),
+        vec![(Path::new("a.txt"), blame("third"))],
+    );
+    cx.executor().run_until_parked();
+    assert_eq!(summary(blame_buffer(cx).await), "third");
+}
+
+#[gpui::test]
+async fn test_blame_line(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let sha = "1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b1b".parse().unwrap();
+    let committed_entry = git::blame::BlameEntry {
+        sha,
+        range: 0..1,
+        author: Some("Ada".to_string()),
+        summary: Some("Add one".to_string()),
+        ..Default::default()
+    };
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree("/dir", json!({ ".git": {}, "a.txt": "one\ntwo\n" }))
+        .await;
+    fs.set_blame_for_repo(
+        Path::new("/dir/.git"),
+        vec![(
+            Path::new("a.txt"),
+            Blame {
+                entries: vec![
+                    committed_entry.clone(),
+                    git::blame::BlameEntry {
+                        range: 1..2,
+                        author: Some("Not Committed Yet".to_string()),
+                        ..Default::default()
+                    },
+                ],
+                messages: HashMap::from_iter([(sha, "Add one\n\nWith details".to_string())]),
+                permalinks: HashMap::default(),
+            },
+        )],
+    );
+
+    let project = Project::test(fs.clone(), ["/dir".as_ref()], cx).await;
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer("/dir/a.txt", cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().run_until_parked();
+    let blame_line = |row: u32, cx: &mut gpui::TestAppContext| {
+        project.update(cx, |project, cx| project.blame_line(&buffer, row, cx))
+    };
+
+    assert_eq!(
+        blame_line(0, cx).await.unwrap(),
+        LineBlame::Committed {
+            entry: committed_entry,
+            message: Some("Add one\n\nWith details".to_string()),
+            permalink: None,
+        }
+    );
+    assert_eq!(blame_line(1, cx).await.unwrap(), LineBlame::NotCommittedYet);
+    assert_eq!(blame_line(2, cx).await.unwrap(), LineBlame::NotCommittedYet);
+}
+
 #[gpui::test]
 async fn test_search_in_gitignored_dirs(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -101,6 +101,25 @@ pub struct Peer {
     epoch: AtomicU32,
     pub connections: RwLock<HashMap<ConnectionId, ConnectionState>>,
     next_connection_id: AtomicU32,
+    keepalive: Mutex<Keepalive>,
+}
+
+/// Controls how a connection is kept alive when no other messages are being sent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Keepalive {
+    /// How long a connection may go without sending anything before it is pinged.
+    pub interval: Duration,
+    /// How long to wait for any message from the other side before disconnecting.
+    pub timeout: Duration,
+}
+
+impl Default for Keepalive {
+    fn default() -> Self {
+        Self {
+            interval: KEEPALIVE_INTERVAL,
+            timeout: RECEIVE_TIMEOUT,
+        }
+    }
 }
 
 #[derive(Clone, Serialize)]
@@ -141,9 +160,19 @@ impl Peer {
             epoch: AtomicU32::new(epoch),
             connections: Default::default(),
             next_connection_id: Default::default(),
+            keepalive: Default::default(),
         })
     }
 
+    /// Sets the keepalive behavior of connections added after this call.
+    pub fn set_keepalive(&self, keepalive: Keepalive) {
+        *self.keepalive.lock() = keepalive;
+    }
+
+    pub fn keepalive(&self) -> Keepalive {
+        *self.keepalive.lock()
+    }
+
     pub fn epoch(&self) -> u32 {
         self.epoch.load(SeqCst)
     }
@@ -188,6 +217,7 @@ impl Peer {
         let mut reader = MessageStream::new(connection.rx);
 
         let this = self.clone();
+        let keepalive = self.keepalive();
         let response_channels = connection_state.response_channels.clone();
         let stream_response_channels = connection_state.stream_response_channels.clone();
 
@@ -209,11 +239,11 @@ impl Peer {
             });
 
             // Send messages on this frequency so the connection isn't closed.
-            let keepalive_timer = create_timer(KEEPALIVE_INTERVAL).fuse();
+            let keepalive_timer = create_timer(keepalive.interval).fuse();
             futures::pin_mut!(keepalive_timer);
 
             // Disconnect if we don't receive messages at least this frequently.
-            let receive_timeout = create_timer(RECEIVE_TIMEOUT).fuse();
+            let receive_timeout = create_timer(keepalive.timeout).fuse();
             futures::pin_mut!(receive_timeout);
 
             loop {
@@ -232,7 +262,7 @@ impl Peer {
                                         tracing::trace!(%connection_id, "outgoing rpc message: done writing");
                                         result.context("failed to write RPC message")?;
                                         tracing::trace!(%connection_id, "keepalive interval: resetting after sending message");
-                                        keepalive_timer.set(create_timer(KEEPALIVE_INTERVAL).fuse());
+                                        keepalive_timer.set(create_timer(keepalive.interval).fuse());
                                     }
                                     _ = create_timer(WRITE_TIMEOUT).fuse() => {
                                         tracing::trace!(%connection_id, "outgoing rpc message: writing timed out");
@@ -252,7 +282,7 @@ impl Peer {
                                     tracing::trace!(%connection_id, "keepalive interval: done pinging");
                                     result.context("failed to send keepalive")?;
                                     tracing::trace!(%connection_id, "keepalive interval: resetting after pinging");
-                                    keepalive_timer.set(create_timer(KEEPALIVE_INTERVAL).fuse());
+                                    keepalive_timer.set(create_timer(keepalive.interval).fuse());
                                 }
                                 _ = create_timer(WRITE_TIMEOUT).fuse() => {
                                     tracing::trace!(%connection_id, "keepalive interval: pinging timed out");
@@ -264,7 +294,7 @@ impl Peer {
                             let incoming = incoming.context("error reading rpc message from socket")?;
                             tracing::trace!(%connection_id, "incoming rpc message: received");
                             tracing::trace!(%connection_id, "receive timeout: resetting");
-                            receive_timeout.set(create_timer(RECEIVE_TIMEOUT).fuse());
+                            receive_timeout.set(create_timer(keepalive.timeout).fuse());
                             if let (proto::Message::Envelope(incoming), received_at) = incoming {
                                 tracing::trace!(%connection_id, "incoming rpc message: processing");
                                 futures::select_biased! {
@@ -315,6 +315,7 @@ messages!(
     (JoinRemoteProject, Foreground),
     (RejoinRemoteProjects, Foreground),
     (RejoinRemoteProjectsResponse, Foreground),
+    (UpdateDevServerProjectStatus, Foreground),
     (MultiLspQuery, Background),
     (MultiLspQueryResponse, Background),
 );
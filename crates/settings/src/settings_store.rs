@@ -97,6 +97,8 @@ pub struct SettingsSources<'a, T> {
     pub user: Option<&'a T>,
     /// The user settings for the current release channel.
     pub release_channel: Option<&'a T>,
+    /// Settings provided by the server this process serves, such as a dev server.
+    pub server: Option<&'a T>,
     /// The project settings, ordered from least specific to most specific.
     pub project: &'a [&'a T],
 }
@@ -113,6 +115,7 @@ impl<'a, T: Serialize> SettingsSources<'a, T> {
             .into_iter()
             .chain(self.user)
             .chain(self.release_channel)
+            .chain(self.server)
             .chain(self.project.iter().copied())
     }
 
@@ -147,7 +150,9 @@ pub struct SettingsStore {
     raw_default_settings: serde_json::Value,
     raw_user_settings: serde_json::Value,
     raw_extension_settings: serde_json::Value,
+    raw_server_settings: serde_json::Value,
     raw_local_settings: BTreeMap<(usize, Arc<Path>), serde_json::Value>,
+    raw_local_default_settings: BTreeMap<usize, serde_json::Value>,
     tab_size_callback: Option<(
         TypeId,
         Box<dyn Fn(&dyn Any) -> Option<usize> + Send + Sync + 'static>,
@@ -163,7 +168,9 @@ impl Default for SettingsStore {
             raw_default_settings: serde_json::json!({}),
             raw_user_settings: serde_json::json!({}),
             raw_extension_settings: serde_json::json!({}),
+            raw_server_settings: serde_json::json!({}),
             raw_local_settings: Default::default(),
+            raw_local_default_settings: Default::default(),
             tab_size_callback: Default::default(),
         }
     }
@@ -233,6 +240,10 @@ impl SettingsStore {
                 .deserialize_setting(&self.raw_extension_settings)
                 .log_err();
 
+            let server_value = setting_value
+                .deserialize_setting(&self.raw_server_settings)
+                .log_err();
+
             if let Some(setting) = setting_value
                 .load_setting(
                     SettingsSources {
@@ -240,6 +251,7 @@ impl SettingsStore {
                         release_channel: release_channel_value.as_ref(),
                         extensions: extension_value.as_ref(),
                         user: user_value.as_ref(),
+                        server: server_value.as_ref(),
                         project: &[],
                     },
                     cx,
@@ -442,6 +454,19 @@ impl SettingsStore {
         Ok(())
     }
 
+    /// Checks that every registered setting can be read from a local settings
+    /// file, without applying it. [`SettingsStore::set_local_settings`] applies
+    /// the settings that are valid and ignores the rest.
+    pub fn validate_local_settings(&self, settings_content: &str) -> Result<()> {
+        let settings: serde_json::Value = parse_json_with_comments(settings_content)?;
+        for setting_value in self.setting_values.values() {
+            setting_value
+                .deserialize_setting(&settings)
+                .with_context(|| format!("invalid {}", setting_value.setting_type_name()))?;
+        }
+        Ok(())
+    }
+
     pub fn set_extension_settings<T: Serialize>(
         &mut self,
         content: T,
@@ -457,9 +482,50 @@ impl SettingsStore {
         }
     }
 
+    /// Set the settings provided by the server this process serves. They take
+    /// precedence over the user's settings, but not over local settings files.
+    pub fn set_server_settings<T: Serialize>(
+        &mut self,
+        content: T,
+        cx: &mut AppContext,
+    ) -> Result<()> {
+        let settings: serde_json::Value = serde_json::to_value(content)?;
+        if settings.is_object() {
+            self.raw_server_settings = settings;
+            self.recompute_values(None, cx)?;
+            Ok(())
+        } else {
+            Err(anyhow!("settings must be an object"))
+        }
+    }
+
+    /// Add or remove settings that apply to everything in a worktree via a JSON
+    /// string. Local settings files in the worktree take precedence over them.
+    pub fn set_local_default_settings(
+        &mut self,
+        root_id: usize,
+        settings_content: Option<&str>,
+        cx: &mut AppContext,
+    ) -> Result<()> {
+        if let Some(content) = settings_content {
+            let settings: serde_json::Value = parse_json_with_comments(content)?;
+            for setting_value in self.setting_values.values() {
+                setting_value
+                    .deserialize_setting(&settings)
+                    .with_context(|| format!("invalid {}", setting_value.setting_type_name()))?;
+            }
+            self.raw_local_default_settings.insert(root_id, settings);
+        } else {
+            self.raw_local_default_settings.remove(&root_id);
+        }
+        self.recompute_values(Some((root_id, "".as_ref())), cx)?;
+        Ok(())
+    }
+
     /// Add or remove a set of local settings via a JSON string.
     pub fn clear_local_settings(&mut self, root_id: usize, cx: &mut AppContext) -> Result<()> {
         self.raw_local_settings.retain(|k, _| k.0 != root_id);
+        self.raw_local_default_settings.remove(&root_id);
         self.recompute_values(Some((root_id, "".as_ref())), cx)?;
         Ok(())
     }
@@ -582,6 +648,22 @@ impl SettingsStore {
         changed_local_path: Option<(usize, &Path)>,
         cx: &mut AppContext,
     ) -> Result<()> {
+        // A worktree's default settings sit beneath any settings file at its root,
+        // which the stable sort keeps after them.
+        let mut all_local_settings = self
+            .raw_local_default_settings
+            .iter()
+            .map(|(root_id, settings)| (*root_id, Arc::<Path>::from(Path::new("")), settings))
+            .chain(
+                self.raw_local_settings
+                    .iter()
+                    .map(|((root_id, path), settings)| (*root_id, path.clone(), settings)),
+            )
+            .collect::<Vec<_>>();
+        all_local_settings.sort_by(|(a_root_id, a_path, _), (b_root_id, b_path, _)| {
+            (a_root_id, a_path).cmp(&(b_root_id, b_path))
+        });
+
         // Reload the global and local values for every setting.
         let mut project_settings_stack = Vec::<DeserializedSetting>::new();
         let mut paths_stack = Vec::<Option<(usize, &Path)>>::new();
@@ -596,6 +678,10 @@ impl SettingsStore {
                 .deserialize_setting(&self.raw_user_settings)
                 .log_err();
 
+            let server_settings = setting_value
+                .deserialize_setting(&self.raw_server_settings)
+                .log_err();
+
             let mut release_channel_settings = None;
             if let Some(release_settings) = &self
                 .raw_user_settings
@@ -620,6 +706,7 @@ impl SettingsStore {
                             extensions: extension_settings.as_ref(),
                             user: user_settings.as_ref(),
                             release_channel: release_channel_settings.as_ref(),
+                            server: server_settings.as_ref(),
                             project: &[],
                         },
                         cx,
@@ -631,7 +718,7 @@ impl SettingsStore {
             }
 
             // Reload the local values for the setting.
-            for ((root_id, path), local_settings) in &self.raw_local_settings {
+            for (root_id, path, local_settings) in &all_local_settings {
                 // Build a stack of all of the local values for that setting.
                 while let Some(prev_entry) = paths_stack.last() {
                     if let Some((prev_root_id, prev_path)) = prev_entry {
@@ -665,6 +752,7 @@ impl SettingsStore {
                                 extensions: extension_settings.as_ref(),
                                 user: user_settings.as_ref(),
                                 release_channel: release_channel_settings.as_ref(),
+                                server: server_settings.as_ref(),
                                 project: &project_settings_stack.iter().collect::<Vec<_>>(),
                             },
                             cx,
@@ -693,6 +781,7 @@ impl Debug for SettingsStore {
             )
             .field("default_settings", &self.raw_default_settings)
             .field("user_settings", &self.raw_user_settings)
+            .field("server_settings", &self.raw_server_settings)
             .field("local_settings", &self.raw_local_settings)
             .finish_non_exhaustive()
     }
@@ -724,6 +813,9 @@ impl<T: Settings> AnySettingValue for SettingValue<T> {
                 release_channel: values
                     .release_channel
                     .map(|value| value.0.downcast_ref::<T::FileContent>().unwrap()),
+                server: values
+                    .server
+                    .map(|value| value.0.downcast_ref::<T::FileContent>().unwrap()),
                 project: values
                     .project
                     .iter()
@@ -1143,6 +1235,101 @@ mod tests {
         );
     }
 
+    #[gpui::test]
+    fn test_validate_local_settings(cx: &mut AppContext) {
+        let mut store = SettingsStore::default();
+        store.register_setting::<UserSettings>(cx);
+        store.register_setting::<TurboSetting>(cx);
+        store
+            .set_default_settings(
+                r#"{
+                    "turbo": false,
+                    "user": {
+                        "name": "John Doe",
+                        "age": 30,
+                        "staff": false
+                    }
+                }"#,
+                cx,
+            )
+            .unwrap();
+
+        let location = SettingsLocation {
+            worktree_id: 1,
+            path: Path::new("/root1/file"),
+        };
+        let content = r#"{ "turbo": true, "user": { "age": "old" } }"#;
+        assert!(store.validate_local_settings(content).is_err());
+        assert!(store.validate_local_settings("{ turbo").is_err());
+        assert!(store
+            .validate_local_settings(r#"{ "turbo": true, "user": { "age": 31 } }"#)
+            .is_ok());
+
+        // Applying the file anyway uses the settings that are valid.
+        store
+            .set_local_settings(1, Path::new("/root1").into(), Some(content), cx)
+            .unwrap();
+        assert_eq!(
+            store.get::<TurboSetting>(Some(location)),
+            &TurboSetting(true)
+        );
+        assert_eq!(store.get::<UserSettings>(Some(location)).age, 30);
+    }
+
+    #[gpui::test]
+    fn test_local_default_settings(cx: &mut AppContext) {
+        let mut store = SettingsStore::default();
+        store.register_setting::<UserSettings>(cx);
+        store
+            .set_default_settings(
+                r#"{
+                    "user": {
+                        "name": "John Doe",
+                        "age": 30,
+                        "staff": false
+                    }
+                }"#,
+                cx,
+            )
+            .unwrap();
+        store
+            .set_local_default_settings(1, Some(r#"{ "user": { "age": 40, "staff": true } }"#), cx)
+            .unwrap();
+        store
+            .set_local_settings(
+                1,
+                Path::new("subdir").into(),
+                Some(r#"{ "user": { "age": 41 } }"#),
+                cx,
+            )
+            .unwrap();
+
+        let user = |store: &SettingsStore, root_id, path| {
+            let user = store.get::<UserSettings>(Some(SettingsLocation {
+                worktree_id: root_id,
+                path: Path::new(path),
+            }));
+            (user.age, user.staff)
+        };
+        assert_eq!(user(&store, 1, "file"), (40, true));
+        // Settings files in the worktree take precedence over its defaults.
+        assert_eq!(user(&store, 1, "subdir/file"), (41, true));
+        store
+            .set_local_settings(
+                1,
+                Path::new("").into(),
+                Some(r#"{ "user": { "age": 39 } }"#),
+                cx,
+            )
+            .unwrap();
+        assert_eq!(user(&store, 1, "file"), (39, true));
+        // Other worktrees are unaffected.
+        assert_eq!(user(&store, 2, "file"), (30, false));
+
+        store.set_local_default_settings(1, None, cx).unwrap();
+        assert_eq!(user(&store, 1, "file"), (39, false));
+    }
+
     #[gpui::test]
     fn test_setting_store_assign_json_before_register(cx: &mut AppContext) {
         let mut store = SettingsStore::default();
@@ -279,6 +279,11 @@ impl LocalRepositoryEntry {
     pub fn repo(&self) -> &Arc<Mutex<dyn GitRepository>> {
         &self.repo_ptr
     }
+
+    /// The id of the last scan in which the repository's git directory changed.
+    pub fn git_dir_scan_id(&self) -> usize {
+        self.git_dir_scan_id
+    }
 }
 
 impl Deref for LocalSnapshot {
@@ -748,9 +753,12 @@ impl LocalWorktree {
         path.starts_with(&self.abs_path)
     }
 
+    /// Loads a buffer for the file at `path`. Its diff base is the file's
+    /// contents at `diff_base_revision` if one is given, or in the index.
     pub fn load_buffer(
         &mut self,
         path: &Path,
+        diff_base_revision: Option<String>,
         cx: &mut ModelContext<Worktree>,
     ) -> Task<Result<Model<Buffer>>> {
         let path = Arc::from(path);
@@ -758,7 +766,9 @@ impl LocalWorktree {
         let buffer_id = BufferId::from(reservation.entity_id().as_non_zero_u64());
         cx.spawn(move |this, mut cx| async move {
             let (file, contents, diff_base) = this
-                .update(&mut cx, |t, cx| t.as_local().unwrap().load(&path, cx))?
+                .update(&mut cx, |t, cx| {
+                    t.as_local().unwrap().load(&path, diff_base_revision, cx)
+                })?
                 .await?;
             let text_buffer = cx
                 .background_executor()
@@ -1064,6 +1074,7 @@ impl LocalWorktree {
     fn load(
         &self,
         path: &Path,
+        diff_base_revision: Option<String>,
         cx: &mut ModelContext<Worktree>,
     ) -> Task<Result<(File, String, Option<String>)>> {
         let path = Arc::from(path);
@@ -1096,7 +1107,9 @@ impl LocalWorktree {
                                 if abs_path_metadata.is_dir || abs_path_metadata.is_symlink {
                                     None
                                 } else {
-                                    git_repo.lock().load_index_text(&repo_path)
+                                    git_repo
+                                        .lock()
+                                        .load_diff_base(&repo_path, diff_base_revision.as_deref())
                                 }
                             }
                         }));
@@ -34,6 +34,32 @@ impl Settings for WorktreeSettings {
         sources: SettingsSources<Self::FileContent>,
         _: &mut AppContext,
     ) -> anyhow::Result<Self> {
-        sources.json_merge()
+        // Globs provided by the server are added to the configured ones rather
+        // than replacing them.
+        let mut settings: Self = SettingsSources {
+            server: None,
+            ..sources
+        }
+        .json_merge()?;
+        if let Some(server) = sources.server {
+            for (globs, server_globs) in [
+                (
+                    &mut settings.file_scan_exclusions,
+                    &server.file_scan_exclusions,
+                ),
+                (&mut settings.private_files, &server.private_files),
+            ] {
+                let Some(server_globs) = server_globs else {
+                    continue;
+                };
+                let globs = globs.get_or_insert_with(Vec::new);
+                for glob in server_globs {
+                    if !globs.contains(glob) {
+                        globs.push(glob.clone());
+                    }
+                }
+            }
+        }
+        Ok(settings)
     }
 }
@@ -578,7 +578,7 @@ async fn test_open_gitignored_files(cx: &mut TestAppContext) {
         .update(cx, |tree, cx| {
             tree.as_local_mut()
                 .unwrap()
-                .load_buffer("one/node_modules/b/b1.js".as_ref(), cx)
+                .load_buffer("one/node_modules/b/b1.js".as_ref(), None, cx)
         })
         .await
         .unwrap();
@@ -620,7 +620,7 @@ async fn test_open_gitignored_files(cx: &mut TestAppContext) {
         .update(cx, |tree, cx| {
             tree.as_local_mut()
                 .unwrap()
-                .load_buffer("one/node_modules/a/a2.js".as_ref(), cx)
+                .load_buffer("one/node_modules/a/a2.js".as_ref(), None, cx)
         })
         .await
         .unwrap();
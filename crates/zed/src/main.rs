@@ -44,7 +44,7 @@ use std::{
     fs::OpenOptions,
     io::{IsTerminal, Write},
     panic,
-    path::Path,
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicU32, Ordering},
         Arc,
@@ -86,12 +86,16 @@ fn fail_to_launch(e: anyhow::Error) {
     })
 }
 
-fn init_headless(dev_server_token: DevServerToken) {
+fn init_headless(dev_server_token: DevServerToken, token_path: Option<PathBuf>) {
     if let Err(e) = init_paths() {
         log::error!("Failed to launch: {}", e);
         return;
     }
-    init_logger();
+    if std::env::var("ZED_DEV_SERVER_LOG_FORMAT").as_deref() == Ok("json") {
+        headless::init_json_logger(LevelFilter::Info).expect("could not initialize logger");
+    } else {
+        init_logger();
+    }
 
     App::new().run(|cx| {
         release_channel::init(env!("CARGO_PKG_VERSION"), cx);
@@ -112,7 +116,7 @@ fn init_headless(dev_server_token: DevServerToken) {
             &client::ClientSettings::get_global(cx).server_url,
         ));
 
-        let client = client::Client::new(clock, http.clone(), cx);
+        let client = client::Client::new(clock.clone(), http.clone(), cx);
         let client = client.clone();
         client.set_dev_server_token(dev_server_token);
 
@@ -145,6 +149,34 @@ fn init_headless(dev_server_token: DevServerToken) {
                 user_store: user_store.clone(),
                 fs: fs.clone(),
                 node_runtime: node_runtime.clone(),
+                clock,
+                access_log: None,
+                project_factory: None,
+                options: headless::DevServerOptions {
+                    status_file_path: std::env::var_os("ZED_DEV_SERVER_STATUS_FILE")
+                        .map(PathBuf::from),
+                    project_log_dir: std::env::var_os("ZED_DEV_SERVER_PROJECT_LOG_DIR")
+                        .map(PathBuf::from),
+                    metrics_address: std::env::var("ZED_DEV_SERVER_METRICS_ADDRESS")
+                        .ok()
+                        .and_then(|address| address.parse().log_err()),
+                    display_name: std::env::var("ZED_DEV_SERVER_DISPLAY_NAME").ok(),
+                    health_address: std::env::var("ZED_DEV_SERVER_HEALTH_ADDRESS")
+                        .ok()
+                        .and_then(|address| address.parse().log_err()),
+                    file_scan_exclusions: std::env::var("ZED_DEV_SERVER_FILE_SCAN_EXCLUSIONS")
+                        .ok()
+                        .map(|exclusions| {
+                            exclusions
+                                .split(',')
+                                .map(|glob| glob.trim().to_string())
+                                .filter(|glob| !glob.is_empty())
+                                .collect()
+                        }),
+                    token_path,
+                    dry_run: std::env::var_os("ZED_DEV_SERVER_DRY_RUN").is_some(),
+                    ..Default::default()
+                },
             },
             cx,
         );
@@ -434,7 +466,16 @@ fn main() {
     let mut args = Args::parse();
     if let Some(dev_server_token) = args.dev_server_token.take() {
         let dev_server_token = DevServerToken(dev_server_token);
-        init_headless(dev_server_token)
+        init_headless(dev_server_token, None)
+    } else if let Some(token_path) = std::env::var_os("ZED_DEV_SERVER_TOKEN_PATH") {
+        let token_path = PathBuf::from(token_path);
+        match headless::read_dev_server_token(&token_path) {
+            Ok(dev_server_token) => init_headless(dev_server_token, Some(token_path)),
+            Err(error) => {
+                eprintln!("{:#}", error);
+                std::process::exit(1);
+            }
+        }
     } else {
         init_ui()
     }